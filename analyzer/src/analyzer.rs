@@ -1,15 +1,20 @@
 #![allow(unused_variables)]
 
 use crate::{
+    suggest_annotation_fix,
     symbol_table::{SymbolAttributes, SymbolEntry, SymbolKind, SymbolTable},
-    SemanticError, SemanticResult,
+    SemanticError, SemanticErrorKind, SemanticResult, SemanticWarning,
 };
 use parser::ast::{
-    ArrayLiteral, BlockExpression, DataType, DataTypeKind, DeclareStatement, Expression,
-    Identifier, InfixExpression, InfixOperator, LetStatement, Literal, Position, PrefixExpression,
-    PrefixOperator, Program, ReturnExpressionStatement, ReturnStatement, Statement,
-    StructStatement, TypeStatement,
+    ArrayLiteral, BlockExpression, CallExpression, CastExpression, DataType, DataTypeKind,
+    DeclareStatement, Expression, FieldKey, FunctionLiteral, FunctionType, Identifier,
+    IfExpression, IndexExpression, InfixExpression, InfixOperator, LetStatement, Literal,
+    MatchExpression, MatchPattern, ParameterKind, Pattern, Position, PrefixExpression,
+    PrefixOperator, Program, ReturnExpressionStatement, ReturnStatement, SequenceExpression,
+    Statement, StructLiteral, StructStatement, ThrowStatement, TryExpression, TypeStatement,
+    WhileStatement,
 };
+use std::collections::HashMap;
 
 /// `Early return`
 ///
@@ -42,7 +47,7 @@ use parser::ast::{
 ///     x + 2 // 9
 /// };
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum AnalyzerReturnKind {
     EarlyReturn(DataTypeKind),
     Expression(DataTypeKind),
@@ -68,6 +73,20 @@ pub struct Analyzer {
     pub program: Program,
     pub symbol_table: SymbolTable,
     pub return_type: AnalyzerReturnKind,
+    pub warnings: Vec<SemanticWarning>,
+    /// The declared return type of the enclosing function, which every `return` is checked against.
+    pub function_return_type: Option<DataTypeKind>,
+    /// Rejects adding a number to a string, which is otherwise converted to a string.
+    pub strict: bool,
+    /// Infers the return type of a function without one from its returns, widening returns of different types into
+    /// a union. Off by default, so such a function returns `void`.
+    pub unions: bool,
+    /// The types of the `return`s analyzed so far, including those in branches and loops, which the return type of
+    /// a function without one is inferred from.
+    pub returns: Vec<DataTypeKind>,
+    /// The labels of the enclosing loops (`None` for a loop without one), innermost last.
+    /// A function body starts without any, since `break` can't leave a function.
+    pub loop_labels: Vec<Option<String>>,
 }
 
 impl Analyzer {
@@ -77,6 +96,12 @@ impl Analyzer {
             program,
             symbol_table: SymbolTable::new(None),
             return_type: AnalyzerReturnKind::Unknown,
+            warnings: Vec::new(),
+            function_return_type: None,
+            strict: false,
+            unions: false,
+            returns: Vec::new(),
+            loop_labels: Vec::new(),
         }
     }
 
@@ -86,6 +111,12 @@ impl Analyzer {
             program,
             symbol_table,
             return_type: AnalyzerReturnKind::Unknown,
+            warnings: Vec::new(),
+            function_return_type: None,
+            strict: false,
+            unions: false,
+            returns: Vec::new(),
+            loop_labels: Vec::new(),
         }
     }
 
@@ -107,7 +138,11 @@ impl Analyzer {
 
                 if let AnalyzerReturnKind::EarlyReturn(return_type) = self.return_type.clone() {
                     if let AnalyzerReturnKind::EarlyReturn(ttype) = ttype {
-                        if return_type != ttype {
+                        // Without a declared return type, union types take the returns of every type.
+                        if self.unions && self.function_return_type.is_none() {
+                            self.return_type =
+                                AnalyzerReturnKind::EarlyReturn(Self::union(&return_type, &ttype));
+                        } else if return_type != ttype {
                             return Err(SemanticError::type_mismatch(return_type, ttype, position));
                         }
                     }
@@ -118,19 +153,122 @@ impl Analyzer {
         Ok(())
     }
 
+    /// `never` is assignable to every type, a type to its optionals and unions, and arrays by their elements.
+    fn is_assignable(found: &DataTypeKind, expected: &DataTypeKind) -> bool {
+        match (found, expected) {
+            (DataTypeKind::Union(found), _) => found
+                .iter()
+                .all(|found| Self::is_assignable(&found.kind, expected)),
+            (_, DataTypeKind::Union(expected)) => expected
+                .iter()
+                .any(|expected| Self::is_assignable(found, &expected.kind)),
+            (_, DataTypeKind::Optional(expected))
+                if found != &DataTypeKind::Optional(expected.clone()) =>
+            {
+                Self::is_assignable(found, &expected.kind)
+            }
+            (DataTypeKind::Array(found), DataTypeKind::Array(expected)) => {
+                Self::is_assignable(&found.kind, &expected.kind)
+            }
+            _ => *found == DataTypeKind::Never || found == expected,
+        }
+    }
+
+    /// Returns the common type of two branches, ignoring a branch that never produces a value.
+    fn join(left: &DataTypeKind, right: &DataTypeKind) -> Option<DataTypeKind> {
+        if Self::is_assignable(left, right) {
+            Some(right.clone())
+        } else if Self::is_assignable(right, left) {
+            Some(left.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the type of a value of either type: their common type if there is one, otherwise their union.
+    fn union(left: &DataTypeKind, right: &DataTypeKind) -> DataTypeKind {
+        if let Some(kind) = Self::join(left, right) {
+            return kind;
+        }
+
+        let mut data_types: Vec<DataType> = Vec::new();
+
+        for kind in [left, right] {
+            let kinds = match kind {
+                DataTypeKind::Union(data_types) => data_types.clone(),
+                kind => vec![DataType::new(kind.clone(), Position::default())],
+            };
+
+            for data_type in kinds {
+                if !data_types.contains(&data_type) {
+                    data_types.push(data_type);
+                }
+            }
+        }
+
+        DataTypeKind::Union(data_types)
+    }
+
     /// Analyzes the program and returns a `SemanticResult` with return type of the program.
     pub fn analyze(&mut self) -> SemanticResult<AnalyzerReturnKind> {
+        self.analyze_statements(false)
+            .map_err(|mut errors| errors.remove(0))
+    }
+
+    /// Analyzes the program like `analyze`, but goes on after a statement fails so every error is reported.
+    pub fn analyze_program(&mut self) -> Result<AnalyzerReturnKind, Vec<SemanticError>> {
+        self.analyze_statements(true)
+    }
+
+    /// Analyzes the statements until one ends the program, or fails if `recover` is `false`.
+    fn analyze_statements(
+        &mut self,
+        recover: bool,
+    ) -> Result<AnalyzerReturnKind, Vec<SemanticError>> {
+        let mut errors = Vec::new();
+
         for statement in self.program.clone() {
-            self.analyze_statement(&statement)?;
+            if let Err(error) = self.analyze_statement(&statement) {
+                errors.push(error);
 
-            match self.return_type {
-                AnalyzerReturnKind::EarlyReturn(_) => return Ok(self.return_type.clone()),
-                AnalyzerReturnKind::Expression(_) => break,
-                _ => {}
+                if !recover {
+                    break;
+                }
+
+                if let Statement::LetStatement(statement) = &statement {
+                    self.bind_failed_let(statement);
+                }
+
+                continue;
+            }
+
+            if self.return_type != AnalyzerReturnKind::Unknown {
+                break;
             }
         }
 
-        Ok(self.return_type.clone())
+        match errors.is_empty() {
+            true => Ok(self.return_type.clone()),
+            false => Err(errors),
+        }
+    }
+
+    /// Binds the name of a failed `let`, so that its uses are not reported as well.
+    fn bind_failed_let(&mut self, statement: &LetStatement) {
+        let data_type = statement
+            .data_type
+            .as_ref()
+            .and_then(|data_type| self.analyze_data_type(data_type).ok())
+            .unwrap_or_else(|| DataType::new(DataTypeKind::Never, statement.position));
+
+        self.symbol_table.insert(
+            &statement.identifier.value,
+            SymbolEntry::new(
+                data_type,
+                SymbolAttributes::default().mutable(statement.is_mutable),
+                SymbolKind::Variable,
+            ),
+        );
     }
 
     fn analyze_statement(&mut self, statement: &Statement) -> SemanticResult<()> {
@@ -140,31 +278,99 @@ impl Analyzer {
             Statement::ReturnExpressionStatement(statement) => {
                 self.analyze_return_expression_statement(statement)
             }
+            Statement::ThrowStatement(statement) => self.analyze_throw_statement(statement),
+            Statement::WhileStatement(statement) => self.analyze_while_statement(statement),
+            Statement::BreakStatement(statement) => {
+                self.analyze_loop_label("break", &statement.label, statement.position)
+            }
+            Statement::ContinueStatement(statement) => {
+                self.analyze_loop_label("continue", &statement.label, statement.position)
+            }
             Statement::TypeStatement(statement) => self.analyze_type_statement(statement),
             Statement::DeclareStatement(statement) => self.analyze_declare_statement(statement),
             Statement::StructStatement(statement) => self.analyze_struct_statement(statement),
             Statement::ExpressionStatement(statement) => {
-                self.analyze_expression(&statement.expression)?;
+                if let Expression::Literal(_) = statement.expression {
+                    self.warnings
+                        .push(SemanticWarning::unused_expression(statement.position));
+                }
+
+                // A statement that never completes makes the rest of the block unreachable.
+                if self.analyze_expression(&statement.expression)?.kind == DataTypeKind::Never {
+                    self.set_return_type(
+                        AnalyzerReturnKind::Expression(DataTypeKind::Never),
+                        statement.position,
+                    )?;
+                }
+
                 Ok(())
             }
         }
     }
 
     fn analyze_let_statement(&mut self, statement: &LetStatement) -> SemanticResult<()> {
+        // A function can call itself, so its name is bound to its signature before the body is analyzed.
+        // The binding counts as declared, so that the one below replaces it.
+        if let Expression::Literal(Literal::FunctionLiteral(function)) = &statement.value {
+            let signature = self.function_signature(function)?;
+
+            self.symbol_table
+                .insert(
+                    &statement.identifier.value,
+                    SymbolEntry::new(
+                        signature,
+                        SymbolAttributes::default().declared(true),
+                        SymbolKind::Variable,
+                    ),
+                )
+                .ok_or_else(|| {
+                    SemanticError::identifier_already_defined(
+                        statement.identifier.value.clone(),
+                        statement.position,
+                    )
+                })?;
+        }
+
         let data_type = match statement.data_type.clone() {
             Some(data_type) => {
                 let type_annotation = self.analyze_data_type(&data_type)?;
+
+                if let (
+                    DataTypeKind::FixedArray(_, size),
+                    Expression::Literal(Literal::ArrayLiteral(array)),
+                ) = (&data_type.kind, &statement.value)
+                {
+                    let size = self.array_size(size, data_type.position)?;
+
+                    if array.elements.len() != size {
+                        return Err(SemanticError::new(
+                            SemanticErrorKind::ArrayLengthMismatch(
+                                size.to_string(),
+                                array.elements.len().to_string(),
+                            ),
+                            statement.position,
+                        ));
+                    }
+                }
+
                 let expression_type = self.analyze_expression_with_provided_type(
                     &statement.value,
                     type_annotation.clone().kind,
                 )?;
 
-                if expression_type != type_annotation {
-                    return Err(SemanticError::type_mismatch(
-                        expression_type,
-                        type_annotation,
+                if !Self::is_assignable(&expression_type.kind, &type_annotation.kind) {
+                    let error = SemanticError::type_mismatch_between(
+                        &expression_type.kind,
+                        &type_annotation.kind,
                         statement.position,
-                    ));
+                    );
+
+                    return Err(
+                        match suggest_annotation_fix(&expression_type.kind, &type_annotation.kind) {
+                            Some(suggestion) => error.with_suggestion(suggestion),
+                            None => error,
+                        },
+                    );
                 }
 
                 type_annotation
@@ -172,12 +378,35 @@ impl Analyzer {
             None => self.analyze_expression(&statement.value)?,
         };
 
+        if data_type.kind == DataTypeKind::Void {
+            return Err(SemanticError::cannot_bind_void(
+                statement.identifier.value.clone(),
+                statement.position,
+            ));
+        }
+
+        if statement.identifier.value == "_" {
+            self.warnings
+                .push(SemanticWarning::discarded_binding(statement.position));
+        }
+
+        let const_function = match &statement.value {
+            Expression::Literal(Literal::FunctionLiteral(function))
+                if function.is_const && !statement.is_mutable =>
+            {
+                Some(function.as_ref().clone())
+            }
+            _ => None,
+        };
+
         self.symbol_table
             .insert(
                 &statement.identifier.value,
                 SymbolEntry::new(
                     data_type,
-                    SymbolAttributes::default().mutable(statement.is_mutable),
+                    SymbolAttributes::default()
+                        .mutable(statement.is_mutable)
+                        .const_function(const_function),
                     SymbolKind::Variable,
                 ),
             )
@@ -192,11 +421,26 @@ impl Analyzer {
     }
 
     fn analyze_return_statement(&mut self, statement: &ReturnStatement) -> SemanticResult<()> {
-        let expression_type = self.analyze_expression(&statement.value)?;
-        self.set_return_type(
-            AnalyzerReturnKind::EarlyReturn(expression_type.kind),
-            statement.position,
-        )?;
+        let value = match &statement.value {
+            Some(value) => {
+                self.typeof_expression_with_provided_type(value, self.function_return_type.clone())?
+            }
+            None => DataType::new(DataTypeKind::Void, statement.position),
+        };
+
+        if let Some(return_type) = &self.function_return_type {
+            if !Self::is_assignable(&value.kind, return_type) {
+                return Err(SemanticError::type_mismatch_between(
+                    &value.kind,
+                    return_type,
+                    value.position,
+                ));
+            }
+        }
+
+        let kind = value.kind;
+        self.returns.push(kind.clone());
+        self.set_return_type(AnalyzerReturnKind::EarlyReturn(kind), statement.position)?;
 
         Ok(())
     }
@@ -214,6 +458,62 @@ impl Analyzer {
         Ok(())
     }
 
+    /// The condition must be a boolean. The value of the body is discarded.
+    fn analyze_while_statement(&mut self, statement: &WhileStatement) -> SemanticResult<()> {
+        let condition = self.typeof_expression(&statement.condition)?;
+
+        if condition.kind != DataTypeKind::Boolean {
+            return Err(SemanticError::type_mismatch(
+                condition.kind,
+                DataTypeKind::Boolean,
+                condition.position,
+            ));
+        }
+
+        self.loop_labels
+            .push(statement.label.as_ref().map(|label| label.value.clone()));
+        let body = self.typeof_conditional_block(&statement.body);
+        self.loop_labels.pop();
+
+        body.map(|_| ())
+    }
+
+    /// `break` and `continue` must be inside a loop, and their label must be the label of an enclosing loop.
+    fn analyze_loop_label(
+        &mut self,
+        keyword: &str,
+        label: &Option<Identifier>,
+        position: Position,
+    ) -> SemanticResult<()> {
+        match label {
+            Some(label) if !self.loop_labels.contains(&Some(label.value.clone())) => Err(
+                SemanticError::label_not_defined(&label.value, label.position),
+            ),
+            None if self.loop_labels.is_empty() => {
+                Err(SemanticError::not_in_loop(keyword, position))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Only strings can be thrown, since `catch` binds the error as a string.
+    fn analyze_throw_statement(&mut self, statement: &ThrowStatement) -> SemanticResult<()> {
+        let value = self.analyze_expression(&statement.value)?;
+
+        if value.kind != DataTypeKind::String {
+            return Err(SemanticError::type_mismatch(
+                value.kind,
+                DataTypeKind::String,
+                value.position,
+            ));
+        }
+
+        self.set_return_type(
+            AnalyzerReturnKind::Expression(DataTypeKind::Never),
+            statement.position,
+        )
+    }
+
     fn analyze_type_statement(&mut self, statement: &TypeStatement) -> SemanticResult<()> {
         let ttype = self.analyze_data_type(&statement.data_type)?;
 
@@ -238,7 +538,11 @@ impl Analyzer {
         self.symbol_table
             .insert(
                 &statement.identifier.value,
-                SymbolEntry::new(ttype, SymbolAttributes::default(), SymbolKind::Variable),
+                SymbolEntry::new(
+                    ttype,
+                    SymbolAttributes::default().declared(true),
+                    SymbolKind::Variable,
+                ),
             )
             .ok_or_else(|| {
                 SemanticError::identifier_already_defined(
@@ -250,8 +554,40 @@ impl Analyzer {
         Ok(())
     }
 
+    /// The struct is defined before its fields are analyzed, so the fields can refer to it.
     fn analyze_struct_statement(&mut self, statement: &StructStatement) -> SemanticResult<()> {
-        todo!()
+        self.symbol_table
+            .insert(
+                &statement.identifier.value,
+                SymbolEntry::new(
+                    DataType::new(
+                        DataTypeKind::Custom(statement.identifier.value.clone()),
+                        statement.position,
+                    ),
+                    SymbolAttributes::default(),
+                    SymbolKind::Struct(Vec::new()),
+                ),
+            )
+            .ok_or_else(|| {
+                SemanticError::type_alias_already_defined(
+                    statement.identifier.value.clone(),
+                    statement.position,
+                )
+            })?;
+
+        let fields = statement
+            .fields
+            .iter()
+            .map(|(identifier, data_type)| {
+                Ok((identifier.value.clone(), self.analyze_data_type(data_type)?))
+            })
+            .collect::<SemanticResult<Vec<_>>>()?;
+
+        if let Some(entry) = self.symbol_table.lookup_mut(&statement.identifier.value) {
+            entry.kind = SymbolKind::Struct(fields);
+        }
+
+        Ok(())
     }
 
     fn analyze_expression(&mut self, expression: &Expression) -> SemanticResult<DataType> {
@@ -277,13 +613,38 @@ impl Analyzer {
                 self.typeof_assignment_expression(expression)
             }
             Expression::BlockExpression(block) => self.typeof_block_expression(block),
+            Expression::SequenceExpression(sequence) => self.typeof_sequence_expression(sequence),
             Expression::PrefixExpression(prefix) => self.typeof_prefix_expression(prefix),
             Expression::InfixExpression(infix) => self.typeof_infix_expression(infix),
-            Expression::IfExpression(expression) => todo!(),
-            Expression::CallExpression(call) => todo!(),
-            Expression::TypeofExpression(expression) => todo!(),
-            Expression::IndexExpression(index) => todo!(),
+            Expression::IfExpression(expression) => {
+                self.typeof_if_expression(expression, provided_type.as_ref())
+            }
+            Expression::TryExpression(expression) => self.typeof_try_expression(expression),
+            Expression::MatchExpression(expression) => self.typeof_match_expression(expression),
+            Expression::CallExpression(call) => self.typeof_call_expression(call),
+            Expression::TypeofExpression(expression) => {
+                self.typeof_expression(&expression.expression)?;
+
+                Ok(DataType::new(DataTypeKind::String, expression.position))
+            }
+            Expression::CastExpression(cast) => self.typeof_cast_expression(cast),
+            Expression::IndexExpression(index) => self.typeof_index_expression(index),
+            Expression::RangeExpression(range) => Err(SemanticError::internal_error(
+                "a range can only be used as an index",
+                range.position,
+            )),
+            Expression::InterpolationExpression(interpolation) => {
+                for part in &interpolation.parts {
+                    self.typeof_expression(part)?;
+                }
+
+                Ok(DataType::new(DataTypeKind::String, interpolation.position))
+            }
             Expression::Literal(literal) => self.typeof_literal(literal, provided_type),
+            Expression::Error(position) => Err(SemanticError::internal_error(
+                "a malformed expression can't be analyzed",
+                *position,
+            )),
             Expression::Debug(expression, position) => {
                 if let Expression::Literal(Literal::Identifier(identifier)) = expression.as_ref() {
                     if let Some(symbol) = self.symbol_table.lookup(&identifier.value) {
@@ -322,11 +683,20 @@ impl Analyzer {
             }
         }
 
+        if let Expression::Literal(Literal::Identifier(value)) = expression.value.as_ref() {
+            if value.value == identifier.value {
+                self.warnings.push(SemanticWarning::self_assignment(
+                    identifier.value.clone(),
+                    identifier.position,
+                ));
+            }
+        }
+
         let value = self.typeof_expression(&expression.value)?;
-        if symbol.data_type != value {
-            return Err(SemanticError::type_mismatch(
-                symbol.data_type,
-                value,
+        if !Self::is_assignable(&value.kind, &symbol.data_type.kind) {
+            return Err(SemanticError::type_mismatch_between(
+                &symbol.data_type.kind,
+                &value.kind,
                 identifier.position,
             ));
         }
@@ -334,10 +704,57 @@ impl Analyzer {
         Ok(symbol.data_type)
     }
 
+    /// A sequence has the type of its last expression.
+    fn typeof_sequence_expression(
+        &mut self,
+        sequence: &SequenceExpression,
+    ) -> SemanticResult<DataType> {
+        let has_effects = sequence.expressions.iter().any(|expression| {
+            matches!(
+                expression,
+                Expression::CallExpression(_) | Expression::AssignmentExpression(_)
+            )
+        });
+        let mut ttype = DataType::new(DataTypeKind::Void, sequence.position);
+
+        for (index, expression) in sequence.expressions.iter().enumerate() {
+            if let Expression::Literal(literal) = expression {
+                if !has_effects && index + 1 < sequence.expressions.len() {
+                    self.warnings
+                        .push(SemanticWarning::unused_expression(literal.position()));
+                }
+            }
+
+            ttype = self.typeof_expression(expression)?;
+
+            // The rest of the sequence is never reached.
+            if ttype.kind == DataTypeKind::Never {
+                break;
+            }
+        }
+
+        Ok(DataType::new(ttype.kind, sequence.position))
+    }
+
     fn typeof_block_expression(&mut self, block: &BlockExpression) -> SemanticResult<DataType> {
         let symbol_table = SymbolTable::new(Some(self.symbol_table.clone()));
-        let kind =
-            Analyzer::new_with_symbol_table(block.statements.clone(), symbol_table).analyze()?;
+        self.typeof_block_with_symbol_table(block, symbol_table)
+    }
+
+    fn typeof_block_with_symbol_table(
+        &mut self,
+        block: &BlockExpression,
+        symbol_table: SymbolTable,
+    ) -> SemanticResult<DataType> {
+        let mut analyzer = Analyzer::new_with_symbol_table(block.statements.clone(), symbol_table);
+        analyzer.function_return_type = self.function_return_type.clone();
+        analyzer.strict = self.strict;
+        analyzer.unions = self.unions;
+        analyzer.loop_labels = self.loop_labels.clone();
+        let kind = analyzer.analyze();
+        self.warnings.append(&mut analyzer.warnings);
+        self.returns.append(&mut analyzer.returns);
+        let kind = kind?;
         Ok(match kind {
             AnalyzerReturnKind::EarlyReturn(ttype) => {
                 self.set_return_type(
@@ -351,107 +768,648 @@ impl Analyzer {
         })
     }
 
-    fn typeof_prefix_expression(&mut self, prefix: &PrefixExpression) -> SemanticResult<DataType> {
-        let right = self.typeof_expression(&prefix.right)?;
+    /// Types a block that may not run, handing back its `return` instead of setting it.
+    fn typeof_conditional_block(
+        &mut self,
+        block: &BlockExpression,
+    ) -> SemanticResult<(DataType, Option<AnalyzerReturnKind>)> {
+        let symbol_table = SymbolTable::new(Some(self.symbol_table.clone()));
+        self.typeof_conditional_block_with_symbol_table(block, symbol_table)
+    }
 
-        /*
-            !T => boolean
-            [-|!]T => number
-        */
-        match prefix.operator {
-            PrefixOperator::Not => {
-                if right.kind != DataTypeKind::Boolean {
-                    Err(SemanticError::operator_not_supported(
-                        prefix.operator.clone(),
-                        right.kind,
-                        prefix.position,
-                    ))
-                } else {
-                    Ok(DataType::new(DataTypeKind::Boolean, prefix.position))
-                }
-            }
-            PrefixOperator::Minus => {
-                if right.kind != DataTypeKind::Number {
-                    Err(SemanticError::operator_not_supported(
-                        prefix.operator.clone(),
-                        right.kind,
-                        prefix.position,
-                    ))
-                } else {
-                    Ok(DataType::new(DataTypeKind::Number, prefix.position))
-                }
-            }
+    fn typeof_conditional_block_with_symbol_table(
+        &mut self,
+        block: &BlockExpression,
+        symbol_table: SymbolTable,
+    ) -> SemanticResult<(DataType, Option<AnalyzerReturnKind>)> {
+        let return_type = self.return_type.clone();
+        let data_type = self.typeof_block_with_symbol_table(block, symbol_table)?;
+
+        if self.return_type == return_type {
+            return Ok((data_type, None));
         }
+
+        Ok((
+            DataType::new(DataTypeKind::Never, block.position),
+            Some(std::mem::replace(&mut self.return_type, return_type)),
+        ))
     }
 
-    fn typeof_infix_expression(&mut self, infix: &InfixExpression) -> SemanticResult<DataType> {
-        let left = self.typeof_expression(&infix.left)?;
-        let right = self.typeof_expression(&infix.right)?;
+    /// Without an `else`, a non-void consequence is typed as optional.
+    fn typeof_if_expression(
+        &mut self,
+        expression: &IfExpression,
+        provided_type: Option<&DataTypeKind>,
+    ) -> SemanticResult<DataType> {
+        let condition = self.typeof_expression(&expression.condition)?;
 
-        /*
-            T [-|*|/|%] T => number
-            T + T => number|string
-            T [==|!=|<|>|<=|>=] T => boolean
-        */
-        match infix.operator {
-            InfixOperator::Plus => match left.kind {
-                DataTypeKind::Number | DataTypeKind::String => {
-                    if left.kind == right.kind {
-                        Ok(DataType::new(left.kind, infix.position))
-                    } else {
-                        Err(SemanticError::type_mismatch(
-                            left.kind,
-                            right.kind,
-                            infix.position,
-                        ))
-                    }
+        if condition.kind != DataTypeKind::Boolean {
+            return Err(SemanticError::type_mismatch(
+                condition.kind,
+                DataTypeKind::Boolean,
+                condition.position,
+            ));
+        }
+
+        let (consequence, consequence_return) =
+            self.typeof_conditional_block(&expression.consequence)?;
+
+        match &expression.alternative {
+            Some(alternative) => {
+                let (alternative, alternative_return) =
+                    self.typeof_conditional_block(alternative)?;
+
+                // Only an `if` whose branches both return ends the enclosing block.
+                if let (Some(consequence), Some(alternative)) =
+                    (consequence_return, alternative_return)
+                {
+                    self.set_return_type(consequence, expression.position)?;
+                    self.set_return_type(alternative, expression.position)?;
                 }
-                _ => Err(SemanticError::operator_not_supported(
-                    infix.operator.clone(),
-                    right.kind,
-                    infix.position,
-                )),
-            },
-            InfixOperator::Minus
-            | InfixOperator::Asterisk
-            | InfixOperator::Slash
-            | InfixOperator::Percent => {
-                if left.kind != DataTypeKind::Number {
-                    Err(SemanticError::operator_not_supported(
-                        infix.operator.clone(),
-                        left.kind,
-                        infix.position,
-                    ))
-                } else if left.kind != right.kind {
-                    Err(SemanticError::type_mismatch(
-                        left.kind,
-                        right.kind,
-                        infix.position,
-                    ))
-                } else {
-                    Ok(DataType::new(DataTypeKind::Number, infix.position))
+
+                match Self::join(&alternative.kind, &consequence.kind) {
+                    Some(kind) => Ok(DataType::new(kind, expression.position)),
+                    None => Err(SemanticError::type_mismatch_between(
+                        &alternative.kind,
+                        &consequence.kind,
+                        expression.position,
+                    )),
                 }
             }
-            InfixOperator::EQ
-            | InfixOperator::NEQ
-            | InfixOperator::LT
-            | InfixOperator::LTE
-            | InfixOperator::GT
-            | InfixOperator::GTE => {
-                if left.kind != right.kind {
-                    Err(SemanticError::type_mismatch(
-                        left.kind,
-                        right.kind,
-                        infix.position,
-                    ))
-                } else {
-                    Ok(DataType::new(DataTypeKind::Boolean, infix.position))
+            None => {
+                // Without `else`, the value is `null` when the condition is `false`.
+                let kind = match consequence.kind {
+                    DataTypeKind::Void | DataTypeKind::Never => DataTypeKind::Void,
+                    DataTypeKind::Optional(_) => consequence.kind.clone(),
+                    _ => DataTypeKind::Optional(Box::new(consequence.clone())),
+                };
+
+                if !matches!(consequence.kind, DataTypeKind::Void | DataTypeKind::Never)
+                    && !matches!(provided_type, Some(DataTypeKind::Optional(_)))
+                {
+                    self.warnings.push(SemanticWarning::missing_else(
+                        consequence.kind,
+                        expression.position,
+                    ));
                 }
+
+                Ok(DataType::new(kind, expression.position))
             }
-            InfixOperator::Dot => todo!(),
         }
     }
 
+    /// The error message is bound as a string in the handler, which must have the same type as the body.
+    fn typeof_try_expression(&mut self, expression: &TryExpression) -> SemanticResult<DataType> {
+        let body = self.typeof_block_expression(&expression.body)?;
+
+        let mut symbol_table = SymbolTable::new(Some(self.symbol_table.clone()));
+        symbol_table.insert(
+            &expression.identifier.value,
+            SymbolEntry::new(
+                DataType::new(DataTypeKind::String, expression.identifier.position),
+                SymbolAttributes::default().mutable(false),
+                SymbolKind::Variable,
+            ),
+        );
+
+        let handler = self.typeof_block_with_symbol_table(&expression.handler, symbol_table)?;
+
+        match Self::join(&handler.kind, &body.kind) {
+            Some(kind) => Ok(DataType::new(kind, expression.position)),
+            None => Err(SemanticError::type_mismatch_between(
+                &handler.kind,
+                &body.kind,
+                expression.position,
+            )),
+        }
+    }
+
+    /// The arms must have the same type, like the branches of an `if`.
+    fn typeof_match_expression(
+        &mut self,
+        expression: &MatchExpression,
+    ) -> SemanticResult<DataType> {
+        let value = self.typeof_expression(&expression.value)?;
+
+        let mut data_type: Option<DataType> = None;
+        let mut returns = Vec::new();
+
+        for arm in &expression.arms {
+            let mut symbol_table = SymbolTable::new(Some(self.symbol_table.clone()));
+
+            match &arm.pattern {
+                MatchPattern::Literal(literal) => {
+                    let pattern = self.typeof_literal(literal, Some(value.kind.clone()))?;
+
+                    if !Self::is_assignable(&pattern.kind, &value.kind) {
+                        return Err(SemanticError::type_mismatch(
+                            pattern.kind,
+                            value.kind,
+                            arm.position,
+                        ));
+                    }
+                }
+                MatchPattern::Binding(identifier) => {
+                    symbol_table.insert(
+                        &identifier.value,
+                        SymbolEntry::new(
+                            value.clone(),
+                            SymbolAttributes::default(),
+                            SymbolKind::Variable,
+                        ),
+                    );
+                }
+                MatchPattern::Wildcard(_) => {}
+            }
+
+            if let Some(guard) = &arm.guard {
+                let mut analyzer =
+                    Analyzer::new_with_symbol_table(Vec::new(), symbol_table.clone());
+                analyzer.strict = self.strict;
+                analyzer.unions = self.unions;
+                let guard = analyzer.typeof_expression(guard);
+                self.warnings.append(&mut analyzer.warnings);
+                let guard = guard?;
+
+                if guard.kind != DataTypeKind::Boolean {
+                    return Err(SemanticError::type_mismatch(
+                        guard.kind,
+                        DataTypeKind::Boolean,
+                        guard.position,
+                    ));
+                }
+            }
+
+            let (body, body_return) =
+                self.typeof_conditional_block_with_symbol_table(&arm.body, symbol_table)?;
+            returns.push(body_return);
+
+            data_type = Some(match data_type {
+                Some(data_type) => match Self::join(&body.kind, &data_type.kind) {
+                    Some(kind) => DataType::new(kind, expression.position),
+                    None => {
+                        return Err(SemanticError::type_mismatch_between(
+                            &body.kind,
+                            &data_type.kind,
+                            arm.position,
+                        ))
+                    }
+                },
+                None => body,
+            });
+        }
+
+        if returns.iter().all(Option::is_some) {
+            for return_type in returns.into_iter().flatten() {
+                self.set_return_type(return_type, expression.position)?;
+            }
+        }
+
+        // Without arms, the `match` always fails.
+        Ok(DataType::new(
+            data_type.map_or(DataTypeKind::Never, |data_type| data_type.kind),
+            expression.position,
+        ))
+    }
+
+    fn typeof_call_expression(&mut self, call: &CallExpression) -> SemanticResult<DataType> {
+        let function = self.typeof_expression(&call.function)?;
+
+        let function_type = match function.kind {
+            DataTypeKind::Fn(function_type) => function_type,
+            kind => {
+                return Err(SemanticError::operator_not_supported(
+                    "()",
+                    kind,
+                    call.position,
+                ))
+            }
+        };
+
+        let generics = function_type.generics.clone().unwrap_or_default();
+        let mut bindings = HashMap::new();
+        let mut parameters = function_type.parameters.iter().peekable();
+
+        for argument in &call.arguments {
+            // Each argument collected by a spread parameter has to match its element type.
+            let parameter = match parameters.peek() {
+                Some((parameter, ParameterKind::Spread)) => match &parameter.kind {
+                    DataTypeKind::Array(element) => &element.kind,
+                    kind => kind,
+                },
+                Some(_) => &parameters.next().unwrap().0.kind,
+                None => {
+                    self.typeof_expression(argument)?;
+                    break;
+                }
+            };
+            // The parameters of generic functions are only known once they are unified.
+            let provided_type = generics.is_empty().then(|| parameter.clone());
+            let argument_type =
+                self.typeof_expression_with_provided_type(argument, provided_type)?;
+
+            if !Self::unify(parameter, &argument_type.kind, &generics, &mut bindings) {
+                return Err(SemanticError::type_mismatch_between(
+                    &argument_type.kind,
+                    &Self::substitute(parameter, &bindings),
+                    argument_type.position,
+                ));
+            }
+        }
+
+        Ok(DataType::new(
+            Self::substitute(&function_type.return_type.kind, &bindings),
+            call.position,
+        ))
+    }
+
+    /// Checks that an argument can be passed as the parameter, binding the type parameters of a generic function.
+    fn unify(
+        parameter: &DataTypeKind,
+        argument: &DataTypeKind,
+        generics: &[Identifier],
+        bindings: &mut HashMap<String, DataTypeKind>,
+    ) -> bool {
+        match (parameter, argument) {
+            (DataTypeKind::Custom(name), _) if generics.iter().any(|g| g.value == *name) => {
+                match bindings.get(name) {
+                    Some(bound) => Self::is_assignable(argument, bound),
+                    None => {
+                        bindings.insert(name.clone(), argument.clone());
+                        true
+                    }
+                }
+            }
+            (DataTypeKind::Array(parameter), DataTypeKind::Array(argument)) => {
+                Self::unify(&parameter.kind, &argument.kind, generics, bindings)
+            }
+            (DataTypeKind::Fn(parameter), DataTypeKind::Fn(argument)) => {
+                parameter.parameters.len() == argument.parameters.len()
+                    && parameter.parameters.iter().zip(&argument.parameters).all(
+                        |((parameter, parameter_kind), (argument, argument_kind))| {
+                            parameter_kind == argument_kind
+                                && Self::unify(&parameter.kind, &argument.kind, generics, bindings)
+                        },
+                    )
+                    && Self::unify(
+                        &parameter.return_type.kind,
+                        &argument.return_type.kind,
+                        generics,
+                        bindings,
+                    )
+            }
+            _ => Self::is_assignable(argument, parameter),
+        }
+    }
+
+    /// Replaces the bound type parameters in a type.
+    fn substitute(kind: &DataTypeKind, bindings: &HashMap<String, DataTypeKind>) -> DataTypeKind {
+        let substitute = |data_type: &DataType| {
+            DataType::new(
+                Self::substitute(&data_type.kind, bindings),
+                data_type.position,
+            )
+        };
+
+        match kind {
+            DataTypeKind::Custom(name) => {
+                bindings.get(name).cloned().unwrap_or_else(|| kind.clone())
+            }
+            DataTypeKind::Array(element) => DataTypeKind::Array(Box::new(substitute(element))),
+            DataTypeKind::Fn(function_type) => DataTypeKind::Fn(FunctionType {
+                parameters: function_type
+                    .parameters
+                    .iter()
+                    .map(|(data_type, kind)| (substitute(data_type), *kind))
+                    .collect(),
+                return_type: Box::new(substitute(&function_type.return_type)),
+                ..function_type.clone()
+            }),
+            _ => kind.clone(),
+        }
+    }
+
+    /// Casts are allowed between numbers, strings and booleans; whether the value converts is checked at runtime.
+    fn typeof_cast_expression(&mut self, cast: &CastExpression) -> SemanticResult<DataType> {
+        let from = self.typeof_expression(&cast.expression)?;
+        let to = self.analyze_data_type(&cast.data_type)?;
+
+        for kind in [&from.kind, &to.kind] {
+            if !matches!(
+                kind,
+                DataTypeKind::Number | DataTypeKind::String | DataTypeKind::Boolean
+            ) {
+                return Err(SemanticError::operator_not_supported(
+                    "as",
+                    kind.clone(),
+                    cast.position,
+                ));
+            }
+        }
+
+        Ok(DataType::new(to.kind, cast.position))
+    }
+
+    fn typeof_index_expression(&mut self, index: &IndexExpression) -> SemanticResult<DataType> {
+        let left = self.typeof_expression(&index.left)?;
+
+        // Structs are indexed with a string literal naming the field.
+        if let DataTypeKind::Custom(name) = &left.kind {
+            return match index.index.as_ref() {
+                Expression::Literal(Literal::StringLiteral(field)) => self
+                    .symbol_table
+                    .field(&left.kind, &field.value)
+                    .map(|data_type| DataType::new(data_type.kind.clone(), index.position))
+                    .ok_or_else(|| {
+                        SemanticError::field_not_defined(&field.value, name, index.position)
+                    }),
+                _ => Err(SemanticError::operator_not_supported(
+                    "[]",
+                    &left.kind,
+                    index.position,
+                )),
+            };
+        }
+
+        let bounds = match index.index.as_ref() {
+            Expression::RangeExpression(range) => {
+                vec![range.start.as_deref(), range.end.as_deref()]
+            }
+            expression => vec![Some(expression)],
+        };
+
+        for bound in bounds.into_iter().flatten() {
+            let ttype = self.typeof_expression(bound)?;
+
+            if ttype.kind != DataTypeKind::Number {
+                return Err(SemanticError::type_mismatch(
+                    ttype.kind,
+                    DataTypeKind::Number,
+                    index.position,
+                ));
+            }
+        }
+
+        if let Expression::RangeExpression(_) = index.index.as_ref() {
+            return match left.kind {
+                DataTypeKind::Array(_) | DataTypeKind::String => Ok(left),
+                kind => Err(SemanticError::operator_not_supported(
+                    "[..]",
+                    kind,
+                    index.position,
+                )),
+            };
+        }
+
+        // Both the array and the index are literals, so the bounds can be checked statically.
+        if let (
+            Expression::Literal(Literal::ArrayLiteral(array)),
+            Expression::Literal(Literal::NumberLiteral(number)),
+        ) = (index.left.as_ref(), index.index.as_ref())
+        {
+            if number.value >= array.elements.len() as f64 {
+                return Err(SemanticError::index_out_of_bounds(
+                    number.value as usize,
+                    array.elements.len(),
+                    index.position,
+                ));
+            }
+        }
+
+        match left.kind {
+            DataTypeKind::Array(element) => Ok(DataType::new(element.kind, index.position)),
+            DataTypeKind::String => Ok(DataType::new(DataTypeKind::String, index.position)),
+            kind => Err(SemanticError::operator_not_supported(
+                "[]",
+                kind,
+                index.position,
+            )),
+        }
+    }
+
+    fn typeof_prefix_expression(&mut self, prefix: &PrefixExpression) -> SemanticResult<DataType> {
+        let right = self.typeof_expression(&prefix.right)?;
+
+        /*
+            !T => boolean
+            [-|!]T => number
+        */
+        match prefix.operator {
+            PrefixOperator::Not => {
+                if right.kind != DataTypeKind::Boolean {
+                    Err(SemanticError::operator_not_supported(
+                        prefix.operator.clone(),
+                        right.kind,
+                        prefix.position,
+                    ))
+                } else {
+                    Ok(DataType::new(DataTypeKind::Boolean, prefix.position))
+                }
+            }
+            PrefixOperator::Minus => {
+                if right.kind != DataTypeKind::Number {
+                    Err(SemanticError::operator_not_supported(
+                        prefix.operator.clone(),
+                        right.kind,
+                        prefix.position,
+                    ))
+                } else {
+                    Ok(DataType::new(DataTypeKind::Number, prefix.position))
+                }
+            }
+        }
+    }
+
+    fn typeof_infix_expression(&mut self, infix: &InfixExpression) -> SemanticResult<DataType> {
+        if matches!(
+            infix.operator,
+            InfixOperator::Dot | InfixOperator::OptionalDot
+        ) {
+            return self.typeof_member_expression(infix);
+        }
+
+        let left = self.typeof_expression(&infix.left)?;
+        let right = self.typeof_expression(&infix.right)?;
+
+        /*
+            number [-|*|**|/|%] number => number
+            T + T => number|string
+            string + number, number + string => string (unless strict)
+            T [==|!=] T => boolean
+            number [<|>|<=|>=] number => boolean
+            T in T[] => boolean
+            string in struct => boolean
+        */
+        match infix.operator {
+            InfixOperator::Plus
+                if !self.strict
+                    && matches!(
+                        (&left.kind, &right.kind),
+                        (DataTypeKind::String, DataTypeKind::Number)
+                            | (DataTypeKind::Number, DataTypeKind::String)
+                    ) =>
+            {
+                Ok(DataType::new(DataTypeKind::String, infix.position))
+            }
+            InfixOperator::Plus => match left.kind {
+                DataTypeKind::Number | DataTypeKind::String => {
+                    if left.kind == right.kind {
+                        Ok(DataType::new(left.kind, infix.position))
+                    } else {
+                        Err(SemanticError::type_mismatch(
+                            left.kind,
+                            right.kind,
+                            infix.position,
+                        ))
+                    }
+                }
+                _ => Err(SemanticError::operator_not_supported(
+                    infix.operator.clone(),
+                    left.kind,
+                    infix.position,
+                )),
+            },
+            InfixOperator::Minus
+            | InfixOperator::Asterisk
+            | InfixOperator::Pow
+            | InfixOperator::Slash
+            | InfixOperator::Percent => {
+                if left.kind != DataTypeKind::Number {
+                    Err(SemanticError::operator_not_supported(
+                        infix.operator.clone(),
+                        left.kind,
+                        infix.position,
+                    ))
+                } else if left.kind != right.kind {
+                    Err(SemanticError::type_mismatch(
+                        left.kind,
+                        right.kind,
+                        infix.position,
+                    ))
+                } else {
+                    Ok(DataType::new(DataTypeKind::Number, infix.position))
+                }
+            }
+            InfixOperator::LT | InfixOperator::LTE | InfixOperator::GT | InfixOperator::GTE
+                if left.kind != DataTypeKind::Number =>
+            {
+                Err(SemanticError::operator_not_supported(
+                    infix.operator.clone(),
+                    left.kind,
+                    infix.position,
+                ))
+            }
+            InfixOperator::EQ
+            | InfixOperator::NEQ
+            | InfixOperator::LT
+            | InfixOperator::LTE
+            | InfixOperator::GT
+            | InfixOperator::GTE => {
+                if left.kind != right.kind {
+                    Err(SemanticError::type_mismatch(
+                        left.kind,
+                        right.kind,
+                        infix.position,
+                    ))
+                } else {
+                    Ok(DataType::new(DataTypeKind::Boolean, infix.position))
+                }
+            }
+            InfixOperator::In => match right.kind {
+                DataTypeKind::Array(element) => {
+                    if left.kind != element.kind {
+                        Err(SemanticError::type_mismatch(
+                            left.kind,
+                            element.kind,
+                            infix.position,
+                        ))
+                    } else {
+                        Ok(DataType::new(DataTypeKind::Boolean, infix.position))
+                    }
+                }
+                // `"field" in value` checks whether a struct has the field.
+                DataTypeKind::Custom(_)
+                    if matches!(
+                        self.symbol_table
+                            .named(&right.kind.to_string())
+                            .map(|entry| &entry.kind),
+                        Some(SymbolKind::Struct(_))
+                    ) =>
+                {
+                    if left.kind != DataTypeKind::String {
+                        Err(SemanticError::type_mismatch(
+                            left.kind,
+                            DataTypeKind::String,
+                            infix.position,
+                        ))
+                    } else {
+                        Ok(DataType::new(DataTypeKind::Boolean, infix.position))
+                    }
+                }
+                _ => Err(SemanticError::operator_not_supported(
+                    infix.operator.clone(),
+                    right.kind,
+                    infix.position,
+                )),
+            },
+            InfixOperator::NullishCoalescing => {
+                // The default unwraps an optional: `number? ?? number` is a `number`.
+                if let DataTypeKind::Optional(value) = &left.kind {
+                    if Self::is_assignable(&right.kind, &value.kind) {
+                        return Ok(DataType::new(value.kind.clone(), infix.position));
+                    }
+                }
+
+                if left.kind != right.kind {
+                    Err(SemanticError::type_mismatch(
+                        left.kind,
+                        right.kind,
+                        infix.position,
+                    ))
+                } else {
+                    Ok(left)
+                }
+            }
+            InfixOperator::Dot | InfixOperator::OptionalDot => Err(SemanticError::internal_error(
+                "member access as an operator",
+                infix.position,
+            )),
+        }
+    }
+
+    /// `a?.b` also accepts an optional struct, in which case the field is optional too.
+    fn typeof_member_expression(&mut self, infix: &InfixExpression) -> SemanticResult<DataType> {
+        let left = self.typeof_expression(&infix.left)?;
+
+        let (kind, optional) = match left.kind {
+            DataTypeKind::Optional(inner) if infix.operator == InfixOperator::OptionalDot => {
+                (inner.kind, true)
+            }
+            kind => (kind, false),
+        };
+
+        let Expression::Literal(Literal::Identifier(field)) = infix.right.as_ref() else {
+            return Err(SemanticError::internal_error(
+                "expected a field name after `.`",
+                infix.position,
+            ));
+        };
+
+        let field_type = self
+            .symbol_table
+            .field(&kind, &field.value)
+            .cloned()
+            .ok_or_else(|| SemanticError::field_not_defined(&field.value, &kind, field.position))?;
+
+        Ok(DataType::new(
+            match optional {
+                true => DataTypeKind::Optional(Box::new(field_type)),
+                false => field_type.kind,
+            },
+            infix.position,
+        ))
+    }
+
     pub fn typeof_literal(
         &mut self,
         literal: &Literal,
@@ -469,30 +1427,324 @@ impl Analyzer {
                 DataType::new(DataTypeKind::Boolean, literal.position)
             }
             Literal::ArrayLiteral(literal) => self.typeof_array_literal(literal, provided_type)?,
-            _ => unimplemented!(),
+            Literal::FunctionLiteral(literal) => self.typeof_function_literal(literal)?,
+            Literal::StructLiteral(literal) => self.typeof_struct_literal(literal)?,
+            // `null` only has a type when it is bound to an optional.
+            Literal::NullLiteral(literal) => match provided_type {
+                Some(kind @ DataTypeKind::Optional(_)) => DataType::new(kind, literal.position),
+                _ => return Err(SemanticError::type_annotation_needed(literal.position)),
+            },
         })
     }
 
-    fn typeof_identifier_literal(&self, identifier: &Identifier) -> SemanticResult<DataType> {
-        Ok(self
-            .symbol_table
-            .variable(&identifier.value)
-            .ok_or_else(|| {
-                SemanticError::identifier_not_defined(identifier.value.clone(), identifier.position)
-            })?
-            .data_type
-            .clone())
-    }
+    /// Computed keys are only known once the struct is created, so they're only checked to be strings.
+    fn typeof_struct_literal(&mut self, literal: &StructLiteral) -> SemanticResult<DataType> {
+        let name = &literal.identifier.value;
+        let data_type = DataTypeKind::Custom(name.clone());
 
-    fn typeof_array_literal(
-        &mut self,
-        literal: &ArrayLiteral,
-        provided_type: Option<DataTypeKind>,
+        let fields = match self.symbol_table.named(name).map(|entry| &entry.kind) {
+            Some(SymbolKind::Struct(fields)) => fields.clone(),
+            _ => {
+                return Err(SemanticError::type_alias_not_defined(
+                    name,
+                    literal.identifier.position,
+                ))
+            }
+        };
+
+        let mut names = Vec::new();
+        let mut computed = 0;
+
+        for (key, value) in &literal.fields {
+            let field = match key {
+                FieldKey::Named(field) => field,
+                FieldKey::Computed(key, position) => {
+                    let key = self.typeof_expression(key)?;
+
+                    if key.kind != DataTypeKind::String {
+                        return Err(SemanticError::type_mismatch(
+                            key.kind,
+                            DataTypeKind::String,
+                            *position,
+                        ));
+                    }
+
+                    // The field is only known at runtime, so the value has to fit every field.
+                    computed += 1;
+                    let value = self.typeof_expression(value)?;
+
+                    if let Some((_, expected)) = fields
+                        .iter()
+                        .find(|(_, expected)| !Self::is_assignable(&value.kind, &expected.kind))
+                    {
+                        return Err(SemanticError::type_mismatch_between(
+                            &value.kind,
+                            &expected.kind,
+                            *position,
+                        ));
+                    }
+
+                    continue;
+                }
+            };
+
+            if names.contains(&&field.value) {
+                return Err(SemanticError::duplicate_field(&field.value, field.position));
+            }
+            names.push(&field.value);
+
+            let expected = self
+                .symbol_table
+                .field(&data_type, &field.value)
+                .cloned()
+                .ok_or_else(|| {
+                    SemanticError::field_not_defined(&field.value, name, field.position)
+                })?;
+            let value =
+                self.typeof_expression_with_provided_type(value, Some(expected.kind.clone()))?;
+
+            if !Self::is_assignable(&value.kind, &expected.kind) {
+                return Err(SemanticError::type_mismatch_between(
+                    &value.kind,
+                    &expected.kind,
+                    field.position,
+                ));
+            }
+        }
+
+        // Every field needs a value. Computed keys may name any of the missing ones, so only their number is checked.
+        let missing = fields
+            .iter()
+            .filter(|(field, _)| !names.contains(&field))
+            .collect::<Vec<_>>();
+
+        if missing.len() > computed {
+            return Err(SemanticError::missing_field(
+                &missing[0].0,
+                name,
+                literal.position,
+            ));
+        }
+
+        Ok(DataType::new(data_type, literal.position))
+    }
+
+    fn typeof_identifier_literal(&self, identifier: &Identifier) -> SemanticResult<DataType> {
+        Ok(self
+            .symbol_table
+            .variable(&identifier.value)
+            .ok_or_else(|| {
+                SemanticError::identifier_not_defined(identifier.value.clone(), identifier.position)
+            })?
+            .data_type
+            .clone())
+    }
+
+    /// Returns the variables a parameter pattern binds and their types.
+    fn pattern_bindings<'a>(
+        &self,
+        pattern: &'a Pattern,
+        data_type: &DataType,
+        position: Position,
+    ) -> SemanticResult<Vec<(&'a Identifier, DataType)>> {
+        match (pattern, &data_type.kind) {
+            (Pattern::Struct(identifiers), DataTypeKind::Custom(name))
+                if matches!(
+                    self.symbol_table.named(name).map(|entry| &entry.kind),
+                    Some(SymbolKind::Struct(_))
+                ) =>
+            {
+                identifiers
+                    .iter()
+                    .map(|identifier| {
+                        self.symbol_table
+                            .field(&data_type.kind, &identifier.value)
+                            .map(|field| (identifier, field.clone()))
+                            .ok_or_else(|| {
+                                SemanticError::field_not_defined(
+                                    &identifier.value,
+                                    name,
+                                    identifier.position,
+                                )
+                            })
+                    })
+                    .collect()
+            }
+            (Pattern::Array(identifiers), DataTypeKind::Array(element)) => Ok(identifiers
+                .iter()
+                .map(|identifier| (identifier, element.as_ref().clone()))
+                .collect()),
+            _ => Err(SemanticError::new(
+                SemanticErrorKind::PatternMismatch(pattern.to_string(), data_type.to_string()),
+                position,
+            )),
+        }
+    }
+
+    /// Types a function literal from its signature. The body is analyzed in a new scope with the parameters bound.
+    fn typeof_function_literal(&mut self, literal: &FunctionLiteral) -> SemanticResult<DataType> {
+        let mut symbol_table = SymbolTable::new(Some(self.symbol_table.clone()));
+        let mut parameters = Vec::new();
+
+        for parameter in &literal.parameters {
+            let data_type = self.analyze_data_type(&parameter.data_type)?;
+
+            // Spread parameters collect the remaining arguments into an array, so they have to be annotated as one.
+            if parameter.kind == ParameterKind::Spread
+                && !matches!(data_type.kind, DataTypeKind::Array(_))
+            {
+                let expected = DataTypeKind::Array(Box::new(data_type.clone()));
+
+                return Err(SemanticError::type_mismatch(
+                    &data_type.kind,
+                    &expected,
+                    parameter.position,
+                )
+                .with_suggestion(format!(
+                    "annotate the spread parameter as `{}`",
+                    expected.to_string().to_lowercase()
+                )));
+            }
+
+            let bindings = match &parameter.pattern {
+                Some(pattern) => self.pattern_bindings(pattern, &data_type, parameter.position)?,
+                None => vec![(&parameter.identifier, data_type.clone())],
+            };
+
+            for (identifier, data_type) in bindings {
+                symbol_table
+                    .insert(
+                        &identifier.value,
+                        SymbolEntry::new(
+                            data_type,
+                            SymbolAttributes::default()
+                                .spread(parameter.kind == ParameterKind::Spread),
+                            SymbolKind::Variable,
+                        ),
+                    )
+                    .ok_or_else(|| {
+                        SemanticError::identifier_already_defined(
+                            identifier.value.clone(),
+                            parameter.position,
+                        )
+                    })?;
+            }
+
+            parameters.push((data_type, parameter.kind));
+        }
+
+        let infer = self.unions && literal.return_type.is_none();
+        let return_type = match &literal.return_type {
+            Some(return_type) => self.analyze_data_type(return_type)?,
+            None => DataType::new(DataTypeKind::Void, literal.position),
+        };
+
+        let mut analyzer =
+            Analyzer::new_with_symbol_table(literal.body.statements.clone(), symbol_table);
+        analyzer.function_return_type = (!infer).then(|| return_type.kind.clone());
+        analyzer.strict = self.strict;
+        analyzer.unions = self.unions;
+        let result = analyzer.analyze();
+        self.warnings.append(&mut analyzer.warnings);
+
+        let result = result?;
+
+        let return_type = if infer {
+            // The function may return the value of every `return` and of the trailing expression, or `void` if it can
+            // fall off the end.
+            let last = match result {
+                AnalyzerReturnKind::EarlyReturn(_) => None,
+                AnalyzerReturnKind::Expression(kind) => Some(kind),
+                AnalyzerReturnKind::Unknown => Some(DataTypeKind::Void),
+            };
+            let kind = analyzer
+                .returns
+                .into_iter()
+                .chain(last)
+                .reduce(|left, right| Self::union(&left, &right))
+                .unwrap_or(DataTypeKind::Void);
+
+            DataType::new(kind, literal.position)
+        } else {
+            // `return` statements are checked as they are analyzed, the trailing expression is checked here.
+            // A body that neither returns nor ends with an expression falls off the end, which only a void function may.
+            match result {
+                AnalyzerReturnKind::Expression(kind)
+                    if !Self::is_assignable(&kind, &return_type.kind) =>
+                {
+                    return Err(SemanticError::type_mismatch_between(
+                        &kind,
+                        &return_type.kind,
+                        literal.position,
+                    ));
+                }
+                AnalyzerReturnKind::Unknown if return_type.kind != DataTypeKind::Void => {
+                    return Err(SemanticError::missing_return(
+                        &return_type.kind,
+                        literal.position,
+                    ));
+                }
+                _ => {}
+            }
+
+            return_type
+        };
+
+        Ok(DataType::new(
+            DataTypeKind::Fn(FunctionType {
+                generics: literal.generics.clone(),
+                parameters,
+                return_type: Box::new(return_type),
+                position: literal.position,
+            }),
+            literal.position,
+        ))
+    }
+
+    /// Types a function literal from its annotations alone, without analyzing the body.
+    fn function_signature(&mut self, literal: &FunctionLiteral) -> SemanticResult<DataType> {
+        let parameters = literal
+            .parameters
+            .iter()
+            .map(|parameter| {
+                Ok((
+                    self.analyze_data_type(&parameter.data_type)?,
+                    parameter.kind,
+                ))
+            })
+            .collect::<SemanticResult<Vec<_>>>()?;
+        let return_type = match &literal.return_type {
+            Some(return_type) => self.analyze_data_type(return_type)?,
+            None => DataType::new(DataTypeKind::Void, literal.position),
+        };
+
+        Ok(DataType::new(
+            DataTypeKind::Fn(FunctionType {
+                generics: literal.generics.clone(),
+                parameters,
+                return_type: Box::new(return_type),
+                position: literal.position,
+            }),
+            literal.position,
+        ))
+    }
+
+    fn typeof_array_literal(
+        &mut self,
+        literal: &ArrayLiteral,
+        provided_type: Option<DataTypeKind>,
     ) -> SemanticResult<DataType> {
+        // The element type of the annotation is passed down, so nested empty arrays (`[[]]`) can be typed too.
+        let element_type = match &provided_type {
+            Some(DataTypeKind::Array(element)) => Some(element.kind.clone()),
+            _ => None,
+        };
+
         let mut data_type: Option<DataType> = None;
 
         for expression in &literal.elements {
-            let ttype = self.typeof_expression(expression)?;
+            let ttype =
+                self.typeof_expression_with_provided_type(expression, element_type.clone())?;
 
             if let Some(data_type) = data_type.clone() {
                 if data_type != ttype {
@@ -512,199 +1764,1393 @@ impl Analyzer {
                 DataTypeKind::Array(Box::new(data_type)),
                 literal.position,
             )),
-            None => provided_type
-                .ok_or_else(|| SemanticError::type_annotation_needed(literal.position))
-                .map(|ttype| DataType::new(ttype, literal.position)),
+            None => match provided_type {
+                Some(ttype @ DataTypeKind::Array(_)) => Ok(DataType::new(ttype, literal.position)),
+                Some(ttype) => Err(SemanticError::type_mismatch(
+                    String::from("[]"),
+                    ttype.to_string(),
+                    literal.position,
+                )),
+                None => Err(SemanticError::type_annotation_needed(literal.position)),
+            },
+        }
+    }
+
+    pub fn typeof_expression(&mut self, expression: &Expression) -> SemanticResult<DataType> {
+        self.typeof_expression_with_provided_type(expression, None)
+    }
+
+    pub fn analyze_data_type(&self, data_type: &DataType) -> SemanticResult<DataType> {
+        Ok(match data_type.kind.clone() {
+            DataTypeKind::Custom(identifier) => self
+                .symbol_table
+                .named(&identifier)
+                .ok_or_else(|| {
+                    SemanticError::type_alias_not_defined(identifier, data_type.position)
+                })?
+                .data_type
+                .clone(),
+            DataTypeKind::Array(data_type) => DataType::new(
+                DataTypeKind::Array(Box::new(self.analyze_data_type(&data_type)?)),
+                data_type.position,
+            ),
+            DataTypeKind::Optional(data_type) => DataType::new(
+                DataTypeKind::Optional(Box::new(self.analyze_data_type(&data_type)?)),
+                data_type.position,
+            ),
+            DataTypeKind::Fn(function_type) => {
+                // The type parameters of a generic function stand for themselves in its signature.
+                let scope = match &function_type.generics {
+                    Some(generics) => {
+                        let mut symbol_table = SymbolTable::new(Some(self.symbol_table.clone()));
+
+                        for generic in generics {
+                            symbol_table
+                                .insert(
+                                    &generic.value,
+                                    SymbolEntry::new(
+                                        DataType::new(
+                                            DataTypeKind::Custom(generic.value.clone()),
+                                            generic.position,
+                                        ),
+                                        SymbolAttributes::default(),
+                                        SymbolKind::Named,
+                                    ),
+                                )
+                                .ok_or_else(|| {
+                                    SemanticError::type_alias_already_defined(
+                                        generic.value.clone(),
+                                        generic.position,
+                                    )
+                                })?;
+                        }
+
+                        Some(Analyzer::new_with_symbol_table(Vec::new(), symbol_table))
+                    }
+                    None => None,
+                };
+                let analyzer = scope.as_ref().unwrap_or(self);
+
+                DataType::new(
+                    DataTypeKind::Fn(FunctionType {
+                        parameters: function_type
+                            .parameters
+                            .iter()
+                            .map(|(data_type, kind)| {
+                                Ok((analyzer.analyze_data_type(data_type)?, *kind))
+                            })
+                            .collect::<SemanticResult<Vec<_>>>()?,
+                        return_type: Box::new(
+                            analyzer.analyze_data_type(&function_type.return_type)?,
+                        ),
+                        ..function_type
+                    }),
+                    data_type.position,
+                )
+            }
+            // The size is only checked against array literals bound with `let`, so it is dropped here.
+            DataTypeKind::FixedArray(element, size) => {
+                self.array_size(&size, data_type.position)?;
+
+                DataType::new(
+                    DataTypeKind::Array(Box::new(self.analyze_data_type(&element)?)),
+                    data_type.position,
+                )
+            }
+            // Only functions can be generic, so no generic type can be declared.
+            DataTypeKind::Generic(generic) => {
+                return Err(SemanticError::type_alias_not_defined(
+                    generic,
+                    data_type.position,
+                ))
+            }
+            _ => data_type.clone(),
+        })
+    }
+
+    /// Evaluates the size of a fixed-size array type, which must be a non-negative integer.
+    fn array_size(&self, size: &Expression, position: Position) -> SemanticResult<usize> {
+        /// How deeply calls of `const fn`s are folded, so recursive ones can't overflow the stack.
+        const MAX_DEPTH: usize = 64;
+
+        fn constant(
+            expression: &Expression,
+            symbol_table: &SymbolTable,
+            parameters: &HashMap<String, f64>,
+            depth: usize,
+        ) -> Option<f64> {
+            let fold = |expression| constant(expression, symbol_table, parameters, depth);
+
+            match expression {
+                Expression::Literal(Literal::NumberLiteral(number)) => Some(number.value),
+                Expression::Literal(Literal::Identifier(identifier)) => {
+                    parameters.get(&identifier.value).copied()
+                }
+                Expression::PrefixExpression(PrefixExpression {
+                    operator: PrefixOperator::Minus,
+                    right,
+                    ..
+                }) => fold(right).map(|value| -value),
+                Expression::InfixExpression(infix) => {
+                    let (left, right) = (fold(&infix.left)?, fold(&infix.right)?);
+
+                    match infix.operator {
+                        InfixOperator::Plus => Some(left + right),
+                        InfixOperator::Minus => Some(left - right),
+                        InfixOperator::Asterisk => Some(left * right),
+                        InfixOperator::Slash => Some(left / right),
+                        InfixOperator::Percent => Some(left % right),
+                        InfixOperator::Pow => Some(left.powf(right)),
+                        _ => None,
+                    }
+                }
+                Expression::CallExpression(call) if depth < MAX_DEPTH => {
+                    let Expression::Literal(Literal::Identifier(identifier)) =
+                        call.function.as_ref()
+                    else {
+                        return None;
+                    };
+                    let function = symbol_table
+                        .variable(&identifier.value)?
+                        .attributes
+                        .const_function
+                        .as_ref()?;
+
+                    if function.parameters.len() != call.arguments.len() {
+                        return None;
+                    }
+
+                    let arguments = function
+                        .parameters
+                        .iter()
+                        .zip(&call.arguments)
+                        .map(|(parameter, argument)| {
+                            Some((parameter.identifier.value.clone(), fold(argument)?))
+                        })
+                        .collect::<Option<HashMap<_, _>>>()?;
+
+                    let body = match function.body.statements.as_slice() {
+                        [Statement::ReturnExpressionStatement(statement)] => &statement.value,
+                        [Statement::ReturnStatement(ReturnStatement {
+                            value: Some(value), ..
+                        })] => value,
+                        _ => return None,
+                    };
+
+                    constant(body, symbol_table, &arguments, depth + 1)
+                }
+                _ => None,
+            }
+        }
+
+        match constant(size, &self.symbol_table, &HashMap::new(), 0) {
+            Some(value) if value >= 0.0 && value.fract() == 0.0 => Ok(value as usize),
+            // Rejected here rather than wrapped or truncated by `as usize`.
+            Some(value) => Err(SemanticError::new(
+                SemanticErrorKind::InvalidArraySize(value.to_string()),
+                position,
+            )),
+            None => Err(SemanticError::new(
+                SemanticErrorKind::NonConstantArraySize,
+                position,
+            )),
         }
     }
+}
+
+#[cfg(test)]
+mod type_tests {
+    use super::*;
+    use crate::{
+        difference::TypeDifference, symbol_entry, symbol_table, SemanticErrorKind,
+        SemanticWarningKind,
+    };
+    use parser::ast::{
+        DataType, DataTypeKind, Expression, ExpressionStatement, Identifier, InfixExpression,
+        Literal, NumberLiteral, Position, RangeExpression, ReturnStatement, Statement,
+        StringLiteral,
+    };
+
+    #[test]
+    fn test_typeof_block_expression() {
+        let parent = {
+            let mut symbol_table = SymbolTable::new(None);
+            symbol_table
+                .insert("x", symbol_entry!(Number, Variable))
+                .unwrap();
+            symbol_table
+        };
+
+        let symbol_table = SymbolTable::new(Some(parent));
+
+        let expression = Expression::BlockExpression(BlockExpression {
+            statements: vec![Statement::ReturnStatement(ReturnStatement {
+                value: Some(Expression::InfixExpression(InfixExpression {
+                    left: Box::new(Expression::Literal(Literal::Identifier(Identifier {
+                        value: String::from("x"),
+                        position: Position::default(),
+                    }))),
+                    operator: InfixOperator::Plus,
+                    right: Box::new(Expression::Literal(Literal::NumberLiteral(NumberLiteral {
+                        value: 1.0,
+                        position: Position::default(),
+                    }))),
+                    position: Position::default(),
+                })),
+                position: Position::default(),
+            })],
+            position: Position::default(),
+        });
+
+        let ttype = Analyzer::new_with_symbol_table(Program::new(), symbol_table)
+            .typeof_expression(&expression)
+            .unwrap();
+
+        assert_eq!(
+            ttype,
+            DataType::new(DataTypeKind::Number, Position::default())
+        );
+    }
+
+    #[test]
+    fn test_typeof_infix_expression() {
+        let expression = Expression::InfixExpression(InfixExpression {
+            left: Box::new(Expression::Literal(Literal::NumberLiteral(NumberLiteral {
+                value: 1.0,
+                position: Position::default(),
+            }))),
+            operator: InfixOperator::Plus,
+            right: Box::new(Expression::Literal(Literal::NumberLiteral(NumberLiteral {
+                value: 2.0,
+                position: Position::default(),
+            }))),
+            position: Position::default(),
+        });
+
+        let ttype = Analyzer::new(Program::new())
+            .typeof_expression(&expression)
+            .unwrap();
+
+        assert_eq!(
+            ttype,
+            DataType::new(DataTypeKind::Number, Position::default())
+        );
+    }
+
+    #[test]
+    fn test_typeof_literal() {
+        let expression = Expression::Literal(Literal::StringLiteral(StringLiteral {
+            value: String::from("x"),
+            position: Position::default(),
+        }));
+
+        let ttype = Analyzer::new(Program::new())
+            .typeof_expression(&expression)
+            .unwrap();
+
+        assert_eq!(
+            ttype,
+            DataType::new(DataTypeKind::String, Position::default())
+        );
+    }
+
+    #[test]
+    fn test_typeof_literal_2() {
+        let symbol_table = symbol_table! {
+            x => Variable, Number;
+        };
+
+        let expression = Expression::Literal(Literal::Identifier(Identifier {
+            value: String::from("x"),
+            position: Position::default(),
+        }));
+
+        let ttype = Analyzer::new_with_symbol_table(Program::new(), symbol_table)
+            .typeof_expression(&expression)
+            .unwrap();
+
+        assert_eq!(
+            ttype,
+            DataType::new(DataTypeKind::Number, Position::default())
+        );
+    }
+
+    #[test]
+    fn test_typeof_array_literal() {
+        let expression = Expression::Literal(Literal::ArrayLiteral(ArrayLiteral {
+            elements: vec![
+                Expression::Literal(Literal::NumberLiteral(NumberLiteral {
+                    value: 1.0,
+                    position: Position::default(),
+                })),
+                Expression::Literal(Literal::NumberLiteral(NumberLiteral {
+                    value: 2.0,
+                    position: Position::default(),
+                })),
+            ],
+            position: Position::default(),
+        }));
+
+        let ttype = Analyzer::new(Program::new())
+            .typeof_expression(&expression)
+            .unwrap();
+
+        assert_eq!(
+            ttype,
+            DataType::new(
+                DataTypeKind::Array(Box::new(DataType::new(
+                    DataTypeKind::Number,
+                    Position::default()
+                ))),
+                Position::default()
+            )
+        );
+    }
+
+    #[test]
+    fn test_typeof_data_type() {
+        let symbol_table = symbol_table! {
+            x => Variable, Number;
+            X => Named, String;
+        };
+
+        let data_type = DataType::new(DataTypeKind::Custom(String::from("X")), Position::default());
+
+        let ttype = Analyzer::new_with_symbol_table(Program::new(), symbol_table)
+            .analyze_data_type(&data_type)
+            .unwrap();
+
+        assert_eq!(
+            ttype,
+            DataType::new(DataTypeKind::String, Position::default())
+        );
+    }
+
+    fn analyze(source: &str) -> SemanticResult<AnalyzerReturnKind> {
+        let program = parser::Parser::from(source).parse_program().unwrap();
+
+        Analyzer::new(program).analyze()
+    }
+
+    #[test]
+    fn test_bind_void() {
+        let error = analyze(
+            r#"
+            declare f = fn() -> void;
+            let x = f();
+            "#,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.kind,
+            SemanticErrorKind::CannotBindVoid(String::from("x"))
+        );
+
+        let error = analyze(
+            r#"
+            declare f = fn() -> void;
+            let x: void = f();
+            "#,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.kind,
+            SemanticErrorKind::CannotBindVoid(String::from("x"))
+        );
+    }
+
+    #[test]
+    fn test_void_call_statement() {
+        assert!(analyze(
+            r#"
+            declare f = fn(number) -> void;
+            f(1);
+            "#,
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_generic_function_type() {
+        for source in [
+            "declare f = fn(Box<number>) -> void;",
+            "declare f = fn(number) -> Box<number>;",
+            "let f: fn(Box<number>) -> number = fn(x: number) -> number { return x; };",
+        ] {
+            assert!(matches!(
+                analyze(source).unwrap_err().kind,
+                SemanticErrorKind::TypeAliasNotDefined(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_constant_index_out_of_bounds() {
+        let error = analyze("let x = [1, 2, 3][5];").unwrap_err();
+
+        assert_eq!(
+            error.kind,
+            SemanticErrorKind::IndexOutOfBounds(String::from("5"), String::from("3"))
+        );
+    }
+
+    #[test]
+    fn test_variable_index() {
+        assert!(analyze(
+            r#"
+            let i = 5;
+            let x: number = [1, 2, 3][i];
+            "#,
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_dump_symbols() {
+        let program = parser::Parser::from("mut count = 1;")
+            .parse_program()
+            .unwrap();
+
+        let mut analyzer = Analyzer::new(program);
+        analyzer.analyze().unwrap();
+
+        assert!(analyzer
+            .symbol_table
+            .to_string()
+            .contains("variable count: Number (mutable)"));
+    }
+
+    fn warnings(source: &str) -> Vec<SemanticWarningKind> {
+        let program = parser::Parser::from(source).parse_program().unwrap();
+
+        let mut analyzer = Analyzer::new(program);
+        analyzer.analyze().unwrap();
+
+        analyzer
+            .warnings
+            .into_iter()
+            .map(|warning| warning.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_self_assignment_warning() {
+        assert_eq!(
+            warnings("mut x = 1; x = x;"),
+            vec![SemanticWarningKind::SelfAssignment(String::from("x"))]
+        );
+    }
+
+    #[test]
+    fn test_unused_expression_warning() {
+        assert_eq!(
+            warnings("let x = 1; 5; { x; };"),
+            vec![
+                SemanticWarningKind::UnusedExpression,
+                SemanticWarningKind::UnusedExpression
+            ]
+        );
+        assert_eq!(
+            warnings("let _ = 1;"),
+            vec![SemanticWarningKind::DiscardedBinding]
+        );
+
+        // Calls and assignments are what a sequence is for, so it has an effect.
+        assert_eq!(
+            warnings("mut x = 1; let y = (1, x, x + 1);"),
+            vec![
+                SemanticWarningKind::UnusedExpression,
+                SemanticWarningKind::UnusedExpression
+            ]
+        );
+        assert!(warnings("mut x = 1; let y = (0, x = 2, x);").is_empty());
+        assert!(warnings("declare f = fn() -> void; let y = (1, f(), 2);").is_empty());
+    }
+
+    #[test]
+    fn test_nested_type_mismatch() {
+        let error = analyze(
+            r#"
+            declare f = fn(number[][]) -> void;
+            let x: string[][] = [["a"]];
+            f(x);
+            "#,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.kind,
+            SemanticErrorKind::NestedTypeMismatch(
+                String::from("String[][]"),
+                String::from("Number[][]"),
+                Box::new(TypeDifference {
+                    path: vec![String::from("element"), String::from("element")],
+                    left: DataTypeKind::String,
+                    right: DataTypeKind::Number,
+                })
+            )
+        );
+    }
+
+    #[test]
+    fn test_empty_array_element_type() {
+        assert!(analyze(
+            r#"
+            declare push = fn(number[], number) -> number[];
+            let x: number[] = [];
+            let y: number[][] = [[], [1]];
+            push(x, 1);
+            "#,
+        )
+        .is_ok());
+
+        assert_eq!(
+            analyze(
+                r#"
+                declare push = fn(number[], number) -> number[];
+                let x: number[] = [];
+                push(x, "a");
+                "#,
+            )
+            .unwrap_err()
+            .kind,
+            SemanticErrorKind::TypeMismatch(String::from("String"), String::from("Number"))
+        );
+
+        assert_eq!(
+            analyze("let x = [];").unwrap_err().kind,
+            SemanticErrorKind::TypeAnnotationNeeded
+        );
+    }
+
+    #[test]
+    fn test_bare_return() {
+        assert!(matches!(
+            analyze("return;"),
+            Ok(AnalyzerReturnKind::EarlyReturn(DataTypeKind::Void))
+        ));
+
+        assert_eq!(
+            analyze("let x: number = { return; 1 };").unwrap_err().kind,
+            SemanticErrorKind::TypeMismatch(String::from("Void"), String::from("Number"))
+        );
+    }
+
+    #[test]
+    fn test_return_matches_function_type() {
+        assert!(analyze(
+            r#"
+            let f = fn(x: number) -> number {
+                let y = {
+                    return x;
+                };
+
+                return 1;
+            };
+            "#,
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_return_mismatches_function_type() {
+        assert_eq!(
+            analyze(r#"let f = fn() -> number { return "a"; };"#)
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::TypeMismatch(String::from("String"), String::from("Number"))
+        );
+
+        assert_eq!(
+            analyze(r#"let f = fn() -> number { "a" };"#)
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::TypeMismatch(String::from("String"), String::from("Number"))
+        );
+    }
+
+    /// A `return` in a branch doesn't end the function, so the statements after the `if` are still checked.
+    #[test]
+    fn test_conditional_return() {
+        assert!(analyze(
+            r#"let f = fn(c: boolean) -> number { if c { return 1; } else { return 2; }; };"#
+        )
+        .is_ok());
+        assert!(warnings("let f = fn(c: boolean) -> number { if c { return 1; }; 2 };").is_empty());
+        assert_eq!(
+            analyze(r#"let f = fn(c: boolean) -> number { if c { return 1; }; return "x"; };"#)
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::TypeMismatch(String::from("String"), String::from("Number"))
+        );
+    }
+
+    #[test]
+    fn test_union_return() {
+        let return_type = |source: &str| -> SemanticResult<String> {
+            let mut analyzer = Analyzer::new(parser::Parser::from(source).parse_program().unwrap());
+            analyzer.unions = true;
+            analyzer.analyze()?;
+
+            Ok(
+                match &analyzer.symbol_table.lookup("f").unwrap().data_type.kind {
+                    DataTypeKind::Fn(function) => function.return_type.kind.to_string(),
+                    kind => panic!("expected a function, got {kind}"),
+                },
+            )
+        };
+        let branches = r#"let f = fn(c: boolean) { if c { return 1; } else { return "x"; }; };"#;
+
+        assert_eq!(return_type(branches), Ok(String::from("Number | String")));
+        assert_eq!(
+            return_type(r#"let f = fn(c: boolean) { if c { return 1; }; "x" };"#),
+            Ok(String::from("Number | String"))
+        );
+        assert_eq!(
+            return_type("let f = fn(c: boolean) { while c { return 1; }; };"),
+            Ok(String::from("Number | Void"))
+        );
+        assert_eq!(
+            return_type("let f = fn(c: boolean) { if c { return 1; }; 2 };"),
+            Ok(String::from("Number"))
+        );
+        assert_eq!(return_type("let f = fn() {};"), Ok(String::from("Void")));
+
+        // A union is only assignable where each of its types is.
+        assert_eq!(
+            return_type(&format!("{branches} let x: number = f(true);"))
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::TypeMismatch(
+                String::from("Number | String"),
+                String::from("Number")
+            )
+        );
+
+        // An annotation is still checked, and without union types the return type is `void`.
+        assert_eq!(
+            return_type(
+                r#"let f = fn(c: boolean) -> number { if c { return 1; } else { return "x"; }; };"#
+            )
+            .unwrap_err()
+            .kind,
+            SemanticErrorKind::TypeMismatch(String::from("String"), String::from("Number"))
+        );
+        assert_eq!(
+            analyze(branches).unwrap_err().kind,
+            SemanticErrorKind::TypeMismatch(String::from("Number"), String::from("Void"))
+        );
+    }
+
+    #[test]
+    fn test_cast() {
+        assert!(analyze(r#"let x: number = "1" as number;"#).is_ok());
+
+        assert_eq!(
+            analyze("let x = [1] as number;").unwrap_err().kind,
+            SemanticErrorKind::OperatorNotSupported(String::from("as"), String::from("Number[]"))
+        );
+    }
+
+    #[test]
+    fn test_type_mismatch_suggestion() {
+        assert_eq!(
+            analyze("let x: number = true;").unwrap_err().suggestion,
+            Some(String::from("change the annotation to `boolean`"))
+        );
+        assert_eq!(
+            analyze("let x: string = 1;").unwrap_err().suggestion,
+            Some(String::from("convert the value with `as string`"))
+        );
+        assert_eq!(
+            analyze("let x: number[] = [\"a\"];")
+                .unwrap_err()
+                .suggestion,
+            Some(String::from("change the annotation to `string[]`"))
+        );
+    }
+
+    #[test]
+    fn test_spread_parameter() {
+        let sum = "let sum = fn(first: number, spread rest: number[]) -> number { first };";
+
+        assert!(analyze(&format!("{sum} let x: number = sum(1, 2, 3);")).is_ok());
+        assert!(analyze(&format!("{sum} let x: number = sum(1);")).is_ok());
+        assert_eq!(
+            analyze(&format!(r#"{sum} sum(1, 2, "a");"#))
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::TypeMismatch(String::from("String"), String::from("Number"))
+        );
+
+        let error = analyze("let f = fn(spread rest: number) {};").unwrap_err();
+
+        assert_eq!(
+            error.kind,
+            SemanticErrorKind::TypeMismatch(String::from("Number"), String::from("Number[]"))
+        );
+        assert_eq!(
+            error.suggestion,
+            Some(String::from("annotate the spread parameter as `number[]`"))
+        );
+    }
+
+    #[test]
+    fn test_optional_if() {
+        let optional =
+            |kind| DataTypeKind::Optional(Box::new(DataType::new(kind, Position::default())));
+
+        assert_eq!(
+            analyze("if 1 > 2 { 1 }"),
+            Ok(AnalyzerReturnKind::Expression(optional(
+                DataTypeKind::Number
+            )))
+        );
+        assert!(analyze("let x: number? = if true { 1 }; let y: number? = 2;").is_ok());
+        assert!(analyze("let x = if true { 1 }; let y: number = x ?? 0;").is_ok());
+        assert_eq!(
+            analyze("let x: number = if true { 1 };").unwrap_err().kind,
+            SemanticErrorKind::TypeMismatch(String::from("Number?"), String::from("Number"))
+        );
+        assert_eq!(
+            analyze(r#"let x: string? = if true { 1 };"#)
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::TypeMismatch(String::from("Number?"), String::from("String?"))
+        );
+    }
+
+    #[test]
+    fn test_if_exhaustiveness() {
+        assert!(analyze("let x: number = if 1 > 2 { 1 } else if true { 2 } else { 3 };").is_ok());
+        assert!(warnings("let x = 1; if x > 0 { x } else { 0 };").is_empty());
+        assert_eq!(
+            warnings("let x = 1; if x > 0 { x };"),
+            vec![SemanticWarningKind::MissingElse(String::from("Number"))]
+        );
+        assert!(warnings("let x = 1; let y: number? = if x > 0 { x };").is_empty());
+        assert_eq!(
+            analyze(r#"if true { 1 } else { "a" };"#).unwrap_err().kind,
+            SemanticErrorKind::TypeMismatch(String::from("String"), String::from("Number"))
+        );
+    }
+
+    #[test]
+    fn test_index_typing() {
+        assert!(analyze("let a = [1, 2]; let x: number = a[0];").is_ok());
+        assert_eq!(
+            analyze("let a = [1, 2]; let y: string = a[0];")
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::TypeMismatch(String::from("Number"), String::from("String"))
+        );
+
+        let point = "struct Point { x: number, label: string } let p = struct Point { x: 1, label: \"a\" };";
+
+        assert!(analyze(&format!(
+            r#"{point} let x: number = p["x"]; let l: string = p["label"];"#
+        ))
+        .is_ok());
+        assert_eq!(
+            analyze(&format!(r#"{point} let x: string = p["x"];"#))
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::TypeMismatch(String::from("Number"), String::from("String"))
+        );
+        assert_eq!(
+            analyze(&format!(r#"{point} p["z"];"#)).unwrap_err().kind,
+            SemanticErrorKind::FieldNotDefined(String::from("z"), String::from("Point"))
+        );
+    }
+
+    #[test]
+    fn test_fixed_array_size() {
+        assert!(analyze("let a: number[3] = [1, 2, 3]; let b: number = a[0];").is_ok());
+        assert!(analyze("let a: number[2 * 2 - 1] = [1, 2, 3];").is_ok());
+        assert_eq!(
+            analyze("let a: number[3] = [1, 2];").unwrap_err().kind,
+            SemanticErrorKind::ArrayLengthMismatch(String::from("3"), String::from("2"))
+        );
+        assert_eq!(
+            analyze("let n = 3; let a: number[n] = [1, 2, 3];")
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::NonConstantArraySize
+        );
+        assert_eq!(
+            analyze("let a: number[1.5] = [1];").unwrap_err().kind,
+            SemanticErrorKind::InvalidArraySize(String::from("1.5"))
+        );
+        assert_eq!(
+            analyze("let a: number[-1] = [];").unwrap_err().kind,
+            SemanticErrorKind::InvalidArraySize(String::from("-1"))
+        );
+        assert_eq!(
+            analyze("let a: number[1 - 3][] = [];").unwrap_err().kind,
+            SemanticErrorKind::InvalidArraySize(String::from("-2"))
+        );
+    }
 
-    pub fn typeof_expression(&mut self, expression: &Expression) -> SemanticResult<DataType> {
-        self.typeof_expression_with_provided_type(expression, None)
+    #[test]
+    fn test_shadow_declared_builtin() {
+        let shadowed =
+            "declare len = fn(string) -> number; let len = fn(xs: number[]) -> number[] { xs };";
+
+        assert!(analyze(&format!(
+            "{shadowed} let f: fn(number[]) -> number[] = len; let xs: number[] = len([1]);"
+        ))
+        .is_ok());
+        assert_eq!(
+            analyze(&format!("{shadowed} let n: number = len(\"a\");"))
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::TypeMismatch(String::from("String"), String::from("Number[]"))
+        );
+        assert_eq!(
+            analyze("let len = 1; declare len = fn(string) -> number;")
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::IdentifierAlreadyDefined(String::from("len"))
+        );
     }
 
-    pub fn analyze_data_type(&self, data_type: &DataType) -> SemanticResult<DataType> {
-        Ok(match data_type.kind.clone() {
-            DataTypeKind::Custom(identifier) => self
-                .symbol_table
-                .named(&identifier)
-                .ok_or_else(|| {
-                    SemanticError::type_alias_not_defined(identifier, data_type.position)
-                })?
-                .data_type
-                .clone(),
-            DataTypeKind::Array(data_type) => DataType::new(
-                DataTypeKind::Array(Box::new(self.analyze_data_type(&data_type)?)),
-                data_type.position,
-            ),
-            DataTypeKind::Generic(_) | DataTypeKind::Fn(_) => {
-                unimplemented!()
-            }
-            _ => data_type.clone(),
-        })
+    #[test]
+    fn test_member_access() {
+        let point = "struct Point { x: number } let p = struct Point { x: 1 };";
+
+        assert!(analyze(&format!("{point} let x: number = p.x;")).is_ok());
+        assert!(analyze(&format!(
+            "{point} let q: Point? = p; let x: number? = q?.x;"
+        ))
+        .is_ok());
+        assert_eq!(
+            analyze(&format!("{point} p.z;")).unwrap_err().kind,
+            SemanticErrorKind::FieldNotDefined(String::from("z"), String::from("Point"))
+        );
+        assert_eq!(
+            analyze("let n = 1; n.x;").unwrap_err().kind,
+            SemanticErrorKind::FieldNotDefined(String::from("x"), String::from("Number"))
+        );
+        assert_eq!(
+            analyze(&format!("{point} let q: Point? = p; let x: number = q?.x;"))
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::TypeMismatch(String::from("Number?"), String::from("Number"))
+        );
+        assert_eq!(
+            analyze(&format!("{point} let q: Point? = p; q.x;"))
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::FieldNotDefined(String::from("x"), String::from("Point?"))
+        );
+        assert!(analyze(&format!("{point} let x: number = p?.x;")).is_ok());
     }
-}
 
-#[cfg(test)]
-mod type_tests {
-    use super::*;
-    use crate::{symbol_entry, symbol_table};
-    use parser::ast::{
-        DataType, DataTypeKind, Expression, Identifier, InfixExpression, Literal, NumberLiteral,
-        Position, ReturnStatement, Statement, StringLiteral,
-    };
+    #[test]
+    fn test_null_literal() {
+        assert!(analyze("let x: number? = null; let y: number = x ?? 1;").is_ok());
+        assert!(analyze("let x: string[]? = null;").is_ok());
+        assert!(analyze("let h = fn(x: number?) -> number { x ?? 0 }; let y = h(null);").is_ok());
+        assert!(analyze("let f = fn(c: boolean) -> number? { if c { return null; }; 1 };").is_ok());
+        assert_eq!(
+            analyze("let h = fn(x: number) -> number { x }; let y = h(null);")
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::TypeAnnotationNeeded
+        );
+        assert_eq!(
+            analyze("let x = null;").unwrap_err().kind,
+            SemanticErrorKind::TypeAnnotationNeeded
+        );
+        assert_eq!(
+            analyze("let x: number = null;").unwrap_err().kind,
+            SemanticErrorKind::TypeAnnotationNeeded
+        );
+    }
 
     #[test]
-    fn test_typeof_block_expression() {
-        let parent = {
-            let mut symbol_table = SymbolTable::new(None);
-            symbol_table
-                .insert("x", symbol_entry!(Number, Variable))
-                .unwrap();
-            symbol_table
+    fn test_unsupported_expressions() {
+        assert!(matches!(
+            analyze("let x: Box<number> = 1;").unwrap_err().kind,
+            SemanticErrorKind::TypeAliasNotDefined(_)
+        ));
+
+        // The parser never produces these outside of an index or a failed parse.
+        let malformed = |expression| {
+            Analyzer::new(vec![Statement::ExpressionStatement(ExpressionStatement {
+                expression,
+                position: Position::default(),
+            })])
+            .analyze()
+            .unwrap_err()
+            .kind
         };
 
-        let symbol_table = SymbolTable::new(Some(parent));
-
-        let expression = Expression::BlockExpression(BlockExpression {
-            statements: vec![Statement::ReturnStatement(ReturnStatement {
-                value: Expression::InfixExpression(InfixExpression {
-                    left: Box::new(Expression::Literal(Literal::Identifier(Identifier {
-                        value: String::from("x"),
-                        position: Position::default(),
-                    }))),
-                    operator: InfixOperator::Plus,
-                    right: Box::new(Expression::Literal(Literal::NumberLiteral(NumberLiteral {
-                        value: 1.0,
-                        position: Position::default(),
-                    }))),
-                    position: Position::default(),
-                }),
+        assert_eq!(
+            malformed(Expression::Error(Position::default())),
+            SemanticErrorKind::InternalError(String::from(
+                "a malformed expression can't be analyzed"
+            ))
+        );
+        assert_eq!(
+            malformed(Expression::RangeExpression(RangeExpression {
+                start: None,
+                end: None,
                 position: Position::default(),
-            })],
-            position: Position::default(),
-        });
+            })),
+            SemanticErrorKind::InternalError(String::from("a range can only be used as an index"))
+        );
+    }
 
-        let ttype = Analyzer::new_with_symbol_table(Program::new(), symbol_table)
-            .typeof_expression(&expression)
-            .unwrap();
+    #[test]
+    fn test_parameter_patterns() {
+        let point = "struct P { x: number, y: number }";
+
+        assert!(analyze(&format!(
+            "{point} let dist = fn({{x, y}}: P) -> number {{ x * x + y * y }}; let d: number = dist(struct P {{ x: 3, y: 4 }});"
+        ))
+        .is_ok());
+        assert!(analyze(
+            "let swap = fn([a, b]: number[2]) -> number[] { [b, a] }; let s: number[] = swap([1, 2]);"
+        )
+        .is_ok());
+        assert_eq!(
+            analyze(&format!(
+                "{point} let f = fn({{x, z}}: P) -> number {{ x }};"
+            ))
+            .unwrap_err()
+            .kind,
+            SemanticErrorKind::FieldNotDefined(String::from("z"), String::from("P"))
+        );
+        assert_eq!(
+            analyze("let f = fn([a, a]: number[]) -> number { a };")
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::IdentifierAlreadyDefined(String::from("a"))
+        );
+        assert_eq!(
+            analyze("let f = fn({a}: number) -> number { a };")
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::PatternMismatch(String::from("{a}"), String::from("Number"))
+        );
+    }
 
+    #[test]
+    fn test_const_function_size() {
+        let square = "let square = const fn(x: number) -> number { x * x };";
+
+        assert!(analyze(&format!(
+            "{square} let a: number[square(2)] = [1, 2, 3, 4];"
+        ))
+        .is_ok());
+        assert!(analyze(&format!(
+            "{square} let twice = const fn(x: number) -> number {{ return square(x) * 2; }}; let a: number[twice(1) + 1] = [1, 2, 3];"
+        ))
+        .is_ok());
         assert_eq!(
-            ttype,
-            DataType::new(DataTypeKind::Number, Position::default())
+            analyze(&format!("{square} let a: number[square(2)] = [1, 2];"))
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::ArrayLengthMismatch(String::from("4"), String::from("2"))
+        );
+        // A call with a non-constant argument isn't folded, but still analyzes as a normal call.
+        assert!(analyze(&format!("{square} let n = 3; let y = square(n);")).is_ok());
+        assert_eq!(
+            analyze(&format!(
+                "{square} let n = 3; let a: number[square(n)] = [1];"
+            ))
+            .unwrap_err()
+            .kind,
+            SemanticErrorKind::NonConstantArraySize
+        );
+        assert_eq!(
+            analyze(
+                "let square = fn(x: number) -> number { x * x }; let a: number[square(1)] = [1];"
+            )
+            .unwrap_err()
+            .kind,
+            SemanticErrorKind::NonConstantArraySize
         );
     }
 
     #[test]
-    fn test_typeof_infix_expression() {
-        let expression = Expression::InfixExpression(InfixExpression {
-            left: Box::new(Expression::Literal(Literal::NumberLiteral(NumberLiteral {
-                value: 1.0,
-                position: Position::default(),
-            }))),
-            operator: InfixOperator::Plus,
-            right: Box::new(Expression::Literal(Literal::NumberLiteral(NumberLiteral {
-                value: 2.0,
-                position: Position::default(),
-            }))),
-            position: Position::default(),
-        });
+    fn test_undefined_identifier() {
+        assert!(analyze("let x = 1; let y = x + 1;").is_ok());
+        assert!(analyze("declare sort = fn(number[]) -> number[]; let x = sort([2, 1]);").is_ok());
 
-        let ttype = Analyzer::new(Program::new())
-            .typeof_expression(&expression)
-            .unwrap();
+        let error = analyze("let x = 1;\nlet y = x + z;").unwrap_err();
 
         assert_eq!(
-            ttype,
-            DataType::new(DataTypeKind::Number, Position::default())
+            error.kind,
+            SemanticErrorKind::IdentifierNotDefined(String::from("z"))
         );
+        assert_eq!((error.position.0, error.position.1), (2, 13));
     }
 
     #[test]
-    fn test_typeof_literal() {
-        let expression = Expression::Literal(Literal::StringLiteral(StringLiteral {
-            value: String::from("x"),
-            position: Position::default(),
-        }));
+    fn test_try_expression() {
+        assert!(analyze(r#"let x: string = try { "ok" } catch (e) { e };"#).is_ok());
+        assert_eq!(
+            analyze("try { 1 } catch (e) { e };").unwrap_err().kind,
+            SemanticErrorKind::TypeMismatch(String::from("String"), String::from("Number"))
+        );
+    }
 
-        let ttype = Analyzer::new(Program::new())
-            .typeof_expression(&expression)
-            .unwrap();
+    #[test]
+    fn test_match_expression() {
+        assert!(analyze(
+            r#"let n = 1; let s: string = match n { 0 => "zero", m if m > 0 => "positive", _ => "negative" };"#
+        )
+        .is_ok());
+        assert!(
+            analyze("let n: number? = null; let m: number = match n { null => 0, x => 1 };")
+                .is_ok()
+        );
+        assert!(analyze(
+            "let f = fn(n: number) -> number { match n { 0 => { return 1; }, _ => { return n; } }; };"
+        )
+        .is_ok());
 
         assert_eq!(
-            ttype,
-            DataType::new(DataTypeKind::String, Position::default())
+            analyze(r#"let n = 1; match n { "a" => 1, _ => 2 };"#)
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::TypeMismatch(String::from("String"), String::from("Number"))
+        );
+        assert_eq!(
+            analyze("let n = 1; match n { m if m => 1, _ => 2 };")
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::TypeMismatch(String::from("Number"), String::from("Boolean"))
+        );
+        assert_eq!(
+            analyze(r#"let n = 1; match n { 0 => 1, _ => "a" };"#)
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::TypeMismatch(String::from("String"), String::from("Number"))
+        );
+        assert_eq!(
+            analyze("let n = 1; match n { m => m }; m;")
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::IdentifierNotDefined(String::from("m"))
+        );
+        assert_eq!(
+            analyze(
+                r#"let f = fn(n: number) -> number { match n { 0 => { return "a"; }, _ => 1 } };"#
+            )
+            .unwrap_err()
+            .kind,
+            SemanticErrorKind::TypeMismatch(String::from("String"), String::from("Number"))
         );
     }
 
     #[test]
-    fn test_typeof_literal_2() {
-        let symbol_table = symbol_table! {
-            x => Variable, Number;
+    fn test_throw_statement() {
+        assert!(analyze(r#"let x = try { throw "a"; 1 } catch (e) { 0 };"#).is_ok());
+        assert_eq!(
+            analyze("throw 1;").unwrap_err().kind,
+            SemanticErrorKind::TypeMismatch(String::from("Number"), String::from("String"))
+        );
+    }
+
+    #[test]
+    fn test_never_type() {
+        let declare = r#"declare panic = fn(string) -> never;"#;
+
+        assert!(analyze(&format!(r#"{declare} let x: number = panic("x");"#)).is_ok());
+        assert!(analyze(&format!(r#"{declare} let s: string[] = [panic("x")];"#)).is_ok());
+        assert!(analyze("let s: (number?)[] = [1];").is_ok());
+        assert!(analyze(
+            r#"
+            let fail = fn(message: string) -> never { throw message; };
+            let f = fn(x: number) -> number {
+                if x > 0 { x } else { fail("negative") }
+            };
+            let g = fn() -> string { fail("no") };
+            let x: boolean = { throw "no"; };
+            "#
+        )
+        .is_ok());
+
+        assert_eq!(
+            analyze("let x: never = 1;").unwrap_err().kind,
+            SemanticErrorKind::TypeMismatch(String::from("Number"), String::from("Never"))
+        );
+        assert_eq!(
+            analyze("let f = fn() -> never { 1 };").unwrap_err().kind,
+            SemanticErrorKind::TypeMismatch(String::from("Number"), String::from("Never"))
+        );
+        assert_eq!(
+            analyze("let f = fn() -> never { let x = 1; };")
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::MissingReturn(String::from("Never"))
+        );
+        assert_eq!(
+            analyze("let f = fn(x: number) -> number { if x > 0 { return x; }; };")
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::MissingReturn(String::from("Number"))
+        );
+        assert!(analyze("let f = fn() -> void { let x = 1; };").is_ok());
+    }
+
+    #[test]
+    fn test_generic_call_inference() {
+        let declare = "declare map = fn<T, U>(T[], fn(T) -> U) -> U[];";
+
+        assert!(analyze(&format!(
+            "{declare} let xs = map([1, 2, 3], fn(x: number) -> number {{ return x * 2; }}); let ys: number[] = xs;"
+        ))
+        .is_ok());
+        assert!(analyze(&format!(
+            r#"{declare} let xs: string[] = map([1], fn(x: number) -> string {{ "${{x}}" }});"#
+        ))
+        .is_ok());
+
+        assert!(matches!(
+            analyze(&format!(
+                "{declare} let xs: string[] = map([1], fn(x: number) -> number {{ x }});"
+            ))
+            .unwrap_err()
+            .kind,
+            SemanticErrorKind::NestedTypeMismatch(found, expected, _)
+                if found == "Number[]" && expected == "String[]"
+        ));
+        assert!(matches!(
+            analyze(&format!(
+                "{declare} let xs = map([1], fn(x: string) -> string {{ x }});"
+            ))
+            .unwrap_err()
+            .kind,
+            SemanticErrorKind::NestedTypeMismatch(found, expected, _)
+                if found == "fn(String) -> String" && expected == "fn(Number) -> U"
+        ));
+    }
+
+    #[test]
+    fn test_typeof() {
+        assert!(analyze("let x: string = typeof 1;").is_ok());
+        assert!(analyze("let x: string = typeof y;").is_err());
+    }
+
+    #[test]
+    fn test_infix_operands() {
+        let not_supported = |operator: &str, data_type: &str| {
+            SemanticErrorKind::OperatorNotSupported(operator.to_string(), data_type.to_string())
         };
 
-        let expression = Expression::Literal(Literal::Identifier(Identifier {
-            value: String::from("x"),
-            position: Position::default(),
-        }));
+        for (source, error) in [
+            ("true + 1;", not_supported("+", "Boolean")),
+            (r#""a" - "b";"#, not_supported("-", "String")),
+            ("[1] * 2;", not_supported("*", "Number[]")),
+            (r#""a" < "b";"#, not_supported("<", "String")),
+            ("true >= false;", not_supported(">=", "Boolean")),
+            (
+                "1 + true;",
+                SemanticErrorKind::TypeMismatch(String::from("Number"), String::from("Boolean")),
+            ),
+            (
+                "1 == true;",
+                SemanticErrorKind::TypeMismatch(String::from("Number"), String::from("Boolean")),
+            ),
+            (
+                "struct P { x: number } 1 in struct P { x: 1 };",
+                SemanticErrorKind::TypeMismatch(String::from("Number"), String::from("String")),
+            ),
+        ] {
+            assert_eq!(analyze(source).unwrap_err().kind, error, "{source}");
+        }
 
-        let ttype = Analyzer::new_with_symbol_table(Program::new(), symbol_table)
-            .typeof_expression(&expression)
-            .unwrap();
+        for source in [
+            "let x: number = 1 + 2 * 3 ** 2 - 5 / 6;",
+            r#"let x: string = "a" + "b";"#,
+            "let x: boolean = 1 < 2 == true;",
+            r#"let x: boolean = "a" == "b";"#,
+            "let x: boolean = 1 in [1];",
+            r#"struct P { x: number } let x: boolean = "x" in struct P { x: 1 };"#,
+        ] {
+            assert!(analyze(source).is_ok(), "{source}");
+        }
+    }
+
+    #[test]
+    fn test_strict_concatenation() {
+        let analyze = |source: &str, strict: bool| {
+            let mut analyzer = Analyzer::new(parser::Parser::from(source).parse_program().unwrap());
+            analyzer.strict = strict;
+            analyzer.analyze()
+        };
+        let source = r#"let f = fn(n: number) -> string { "count: " + n };"#;
 
+        assert!(analyze(source, false).is_ok());
+        assert!(analyze(r#"let x: string = 5 + "";"#, false).is_ok());
         assert_eq!(
-            ttype,
-            DataType::new(DataTypeKind::Number, Position::default())
+            analyze(source, true).unwrap_err().kind,
+            SemanticErrorKind::TypeMismatch(String::from("String"), String::from("Number"))
         );
     }
 
     #[test]
-    fn test_typeof_array_literal() {
-        let expression = Expression::Literal(Literal::ArrayLiteral(ArrayLiteral {
-            elements: vec![
-                Expression::Literal(Literal::NumberLiteral(NumberLiteral {
-                    value: 1.0,
-                    position: Position::default(),
-                })),
-                Expression::Literal(Literal::NumberLiteral(NumberLiteral {
-                    value: 2.0,
-                    position: Position::default(),
-                })),
-            ],
-            position: Position::default(),
-        }));
+    fn test_struct_literal_keys() {
+        let declare = "struct P { x: number, y: number }";
 
-        let ttype = Analyzer::new(Program::new())
-            .typeof_expression(&expression)
-            .unwrap();
+        assert!(analyze(&format!(
+            r#"{declare} let k = "y"; let p = struct P {{ "x": 1, [k]: 2 }};"#
+        ))
+        .is_ok());
+        assert_eq!(
+            analyze(&format!(
+                r#"{declare} let p = struct P {{ x: 1, "x": 2 }};"#
+            ))
+            .unwrap_err()
+            .kind,
+            SemanticErrorKind::DuplicateField(String::from("x"))
+        );
+        assert_eq!(
+            analyze(&format!("{declare} let p = struct P {{ [1]: 2 }};"))
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::TypeMismatch(String::from("Number"), String::from("String"))
+        );
+        assert_eq!(
+            analyze(r#"struct P { x: number } let k = "x"; let p = struct P { [k]: "s" };"#)
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::TypeMismatch(String::from("String"), String::from("Number"))
+        );
+    }
+
+    #[test]
+    fn test_missing_field() {
+        let declare = "struct P { x: number, y: number }";
 
         assert_eq!(
-            ttype,
-            DataType::new(
-                DataTypeKind::Array(Box::new(DataType::new(
-                    DataTypeKind::Number,
-                    Position::default()
-                ))),
-                Position::default()
-            )
+            analyze(&format!("{declare} let p = struct P {{ x: 1 }};"))
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::MissingField(String::from("y"), String::from("P"))
+        );
+        assert_eq!(
+            analyze(&format!(
+                r#"{declare} let k = "y"; let p = struct P {{ [k]: 1 }};"#
+            ))
+            .unwrap_err()
+            .kind,
+            SemanticErrorKind::MissingField(String::from("x"), String::from("P"))
         );
+        assert!(analyze(&format!("{declare} let p = struct P {{ y: 1, x: 2 }};")).is_ok());
+        assert!(analyze("struct P { x: number? } let p = struct P { x: 1 };").is_ok());
+        assert!(analyze("struct P { x: number? } let p = struct P { x: null };").is_ok());
+        assert!(analyze(
+            r#"declare panic = fn(string) -> never; struct P { x: number } let p = struct P { x: panic("x") };"#
+        )
+        .is_ok());
     }
 
     #[test]
-    fn test_typeof_data_type() {
-        let symbol_table = symbol_table! {
-            x => Variable, Number;
-            X => Named, String;
-        };
+    fn test_recursive_struct() {
+        assert!(analyze(
+            "
+            struct Tree { value: number, children: Tree[] }
+            let leaf = struct Tree { value: 2, children: [] };
+            let tree = struct Tree { value: 1, children: [leaf] };
+            "
+        )
+        .is_ok());
+        assert!(matches!(
+            analyze("struct Tree { value: number, children: Tree[] } let tree = struct Tree { value: 1, children: [1] };")
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::NestedTypeMismatch(..) | SemanticErrorKind::TypeMismatch(..)
+        ));
+        assert_eq!(
+            analyze("type A = A;").unwrap_err().kind,
+            SemanticErrorKind::TypeAliasNotDefined(String::from("A"))
+        );
+        assert_eq!(
+            analyze("type A = A[];").unwrap_err().kind,
+            SemanticErrorKind::TypeAliasNotDefined(String::from("A"))
+        );
+    }
 
-        let data_type = DataType::new(DataTypeKind::Custom(String::from("X")), Position::default());
+    #[test]
+    fn test_while_statement() {
+        assert!(analyze(
+            "
+            mut i = 0;
+            outer: while i < 3 {
+                i = i + 1;
+                while true {
+                    if i == 2 { continue outer; };
+                    break outer;
+                };
+            }
+            "
+        )
+        .is_ok());
+        assert_eq!(
+            analyze("while 1 { }").unwrap_err().kind,
+            SemanticErrorKind::TypeMismatch(String::from("Number"), String::from("Boolean"))
+        );
+        assert_eq!(
+            analyze("outer: while true { while true { break inner; } }")
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::LabelNotDefined(String::from("inner"))
+        );
+        assert_eq!(
+            analyze("break;").unwrap_err().kind,
+            SemanticErrorKind::NotInLoop(String::from("break"))
+        );
+        assert_eq!(
+            analyze("while true { let f = fn() -> void { continue; }; }")
+                .unwrap_err()
+                .kind,
+            SemanticErrorKind::NotInLoop(String::from("continue"))
+        );
+    }
 
-        let ttype = Analyzer::new_with_symbol_table(Program::new(), symbol_table)
-            .analyze_data_type(&data_type)
-            .unwrap();
+    #[test]
+    fn test_analyze_program() {
+        let program = parser::Parser::from(
+            r#"
+            let x: number = "a";
+            let y = x + 1;
+            let z: boolean = 1;
+            let w = undefined;
+            let v: string = w;
+            "#,
+        )
+        .parse_program()
+        .unwrap();
+
+        let errors = Analyzer::new(program)
+            .analyze_program()
+            .unwrap_err()
+            .into_iter()
+            .map(|error| error.kind)
+            .collect::<Vec<_>>();
 
         assert_eq!(
-            ttype,
-            DataType::new(DataTypeKind::String, Position::default())
+            errors,
+            vec![
+                SemanticErrorKind::TypeMismatch(String::from("String"), String::from("Number")),
+                SemanticErrorKind::TypeMismatch(String::from("Number"), String::from("Boolean")),
+                SemanticErrorKind::IdentifierNotDefined(String::from("undefined")),
+            ]
+        );
+
+        let program = parser::Parser::from("let x = 1; x + 1")
+            .parse_program()
+            .unwrap();
+        assert_eq!(
+            Analyzer::new(program).analyze_program(),
+            Ok(AnalyzerReturnKind::Expression(DataTypeKind::Number))
         );
     }
 }
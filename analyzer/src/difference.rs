@@ -0,0 +1,147 @@
+use parser::ast::{DataTypeKind, FunctionType};
+use std::fmt;
+
+/// The innermost part of two composite types that differs, e.g. the element type of `number[][]` and `string[][]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeDifference {
+    /// From the outermost type inwards, e.g. `["parameter 1", "element"]`.
+    pub path: Vec<String>,
+    pub left: DataTypeKind,
+    pub right: DataTypeKind,
+}
+
+impl fmt::Display for TypeDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.join(" > "))
+    }
+}
+
+/// Compares two types, returning `None` if they are equal.
+pub fn difference(left: &DataTypeKind, right: &DataTypeKind) -> Option<TypeDifference> {
+    let nested = |segment: String, difference: Option<TypeDifference>| {
+        difference.map(|mut difference| {
+            difference.path.insert(0, segment);
+            difference
+        })
+    };
+
+    match (left, right) {
+        (DataTypeKind::Array(left), DataTypeKind::Array(right)) => {
+            nested("element".to_string(), difference(&left.kind, &right.kind))
+        }
+        (DataTypeKind::Fn(left), DataTypeKind::Fn(right))
+            if left.parameters.len() == right.parameters.len() =>
+        {
+            function_difference(left, right, nested)
+        }
+        (left, right) if left == right => None,
+        (left, right) => Some(TypeDifference {
+            path: Vec::new(),
+            left: left.clone(),
+            right: right.clone(),
+        }),
+    }
+}
+
+fn function_difference(
+    left: &FunctionType,
+    right: &FunctionType,
+    nested: impl Fn(String, Option<TypeDifference>) -> Option<TypeDifference>,
+) -> Option<TypeDifference> {
+    for (index, ((left, left_kind), (right, right_kind))) in
+        left.parameters.iter().zip(&right.parameters).enumerate()
+    {
+        let segment = format!("parameter {}", index + 1);
+
+        if left_kind != right_kind {
+            return Some(TypeDifference {
+                path: vec![segment],
+                left: left.kind.clone(),
+                right: right.kind.clone(),
+            });
+        }
+
+        if let Some(difference) = nested(segment, difference(&left.kind, &right.kind)) {
+            return Some(difference);
+        }
+    }
+
+    nested(
+        "return type".to_string(),
+        difference(&left.return_type.kind, &right.return_type.kind),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::ast::{DataType, ParameterKind, Position};
+
+    fn data_type(kind: DataTypeKind) -> DataType {
+        DataType::new(kind, Position::default())
+    }
+
+    fn array(kind: DataTypeKind) -> DataTypeKind {
+        DataTypeKind::Array(Box::new(data_type(kind)))
+    }
+
+    fn function(parameters: Vec<DataTypeKind>, return_type: DataTypeKind) -> DataTypeKind {
+        DataTypeKind::Fn(FunctionType {
+            generics: None,
+            parameters: parameters
+                .into_iter()
+                .map(|kind| (data_type(kind), ParameterKind::Normal))
+                .collect(),
+            return_type: Box::new(data_type(return_type)),
+            position: Position::default(),
+        })
+    }
+
+    #[test]
+    fn test_equal() {
+        assert_eq!(
+            difference(
+                &function(vec![array(DataTypeKind::Number)], DataTypeKind::String),
+                &function(vec![array(DataTypeKind::Number)], DataTypeKind::String),
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_nested_difference() {
+        let difference = difference(
+            &function(
+                vec![DataTypeKind::Boolean, array(DataTypeKind::Number)],
+                DataTypeKind::String,
+            ),
+            &function(
+                vec![DataTypeKind::Boolean, array(DataTypeKind::String)],
+                DataTypeKind::String,
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(
+            difference,
+            TypeDifference {
+                path: vec!["parameter 2".to_string(), "element".to_string()],
+                left: DataTypeKind::Number,
+                right: DataTypeKind::String,
+            }
+        );
+        assert_eq!(difference.to_string(), "parameter 2 > element");
+    }
+
+    #[test]
+    fn test_top_level_difference() {
+        assert_eq!(
+            difference(&DataTypeKind::Number, &DataTypeKind::String),
+            Some(TypeDifference {
+                path: Vec::new(),
+                left: DataTypeKind::Number,
+                right: DataTypeKind::String,
+            })
+        );
+    }
+}
@@ -1,17 +1,33 @@
 pub mod analyzer;
+pub mod difference;
 pub mod symbol_table;
 
-use parser::ast::Position;
+use difference::{difference, TypeDifference};
+use parser::ast::{DataTypeKind, Position};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct SemanticError {
     pub kind: SemanticErrorKind,
     pub position: Position,
+    /// A change that would most likely fix the error, e.g. "change the annotation to `string`".
+    pub suggestion: Option<String>,
 }
 
 impl SemanticError {
     pub fn new(kind: SemanticErrorKind, position: Position) -> Self {
-        Self { kind, position }
+        Self {
+            kind,
+            position,
+            suggestion: None,
+        }
+    }
+
+    pub fn with_suggestion<T>(mut self, suggestion: T) -> Self
+    where
+        T: ToString,
+    {
+        self.suggestion = Some(suggestion.to_string());
+        self
     }
 
     pub fn type_mismatch<T>(left: T, right: T, position: Position) -> Self
@@ -24,6 +40,25 @@ impl SemanticError {
         )
     }
 
+    /// Same as `type_mismatch`, but points at the innermost differing part of composite types.
+    pub fn type_mismatch_between(
+        left: &DataTypeKind,
+        right: &DataTypeKind,
+        position: Position,
+    ) -> Self {
+        match difference(left, right) {
+            Some(difference) if !difference.path.is_empty() => Self::new(
+                SemanticErrorKind::NestedTypeMismatch(
+                    left.to_string(),
+                    right.to_string(),
+                    Box::new(difference),
+                ),
+                position,
+            ),
+            _ => Self::type_mismatch(left, right, position),
+        }
+    }
+
     pub fn identifier_not_defined<T>(identifier: T, position: Position) -> Self
     where
         T: ToString,
@@ -44,6 +79,23 @@ impl SemanticError {
         )
     }
 
+    pub fn label_not_defined<T>(label: T, position: Position) -> Self
+    where
+        T: ToString,
+    {
+        Self::new(
+            SemanticErrorKind::LabelNotDefined(label.to_string()),
+            position,
+        )
+    }
+
+    pub fn not_in_loop<T>(keyword: T, position: Position) -> Self
+    where
+        T: ToString,
+    {
+        Self::new(SemanticErrorKind::NotInLoop(keyword.to_string()), position)
+    }
+
     pub fn type_alias_not_defined<T>(type_alias: T, position: Position) -> Self
     where
         T: ToString,
@@ -88,12 +140,105 @@ impl SemanticError {
             position,
         )
     }
+
+    pub fn index_out_of_bounds(index: usize, length: usize, position: Position) -> Self {
+        Self::new(
+            SemanticErrorKind::IndexOutOfBounds(index.to_string(), length.to_string()),
+            position,
+        )
+    }
+
+    pub fn duplicate_field<T>(field: T, position: Position) -> Self
+    where
+        T: ToString,
+    {
+        Self::new(
+            SemanticErrorKind::DuplicateField(field.to_string()),
+            position,
+        )
+    }
+
+    pub fn field_not_defined<F, T>(field: F, data_type: T, position: Position) -> Self
+    where
+        F: ToString,
+        T: ToString,
+    {
+        Self::new(
+            SemanticErrorKind::FieldNotDefined(field.to_string(), data_type.to_string()),
+            position,
+        )
+    }
+
+    pub fn missing_field<F, T>(field: F, data_type: T, position: Position) -> Self
+    where
+        F: ToString,
+        T: ToString,
+    {
+        Self::new(
+            SemanticErrorKind::MissingField(field.to_string(), data_type.to_string()),
+            position,
+        )
+    }
+
+    pub fn missing_return<T>(return_type: T, position: Position) -> Self
+    where
+        T: ToString,
+    {
+        Self::new(
+            SemanticErrorKind::MissingReturn(return_type.to_string()),
+            position,
+        )
+    }
+
+    pub fn internal_error<T>(message: T, position: Position) -> Self
+    where
+        T: ToString,
+    {
+        Self::new(
+            SemanticErrorKind::InternalError(message.to_string()),
+            position,
+        )
+    }
+
+    pub fn cannot_bind_void<T>(identifier: T, position: Position) -> Self
+    where
+        T: ToString,
+    {
+        Self::new(
+            SemanticErrorKind::CannotBindVoid(identifier.to_string()),
+            position,
+        )
+    }
+}
+
+/// Suggests how to make a value of type `found` fit an annotation of type `expected`.
+/// Only values built from primitive types get a suggestion, other annotations are too likely to be intended.
+pub fn suggest_annotation_fix(found: &DataTypeKind, expected: &DataTypeKind) -> Option<String> {
+    fn is_primitive(kind: &DataTypeKind) -> bool {
+        match kind {
+            DataTypeKind::Number | DataTypeKind::String | DataTypeKind::Boolean => true,
+            DataTypeKind::Array(element) => is_primitive(&element.kind),
+            _ => false,
+        }
+    }
+
+    match (found, expected) {
+        (DataTypeKind::Number | DataTypeKind::Boolean, DataTypeKind::String) => {
+            Some(String::from("convert the value with `as string`"))
+        }
+        (found, _) if is_primitive(found) => Some(format!(
+            "change the annotation to `{}`",
+            found.to_string().to_lowercase()
+        )),
+        _ => None,
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 #[rustfmt::skip]
 pub enum SemanticErrorKind {
     TypeMismatch(String, String),
+    NestedTypeMismatch(String, String, Box<TypeDifference>),
     IdentifierNotDefined(String),
     TypeAliasNotDefined(String),
     IdentifierAlreadyDefined(String),
@@ -101,6 +246,75 @@ pub enum SemanticErrorKind {
     OperatorNotSupported(String, String),
     TypeAnnotationNeeded,
     CannotAssignToImmutableVariable(String),
+    CannotBindVoid(String),
+    IndexOutOfBounds(String, String),
+    FieldNotDefined(String, String),
+    DuplicateField(String),
+    MissingField(String, String),
+    MissingReturn(String),
+    NonConstantArraySize,
+    InvalidArraySize(String),
+    PatternMismatch(String, String),
+    ArrayLengthMismatch(String, String),
+    LabelNotDefined(String),
+    NotInLoop(String),
+    /// An AST the analyzer doesn't expect, which the parser shouldn't produce.
+    InternalError(String),
 }
 
 pub type SemanticResult<T> = Result<T, SemanticError>;
+
+/// Reported for code that is valid but most likely a mistake. Warnings never stop the analysis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemanticWarning {
+    pub kind: SemanticWarningKind,
+    pub position: Position,
+}
+
+impl SemanticWarning {
+    pub fn new(kind: SemanticWarningKind, position: Position) -> Self {
+        Self { kind, position }
+    }
+
+    pub fn self_assignment<T>(identifier: T, position: Position) -> Self
+    where
+        T: ToString,
+    {
+        Self::new(
+            SemanticWarningKind::SelfAssignment(identifier.to_string()),
+            position,
+        )
+    }
+
+    pub fn unused_expression(position: Position) -> Self {
+        Self::new(SemanticWarningKind::UnusedExpression, position)
+    }
+
+    pub fn discarded_binding(position: Position) -> Self {
+        Self::new(SemanticWarningKind::DiscardedBinding, position)
+    }
+
+    pub fn missing_else<T>(data_type: T, position: Position) -> Self
+    where
+        T: ToString,
+    {
+        Self::new(
+            SemanticWarningKind::MissingElse(data_type.to_string()),
+            position,
+        )
+    }
+
+    pub fn dangling_statement(position: Position) -> Self {
+        Self::new(SemanticWarningKind::DanglingStatement, position)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[rustfmt::skip]
+pub enum SemanticWarningKind {
+    SelfAssignment(String),
+    UnusedExpression,
+    DiscardedBinding,
+    MissingElse(String),
+    DanglingStatement,
+}
@@ -1,5 +1,5 @@
-use parser::ast::DataType;
-use std::collections::HashMap;
+use parser::ast::{DataType, DataTypeKind, FunctionLiteral};
+use std::{collections::HashMap, fmt};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct SymbolEntry {
@@ -22,6 +22,10 @@ impl SymbolEntry {
 pub struct SymbolAttributes {
     pub is_spread: Option<bool>,
     pub is_mutable: Option<bool>,
+    /// The literal of an immutable `const fn` binding, which calls with constant arguments are folded with.
+    pub const_function: Option<FunctionLiteral>,
+    /// The binding comes from a `declare` statement, which describes a builtin that a binding of the same name replaces.
+    pub is_declared: Option<bool>,
 }
 
 impl SymbolAttributes {
@@ -34,13 +38,58 @@ impl SymbolAttributes {
         self.is_mutable = Some(is_mutable);
         self
     }
+
+    pub fn const_function(mut self, function: Option<FunctionLiteral>) -> Self {
+        self.const_function = function;
+        self
+    }
+
+    pub fn declared(mut self, is_declared: bool) -> Self {
+        self.is_declared = Some(is_declared);
+        self
+    }
+}
+
+impl fmt::Display for SymbolAttributes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut attributes = Vec::new();
+
+        if self.is_mutable == Some(true) {
+            attributes.push("mutable");
+        }
+
+        if self.is_spread == Some(true) {
+            attributes.push("spread");
+        }
+
+        if self.const_function.is_some() {
+            attributes.push("const");
+        }
+
+        if self.is_declared == Some(true) {
+            attributes.push("declared");
+        }
+
+        write!(f, "{}", attributes.join(", "))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum SymbolKind {
     Variable,
     Named,
-    // Struct - todo
+    /// A struct type with its field names and types. Its `data_type` is the struct itself.
+    Struct(Vec<(String, DataType)>),
+}
+
+impl fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymbolKind::Variable => write!(f, "variable"),
+            SymbolKind::Named => write!(f, "type"),
+            SymbolKind::Struct(_) => write!(f, "struct"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -57,8 +106,13 @@ impl SymbolTable {
         }
     }
 
+    /// Fails if the name is already bound in this scope, unless it was only declared.
     pub fn insert(&mut self, name: &str, entry: SymbolEntry) -> Option<()> {
-        if self.entries.contains_key(name) {
+        if self
+            .entries
+            .get(name)
+            .is_some_and(|entry| entry.attributes.is_declared != Some(true))
+        {
             return None;
         }
 
@@ -85,9 +139,66 @@ impl SymbolTable {
             .filter(|entry| entry.kind == SymbolKind::Variable)
     }
 
+    /// Looks up a type alias or a struct.
     pub fn named(&self, name: &str) -> Option<&SymbolEntry> {
         self.lookup(name)
-            .filter(|entry| entry.kind == SymbolKind::Named)
+            .filter(|entry| matches!(entry.kind, SymbolKind::Named | SymbolKind::Struct(_)))
+    }
+
+    /// Returns the type of the field if `data_type` is a struct with such a field.
+    pub fn field(&self, data_type: &DataTypeKind, field: &str) -> Option<&DataType> {
+        let DataTypeKind::Custom(name) = data_type else {
+            return None;
+        };
+
+        match &self.named(name)?.kind {
+            SymbolKind::Struct(fields) => fields
+                .iter()
+                .find(|(name, _)| name == field)
+                .map(|(_, data_type)| data_type),
+            _ => None,
+        }
+    }
+}
+
+/// Human-readable dump of the symbol table, one entry per line sorted by name.
+impl fmt::Display for SymbolTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let depth = match &self.parent {
+            Some(parent) => {
+                write!(f, "{parent}")?;
+                parent.depth() + 1
+            }
+            None => 0,
+        };
+
+        let mut entries = self.entries.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|(name, _)| *name);
+
+        for (name, entry) in entries {
+            write!(
+                f,
+                "{}{} {name}: {}",
+                "  ".repeat(depth),
+                entry.kind,
+                entry.data_type
+            )?;
+
+            let attributes = entry.attributes.to_string();
+            if !attributes.is_empty() {
+                write!(f, " ({attributes})")?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl SymbolTable {
+    fn depth(&self) -> usize {
+        self.parent.as_ref().map_or(0, |parent| parent.depth() + 1)
     }
 }
 
@@ -165,6 +276,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_insert_over_declared() {
+        let mut symbol_table = SymbolTable::new(None);
+
+        let mut declared = symbol_entry!(Number, Variable);
+        declared.attributes = declared.attributes.declared(true);
+        symbol_table.insert("x", declared).unwrap();
+
+        let entry = symbol_entry!(String, Variable);
+        symbol_table.insert("x", entry.clone()).unwrap();
+
+        assert_eq!(symbol_table.entries.get("x"), Some(&entry));
+        assert_eq!(
+            symbol_table.insert("x", symbol_entry!(Number, Variable)),
+            None
+        );
+    }
+
     #[test]
     fn test_lookup() {
         let mut parent = SymbolTable::new(None);
@@ -265,4 +394,26 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_display() {
+        let mut symbol_table = symbol_table! {
+            y => Named, String;
+        };
+        symbol_table
+            .insert(
+                "x",
+                SymbolEntry::new(
+                    DataType::new(DataTypeKind::Number, Position::default()),
+                    SymbolAttributes::default().mutable(true),
+                    SymbolKind::Variable,
+                ),
+            )
+            .unwrap();
+
+        assert_eq!(
+            symbol_table.to_string(),
+            "variable x: Number (mutable)\ntype y: String\n"
+        );
+    }
 }
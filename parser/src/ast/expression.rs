@@ -1,35 +1,243 @@
-use super::{Identifier, Literal, Position, Statement};
+use super::{DataType, FieldKey, Identifier, Literal, Position, Statement};
 use crate::tokenizer::TokenKind;
 use std::fmt;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub enum Expression {
     AssignmentExpression(AssignmentExpression),
     BlockExpression(BlockExpression),
+    SequenceExpression(SequenceExpression),
     PrefixExpression(PrefixExpression),
     InfixExpression(InfixExpression),
     IfExpression(IfExpression),
     CallExpression(CallExpression),
     TypeofExpression(TypeofExpression),
+    TryExpression(TryExpression),
+    MatchExpression(MatchExpression),
+    CastExpression(CastExpression),
     IndexExpression(IndexExpression),
+    RangeExpression(RangeExpression),
+    InterpolationExpression(InterpolationExpression),
     Literal(Literal),
     Debug(Box<Expression>, Position),
+    /// A malformed expression the parser skipped over after reporting it.
+    Error(Position),
 }
 
-#[derive(Debug, PartialEq, Clone)]
+impl Expression {
+    /// Returns a formatter writing the expression with the position of every node after it (e.g. `(1@1:1 + x@1:5)@1:3`),
+    /// to correlate it with the source in tooling output. Blocks are written as `{ .. }`.
+    pub fn display_with_positions(&self) -> PositionedExpression<'_> {
+        PositionedExpression(self)
+    }
+}
+
+pub struct PositionedExpression<'a>(&'a Expression);
+
+impl fmt::Display for PositionedExpression<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn at(expression: &Expression) -> PositionedExpression<'_> {
+            PositionedExpression(expression)
+        }
+
+        let list = |expressions: &[Expression]| {
+            expressions
+                .iter()
+                .map(|expression| at(expression).to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        match self.0 {
+            Expression::AssignmentExpression(expression) => write!(
+                f,
+                "({} = {})@{}",
+                expression.identifier.value,
+                at(&expression.value),
+                expression.position
+            ),
+            Expression::BlockExpression(block) => write!(f, "{{ .. }}@{}", block.position),
+            Expression::SequenceExpression(expression) => write!(
+                f,
+                "({})@{}",
+                list(&expression.expressions),
+                expression.position
+            ),
+            Expression::PrefixExpression(expression) => write!(
+                f,
+                "({}{})@{}",
+                expression.operator,
+                at(&expression.right),
+                expression.position
+            ),
+            Expression::InfixExpression(expression) => match expression.operator {
+                InfixOperator::Dot | InfixOperator::OptionalDot => write!(
+                    f,
+                    "({}{}{})@{}",
+                    at(&expression.left),
+                    expression.operator,
+                    at(&expression.right),
+                    expression.position
+                ),
+                _ => write!(
+                    f,
+                    "({} {} {})@{}",
+                    at(&expression.left),
+                    expression.operator,
+                    at(&expression.right),
+                    expression.position
+                ),
+            },
+            Expression::IfExpression(expression) => {
+                write!(f, "(if {} {{ .. }}", at(&expression.condition))?;
+
+                if expression.alternative.is_some() {
+                    write!(f, " else {{ .. }}")?;
+                }
+
+                write!(f, ")@{}", expression.position)
+            }
+            Expression::CallExpression(expression) => write!(
+                f,
+                "{}({})@{}",
+                at(&expression.function),
+                list(&expression.arguments),
+                expression.position
+            ),
+            Expression::TypeofExpression(expression) => write!(
+                f,
+                "(typeof {})@{}",
+                at(&expression.expression),
+                expression.position
+            ),
+            Expression::TryExpression(expression) => write!(
+                f,
+                "(try {{ .. }} catch ({}) {{ .. }})@{}",
+                expression.identifier.value, expression.position
+            ),
+            Expression::MatchExpression(expression) => write!(
+                f,
+                "(match {} {{ .. }})@{}",
+                at(&expression.value),
+                expression.position
+            ),
+            Expression::CastExpression(expression) => write!(
+                f,
+                "({} as {})@{}",
+                at(&expression.expression),
+                expression.data_type,
+                expression.position
+            ),
+            Expression::IndexExpression(expression) => write!(
+                f,
+                "{}[{}]@{}",
+                at(&expression.left),
+                at(&expression.index),
+                expression.position
+            ),
+            Expression::RangeExpression(expression) => {
+                write!(f, "(")?;
+
+                if let Some(start) = &expression.start {
+                    write!(f, "{}", at(start))?;
+                }
+
+                write!(f, "..")?;
+
+                if let Some(end) = &expression.end {
+                    write!(f, "{}", at(end))?;
+                }
+
+                write!(f, ")@{}", expression.position)
+            }
+            Expression::InterpolationExpression(expression) => {
+                write!(f, "\"")?;
+
+                for part in &expression.parts {
+                    match part {
+                        Expression::Literal(Literal::StringLiteral(string)) => {
+                            write!(f, "{}", string.value)?
+                        }
+                        part => write!(f, "${{{}}}", at(part))?,
+                    }
+                }
+
+                write!(f, "\"@{}", expression.position)
+            }
+            Expression::Literal(literal) => match literal {
+                Literal::Identifier(identifier) => {
+                    write!(f, "{}@{}", identifier.value, identifier.position)
+                }
+                Literal::NumberLiteral(number) => write!(f, "{}@{}", number.value, number.position),
+                Literal::StringLiteral(string) => {
+                    write!(f, "{:?}@{}", string.value, string.position)
+                }
+                Literal::BooleanLiteral(boolean) => {
+                    write!(f, "{}@{}", boolean.value, boolean.position)
+                }
+                Literal::NullLiteral(null) => write!(f, "null@{}", null.position),
+                Literal::FunctionLiteral(function) => {
+                    let parameters = function
+                        .parameters
+                        .iter()
+                        .map(|parameter| parameter.identifier.value.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    write!(f, "fn({parameters}) {{ .. }}@{}", function.position)
+                }
+                Literal::ArrayLiteral(array) => {
+                    write!(f, "[{}]@{}", list(&array.elements), array.position)
+                }
+                Literal::StructLiteral(literal) => {
+                    let fields = literal
+                        .fields
+                        .iter()
+                        .map(|(key, value)| match key {
+                            FieldKey::Named(identifier) => {
+                                format!("{}: {}", identifier.value, at(value))
+                            }
+                            FieldKey::Computed(key, _) => format!("[{}]: {}", at(key), at(value)),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    write!(
+                        f,
+                        "{} {{ {fields} }}@{}",
+                        literal.identifier.value, literal.position
+                    )
+                }
+            },
+            Expression::Debug(expression, position) => {
+                write!(f, "(debug {})@{}", at(expression), position)
+            }
+            Expression::Error(position) => write!(f, "<error>@{position}"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct AssignmentExpression {
     pub identifier: Identifier,
     pub value: Box<Expression>,
     pub position: Position,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct BlockExpression {
     pub statements: Vec<Statement>,
     pub position: Position,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// `(a, b, c)`, which evaluates every expression in order and yields the last one. It has at least two.
+#[derive(Debug, PartialEq, Clone, Hash)]
+pub struct SequenceExpression {
+    pub expressions: Vec<Expression>,
+    pub position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct IfExpression {
     pub condition: Box<Expression>,
     pub consequence: Box<BlockExpression>,
@@ -37,34 +245,93 @@ pub struct IfExpression {
     pub position: Position,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct CallExpression {
     pub function: Box<Expression>,
     pub arguments: Vec<Expression>,
     pub position: Position,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// `try { body } catch (identifier) { handler }`
+#[derive(Debug, PartialEq, Clone, Hash)]
+pub struct TryExpression {
+    pub body: BlockExpression,
+    pub identifier: Identifier,
+    pub handler: BlockExpression,
+    pub position: Position,
+}
+
+/// `match value { pattern if guard => body, .. }`
+#[derive(Debug, PartialEq, Clone, Hash)]
+pub struct MatchExpression {
+    pub value: Box<Expression>,
+    pub arms: Vec<MatchArm>,
+    pub position: Position,
+}
+
+/// An arm of a `match`, which is taken if the pattern matches the value and the guard, if any, is `true`.
+#[derive(Debug, PartialEq, Clone, Hash)]
+pub struct MatchArm {
+    pub pattern: MatchPattern,
+    pub guard: Option<Expression>,
+    pub body: BlockExpression,
+    pub position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone, Hash)]
+pub enum MatchPattern {
+    /// Matches a value equal to a number, string, boolean or `null` literal.
+    Literal(Literal),
+    /// Matches any value and binds it to the name in the guard and the body.
+    Binding(Identifier),
+    /// `_`, which matches any value.
+    Wildcard(Position),
+}
+
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct TypeofExpression {
     pub expression: Box<Expression>,
     pub position: Position,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// `expression as type`
+#[derive(Debug, PartialEq, Clone, Hash)]
+pub struct CastExpression {
+    pub expression: Box<Expression>,
+    pub data_type: DataType,
+    pub position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct IndexExpression {
     pub left: Box<Expression>,
     pub index: Box<Expression>,
     pub position: Position,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// `start..end`, where both bounds are optional (`..end`, `start..`, `..`).
+#[derive(Debug, PartialEq, Clone, Hash)]
+pub struct RangeExpression {
+    pub start: Option<Box<Expression>>,
+    pub end: Option<Box<Expression>>,
+    pub position: Position,
+}
+
+/// `"a ${b} c"`, the literal segments are kept as string literals.
+#[derive(Debug, PartialEq, Clone, Hash)]
+pub struct InterpolationExpression {
+    pub parts: Vec<Expression>,
+    pub position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct PrefixExpression {
     pub operator: PrefixOperator,
     pub right: Box<Expression>,
     pub position: Position,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub enum PrefixOperator {
     Minus,
     Not,
@@ -89,7 +356,7 @@ impl fmt::Display for PrefixOperator {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct InfixExpression {
     pub left: Box<Expression>,
     pub operator: InfixOperator,
@@ -97,29 +364,36 @@ pub struct InfixExpression {
     pub position: Position,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub enum InfixOperator {
-    Dot,      // A.B
-    Plus,     // A + B
-    Minus,    // A - B
-    Asterisk, // A * B
-    Slash,    // A / B
-    Percent,  // A % B
-    EQ,       // A == B
-    NEQ,      // A != B
-    GT,       // A > B
-    GTE,      // A >= B
-    LT,       // A < B
-    LTE,      // A <= B
+    Dot,               // A.B
+    OptionalDot,       // A?.B
+    NullishCoalescing, // A ?? B
+    Plus,              // A + B
+    Minus,             // A - B
+    Asterisk,          // A * B
+    Pow,               // A ** B
+    Slash,             // A / B
+    Percent,           // A % B
+    EQ,                // A == B
+    NEQ,               // A != B
+    GT,                // A > B
+    GTE,               // A >= B
+    LT,                // A < B
+    LTE,               // A <= B
+    In,                // A in B
 }
 
 impl From<TokenKind<'_>> for InfixOperator {
     fn from(token_kind: TokenKind) -> Self {
         match token_kind {
             TokenKind::Dot => Self::Dot,
+            TokenKind::QuestionDot => Self::OptionalDot,
+            TokenKind::DoubleQuestion => Self::NullishCoalescing,
             TokenKind::Plus => Self::Plus,
             TokenKind::Minus => Self::Minus,
             TokenKind::Asterisk => Self::Asterisk,
+            TokenKind::Pow => Self::Pow,
             TokenKind::Slash => Self::Slash,
             TokenKind::Percent => Self::Percent,
             TokenKind::EQ => Self::EQ,
@@ -128,6 +402,7 @@ impl From<TokenKind<'_>> for InfixOperator {
             TokenKind::GTE => Self::GTE,
             TokenKind::LT => Self::LT,
             TokenKind::LTE => Self::LTE,
+            TokenKind::In => Self::In,
             _ => unreachable!(),
         }
     }
@@ -137,9 +412,12 @@ impl fmt::Display for InfixOperator {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Dot => write!(f, "."),
+            Self::OptionalDot => write!(f, "?."),
+            Self::NullishCoalescing => write!(f, "??"),
             Self::Plus => write!(f, "+"),
             Self::Minus => write!(f, "-"),
             Self::Asterisk => write!(f, "*"),
+            Self::Pow => write!(f, "**"),
             Self::Slash => write!(f, "/"),
             Self::Percent => write!(f, "%"),
             Self::EQ => write!(f, "=="),
@@ -148,6 +426,7 @@ impl fmt::Display for InfixOperator {
             Self::GTE => write!(f, ">="),
             Self::LT => write!(f, "<"),
             Self::LTE => write!(f, "<="),
+            Self::In => write!(f, "in"),
         }
     }
 }
@@ -1,17 +1,38 @@
 use super::{BlockExpression, DataType, Expression, IdentifierGeneric, Position};
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+};
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub enum Literal {
     Identifier(Identifier),
     NumberLiteral(NumberLiteral),
     StringLiteral(StringLiteral),
     BooleanLiteral(BooleanLiteral),
-    FunctionLiteral(FunctionLiteral),
+    NullLiteral(NullLiteral),
+    /// Boxed since it's by far the largest literal, which would otherwise grow every `Expression`.
+    FunctionLiteral(Box<FunctionLiteral>),
     ArrayLiteral(ArrayLiteral),
     StructLiteral(StructLiteral),
 }
 
-#[derive(Debug, PartialEq, Clone)]
+impl Literal {
+    pub fn position(&self) -> Position {
+        match self {
+            Literal::Identifier(literal) => literal.position,
+            Literal::NumberLiteral(literal) => literal.position,
+            Literal::StringLiteral(literal) => literal.position,
+            Literal::BooleanLiteral(literal) => literal.position,
+            Literal::NullLiteral(literal) => literal.position,
+            Literal::FunctionLiteral(literal) => literal.position,
+            Literal::ArrayLiteral(literal) => literal.position,
+            Literal::StructLiteral(literal) => literal.position,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct Identifier {
     pub value: String,
     pub position: Position,
@@ -23,50 +44,104 @@ pub struct NumberLiteral {
     pub position: Position,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// Hashes the bits of the value. `0` and `-0` have the same hash, since they are equal.
+impl Hash for NumberLiteral {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.value + 0.0).to_bits().hash(state);
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct StringLiteral {
     pub value: String,
     pub position: Position,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct BooleanLiteral {
     pub value: bool,
     pub position: Position,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash)]
+pub struct NullLiteral {
+    pub position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct FunctionLiteral {
     pub parameters: Vec<Parameter>,
     pub body: BlockExpression,
     pub generics: Option<IdentifierGeneric>,
-    pub return_type: DataType,
+    /// `None` if it is omitted (`fn() {}`), in which case the function returns `void`, or with union types enabled,
+    /// the type the analyzer infers from its returns.
+    pub return_type: Option<DataType>,
+    /// Whether the function is declared with `const fn`, allowing calls with constant arguments
+    /// to be folded at analysis time.
+    pub is_const: bool,
     pub position: Position,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct Parameter {
     pub identifier: Identifier,
     pub data_type: DataType,
     pub kind: ParameterKind,
+    /// The pattern the argument is destructured with. The identifier of such a parameter is the pattern itself
+    /// (e.g. `{x, y}`), so it never collides with a variable.
+    pub pattern: Option<Pattern>,
     pub position: Position,
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+/// A destructuring pattern of a parameter, e.g. `{x, y}: Point` or `[a, b]: number[]`.
+#[derive(Debug, PartialEq, Clone, Hash)]
+pub enum Pattern {
+    /// Binds the fields of a struct to variables of the same names.
+    Struct(Vec<Identifier>),
+    /// Binds the elements of an array with exactly that many elements.
+    Array(Vec<Identifier>),
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = |identifiers: &[Identifier]| {
+            identifiers
+                .iter()
+                .map(|identifier| identifier.value.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        match self {
+            Pattern::Struct(identifiers) => write!(f, "{{{}}}", names(identifiers)),
+            Pattern::Array(identifiers) => write!(f, "[{}]", names(identifiers)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Hash)]
 pub enum ParameterKind {
     Normal,
     Spread,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct ArrayLiteral {
     pub elements: Vec<Expression>,
     pub position: Position,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct StructLiteral {
     pub identifier: Identifier,
-    pub fields: Vec<(Identifier, Expression)>,
+    pub fields: Vec<(FieldKey, Expression)>,
     pub position: Position,
 }
+
+/// The key of a field in a struct literal: a name (`x: 1` or `"with space": 1`),
+/// or an expression evaluated when the struct is created (`[key]: 1`).
+#[derive(Debug, PartialEq, Clone, Hash)]
+pub enum FieldKey {
+    Named(Identifier),
+    Computed(Expression, Position),
+}
@@ -5,7 +5,12 @@ pub mod statement;
 pub use expression::*;
 pub use literal::*;
 pub use statement::*;
-use std::fmt;
+
+pub use crate::precedence::Priority;
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+};
 
 pub type Program = Vec<Statement>;
 
@@ -15,16 +20,24 @@ impl fmt::Display for DataType {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub enum DataTypeKind {
     Number,
     String,
     Boolean,
     Array(Box<DataType>),
+    /// An array type with a size, e.g. `number[3]`. The size must be a constant expression.
+    FixedArray(Box<DataType>, Box<Expression>),
     Fn(FunctionType),
+    /// A value of the type or `null`, e.g. `number?`. It is the type of an `if` without `else`.
+    Optional(Box<DataType>),
+    /// A value of one of the types, e.g. `Number | String`. It has no syntax, and is only inferred.
+    Union(Vec<DataType>),
     Generic(Generic),
     Custom(String),
     Void,
+    /// The type of expressions that never produce a value. It is assignable to every type.
+    Never,
 }
 
 impl fmt::Display for DataTypeKind {
@@ -33,11 +46,34 @@ impl fmt::Display for DataTypeKind {
             DataTypeKind::Number | DataTypeKind::String | DataTypeKind::Boolean => {
                 write!(f, "{self:?}")
             }
-            DataTypeKind::Array(data_type) => write!(f, "{data_type}[]"),
+            DataTypeKind::Array(data_type) => match data_type.kind {
+                DataTypeKind::Fn(_) | DataTypeKind::Union(_) => write!(f, "({data_type})[]"),
+                _ => write!(f, "{data_type}[]"),
+            },
+            DataTypeKind::FixedArray(data_type, size) => match size.as_ref() {
+                Expression::Literal(Literal::NumberLiteral(size)) => {
+                    write!(f, "{data_type}[{}]", size.value)
+                }
+                _ => write!(f, "{data_type}[..]"),
+            },
             DataTypeKind::Fn(function_type) => write!(f, "{function_type}"),
+            DataTypeKind::Optional(data_type) => match data_type.kind {
+                DataTypeKind::Fn(_) | DataTypeKind::Union(_) => write!(f, "({data_type})?"),
+                _ => write!(f, "{data_type}?"),
+            },
+            DataTypeKind::Union(data_types) => write!(
+                f,
+                "{}",
+                data_types
+                    .iter()
+                    .map(|data_type| data_type.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" | ")
+            ),
             DataTypeKind::Generic(generic) => write!(f, "{generic}"),
             DataTypeKind::Custom(identifier) => write!(f, "{identifier}"),
             DataTypeKind::Void => write!(f, "Void"),
+            DataTypeKind::Never => write!(f, "Never"),
         }
     }
 }
@@ -68,7 +104,7 @@ impl std::fmt::Display for FunctionType {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct Generic(pub Box<DataType>, pub Vec<DataType>);
 
 impl Generic {
@@ -98,12 +134,18 @@ impl PartialEq for Position {
     }
 }
 
+/// Positions are not hashed, consistently with `PartialEq`.
+impl Hash for Position {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
 impl fmt::Display for Position {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(f, "{}:{}", self.0, self.1)
     }
 }
-#[derive(Debug, PartialEq, Clone)]
+
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct DataType {
     pub kind: DataTypeKind,
     pub position: Position,
@@ -118,23 +160,10 @@ impl DataType {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct FunctionType {
     pub generics: Option<IdentifierGeneric>,
     pub parameters: Vec<(DataType, ParameterKind)>,
     pub return_type: Box<DataType>,
     pub position: Position,
 }
-
-#[derive(Debug, Eq, PartialEq, PartialOrd)]
-pub enum Priority {
-    Lowest,
-    Dot,
-    Equals,
-    LessGreater,
-    Sum,
-    Product,
-    Prefix,
-    Call,
-    Index,
-}
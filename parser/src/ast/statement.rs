@@ -1,17 +1,39 @@
-use super::{DataType, Expression, Identifier, IdentifierGeneric, Position};
+use super::{BlockExpression, DataType, Expression, Identifier, IdentifierGeneric, Position};
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub enum Statement {
     LetStatement(LetStatement),
     ReturnStatement(ReturnStatement),
     ReturnExpressionStatement(ReturnExpressionStatement),
+    ThrowStatement(ThrowStatement),
+    WhileStatement(WhileStatement),
+    BreakStatement(BreakStatement),
+    ContinueStatement(ContinueStatement),
     TypeStatement(TypeStatement),
     DeclareStatement(DeclareStatement),
     StructStatement(StructStatement),
     ExpressionStatement(ExpressionStatement),
 }
 
-#[derive(Debug, PartialEq, Clone)]
+impl Statement {
+    pub fn position(&self) -> Position {
+        match self {
+            Statement::LetStatement(statement) => statement.position,
+            Statement::ReturnStatement(statement) => statement.position,
+            Statement::ReturnExpressionStatement(statement) => statement.position,
+            Statement::ThrowStatement(statement) => statement.position,
+            Statement::WhileStatement(statement) => statement.position,
+            Statement::BreakStatement(statement) => statement.position,
+            Statement::ContinueStatement(statement) => statement.position,
+            Statement::TypeStatement(statement) => statement.position,
+            Statement::DeclareStatement(statement) => statement.position,
+            Statement::StructStatement(statement) => statement.position,
+            Statement::ExpressionStatement(statement) => statement.position,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct LetStatement {
     pub identifier: Identifier,
     pub value: Expression,
@@ -20,7 +42,7 @@ pub struct LetStatement {
     pub position: Position,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct TypeStatement {
     pub identifier: Identifier,
     pub data_type: DataType,
@@ -28,14 +50,14 @@ pub struct TypeStatement {
     pub position: Position,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct DeclareStatement {
     pub identifier: Identifier,
     pub data_type: DataType,
     pub position: Position,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct StructStatement {
     pub identifier: Identifier,
     pub generics: IdentifierGeneric,
@@ -43,19 +65,50 @@ pub struct StructStatement {
     pub position: Position,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct ReturnStatement {
+    /// `None` for a bare `return;`.
+    pub value: Option<Expression>,
+    pub position: Position,
+}
+
+/// `throw value;`
+#[derive(Debug, PartialEq, Clone, Hash)]
+pub struct ThrowStatement {
     pub value: Expression,
     pub position: Position,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// `label: while condition { ... }`, where the label is optional.
+#[derive(Debug, PartialEq, Clone, Hash)]
+pub struct WhileStatement {
+    pub label: Option<Identifier>,
+    pub condition: Expression,
+    pub body: BlockExpression,
+    pub position: Position,
+}
+
+/// `break;` or `break label;`
+#[derive(Debug, PartialEq, Clone, Hash)]
+pub struct BreakStatement {
+    pub label: Option<Identifier>,
+    pub position: Position,
+}
+
+/// `continue;` or `continue label;`
+#[derive(Debug, PartialEq, Clone, Hash)]
+pub struct ContinueStatement {
+    pub label: Option<Identifier>,
+    pub position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct ReturnExpressionStatement {
     pub value: Expression,
     pub position: Position,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash)]
 pub struct ExpressionStatement {
     pub expression: Expression,
     pub position: Position,
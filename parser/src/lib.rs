@@ -1,5 +1,6 @@
 pub mod ast;
 pub mod helpers;
+pub mod precedence;
 pub mod tokenizer;
 
 use crate::{
@@ -7,6 +8,7 @@ use crate::{
     tokenizer::{Lexer, Token, TokenKind},
     Position,
 };
+use std::ops::Range;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ParsingError {
@@ -58,19 +60,65 @@ impl ParsingError {
             position,
         )
     }
+
+    pub fn expected_one_of<T>(expected: &[T], got: T, position: Position) -> Self
+    where
+        T: ToString,
+    {
+        Self::new(
+            ParsingErrorKind::ExpectedOneOf(
+                expected.iter().map(ToString::to_string).collect(),
+                got.to_string(),
+            ),
+            position,
+        )
+    }
+
+    pub fn invalid_number<T>(literal: T, position: Position) -> Self
+    where
+        T: ToString,
+    {
+        Self::new(
+            ParsingErrorKind::InvalidNumber(literal.to_string()),
+            position,
+        )
+    }
+
+    pub fn unterminated_comment(position: Position) -> Self {
+        Self::new(ParsingErrorKind::UnterminatedComment, position)
+    }
+
+    pub fn nesting_too_deep(limit: usize, position: Position) -> Self {
+        Self::new(ParsingErrorKind::NestingTooDeep(limit), position)
+    }
+
+    pub fn too_many_errors(limit: usize, position: Position) -> Self {
+        Self::new(ParsingErrorKind::TooManyErrors(limit), position)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 #[rustfmt::skip]
 pub enum ParsingErrorKind {
     ExpectedNextToken(String, String),
+    ExpectedOneOf(Vec<String>, String),
     ExpectedDataType(String),
     ExpectedExpression(String),
     UnexpectedToken(String),
+    NestingTooDeep(usize),
+    TooManyErrors(usize),
+    InvalidNumber(String),
+    UnterminatedComment,
 }
 
 pub type ParseResult<T> = Result<T, ParsingError>;
 
+/// The maximum depth of nested expressions (including string interpolations) before the parser gives up.
+pub const MAX_NESTING_DEPTH: usize = 64;
+
+/// The default number of errors `parse_program` collects before it gives up.
+pub const MAX_ERRORS: usize = 100;
+
 /// # Parser
 ///
 /// The parser is responsible for parsing the tokens from the lexer into an AST.
@@ -98,10 +146,24 @@ pub type ParseResult<T> = Result<T, ParsingError>;
 pub struct Parser<'a> {
     pub lexer: Lexer<'a>,
     pub errors: Vec<ParsingError>,
+    /// The source range of each top-level statement parsed by `parse_program`.
+    /// Each range extends to the start of the next statement (or the end of the source).
+    pub spans: Vec<Range<usize>>,
     current_token: Token<'a>,
     peek_token: Token<'a>,
     position: Position,
     previous_statement: Option<Statement>,
+    depth: usize,
+    /// The number of errors after which `parse_program` stops, marking the end with `TooManyErrors`.
+    /// `None` collects every error.
+    pub max_errors: Option<usize>,
+    /// The flags that enable `#if FLAG { ... }` blocks. Flags that are not defined are false.
+    pub defines: Vec<String>,
+    /// The positions of statements indented under an `if` or `while` whose body has no braces, which look like
+    /// part of the body but aren't (e.g. `if (c) return 1;` followed by an indented line).
+    pub dangling: Vec<Position>,
+    /// The position of the `if` or `while` keyword, if the last statement ended with a body without braces.
+    braceless: Option<Position>,
 }
 
 impl<'a> From<&'a str> for Parser<'a> {
@@ -115,6 +177,7 @@ impl<'a> Parser<'a> {
     pub fn new(lexer: Lexer<'a>) -> Self {
         Parser {
             lexer,
+            max_errors: Some(MAX_ERRORS),
             ..Default::default()
         }
     }
@@ -123,6 +186,13 @@ impl<'a> Parser<'a> {
         self.current_token = self.peek_token.clone();
         self.peek_token = self.lexer.next_token();
 
+        // An unterminated comment runs until the end of the input, so it is reported and read as the end.
+        if self.peek_token.kind == TokenKind::UnterminatedComment {
+            self.errors
+                .push(ParsingError::unterminated_comment(self.peek_token.position));
+            self.peek_token.kind = TokenKind::EOF;
+        }
+
         self.position = Position(self.current_token.position.0, self.current_token.position.1);
     }
 
@@ -132,7 +202,36 @@ impl<'a> Parser<'a> {
 
             Ok(())
         } else {
-            Err(ParsingError::expected_expression(
+            Err(ParsingError::expected_next_token(
+                token_type.to_string(),
+                self.current_token.kind.to_string(),
+                self.position,
+            ))
+        }
+    }
+
+    /// Expects the `}` closing a struct, without moving past it.
+    fn expect_closing_brace(&self) -> ParseResult<()> {
+        if self.current_token.kind == TokenKind::RBrace {
+            Ok(())
+        } else {
+            Err(ParsingError::expected_next_token(
+                TokenKind::RBrace.to_string(),
+                self.current_token.kind.to_string(),
+                self.position,
+            ))
+        }
+    }
+
+    /// Expects a `,` between elements of a list, or the token closing the list.
+    fn expect_separator(&mut self, closing: &TokenKind) -> ParseResult<()> {
+        if self.current_token.kind == TokenKind::Comma {
+            self.next_token();
+
+            Ok(())
+        } else {
+            Err(ParsingError::expected_one_of(
+                &[TokenKind::Comma.to_string(), closing.to_string()],
                 self.current_token.kind.to_string(),
                 self.position,
             ))
@@ -144,46 +243,69 @@ impl<'a> Parser<'a> {
     }
 
     fn get_priority(&self, token_type: &TokenKind) -> Priority {
-        match token_type {
-            TokenKind::Dot | TokenKind::Arrow => Priority::Dot,
-            TokenKind::Assign | TokenKind::EQ | TokenKind::NEQ => Priority::Equals,
-            TokenKind::Plus | TokenKind::Minus => Priority::Sum,
-            TokenKind::Slash | TokenKind::Asterisk => Priority::Product,
-            TokenKind::LT | TokenKind::GT | TokenKind::LTE | TokenKind::GTE => {
-                Priority::LessGreater
-            }
-            TokenKind::LParen => Priority::Call,
-            TokenKind::LBracket => Priority::Index,
-            _ => Priority::Lowest,
-        }
+        precedence::precedence(token_type).map_or(Priority::Lowest, |(priority, _)| priority)
     }
 
     fn peek_priority(&mut self) -> Priority {
         self.get_priority(&self.peek_token.kind)
     }
 
-    fn current_priority(&self) -> Priority {
-        self.get_priority(&self.current_token.kind)
-    }
-
     /// Parses the tokens from the lexer into an AST.
     ///
     /// if there are any errors, they will be returned as a `Vec<ParsingError>`.
+    /// After `max_errors` errors, parsing stops and a `TooManyErrors` error is added.
     pub fn parse_program(&mut self) -> Result<Program, Vec<ParsingError>> {
         self.next_token();
         self.next_token();
 
         let mut program = Program::default();
+        let mut starts = Vec::new();
 
         while self.current_token.kind != TokenKind::EOF {
-            match self.parse_statement() {
-                Ok(statement) => program.push(statement),
-                Err(error) => self.errors.push(error),
+            let start = self.current_token.offset;
+
+            if self.current_token.kind == TokenKind::Hash {
+                match self.parse_conditional() {
+                    Ok(statements) => {
+                        starts.extend(statements.iter().map(|_| start));
+                        program.extend(statements);
+                    }
+                    Err(error) => self.errors.push(error),
+                }
+            } else {
+                match self.parse_statement() {
+                    Ok(statement) => {
+                        program.push(statement);
+                        starts.push(start);
+                    }
+                    Err(error) => self.errors.push(error),
+                }
+            }
+
+            if let Some(max_errors) = self.max_errors {
+                if self.errors.len() >= max_errors {
+                    self.errors.truncate(max_errors);
+                    self.errors
+                        .push(ParsingError::too_many_errors(max_errors, self.position));
+                    break;
+                }
             }
 
             self.next_token();
         }
 
+        self.spans = starts
+            .iter()
+            .map(|start| {
+                *start
+                    ..starts
+                        .iter()
+                        .find(|next| *next > start)
+                        .copied()
+                        .unwrap_or(self.lexer.input.len())
+            })
+            .collect();
+
         if !self.errors.is_empty() {
             Err(self.errors.clone())
         } else {
@@ -191,6 +313,104 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Parses the whole input as a single expression (e.g. `x + 1` in a REPL).
+    pub fn parse_single_expression(&mut self) -> ParseResult<Expression> {
+        self.next_token();
+        self.next_token();
+
+        let expression = self.parse_expression(&Priority::Lowest);
+        self.next_token();
+
+        if let Some(error) = self.errors.first() {
+            return Err(error.clone());
+        }
+
+        let expression = expression?;
+
+        if self.current_token.kind == TokenKind::Semicolon {
+            self.next_token();
+        }
+
+        if self.current_token.kind != TokenKind::EOF {
+            return Err(ParsingError::unexpected_token(
+                self.current_token.kind.to_string(),
+                self.position,
+            ));
+        }
+
+        Ok(expression)
+    }
+
+    /// Re-parses `source` after the `edit` range of the previous source was replaced with `length` bytes,
+    /// reusing the statements of `program`, which this parser parsed, that the edit does not touch.
+    /// Re-parsed `#if` blocks are resolved with the same `defines`.
+    ///
+    /// The positions of the reused statements after the edit are not updated.
+    pub fn reparse(
+        &self,
+        source: &str,
+        program: &Program,
+        edit: Range<usize>,
+        length: usize,
+    ) -> Result<(Program, Vec<Range<usize>>), Vec<ParsingError>> {
+        let spans = &self.spans;
+        let parser = |lexer| Parser {
+            defines: self.defines.clone(),
+            max_errors: self.max_errors,
+            ..Parser::new(lexer)
+        };
+
+        let affected = spans
+            .iter()
+            .enumerate()
+            .filter(|(_, span)| span.start <= edit.end && span.end >= edit.start)
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+
+        let (first, last) = match (affected.first(), affected.last()) {
+            (Some(first), Some(last)) if program.len() == spans.len() => (*first, *last),
+            _ => {
+                let mut parser = parser(Lexer::new(source));
+                let program = parser.parse_program()?;
+
+                return Ok((program, parser.spans));
+            }
+        };
+
+        let shift = |offset: usize| offset + length - (edit.end - edit.start);
+
+        let start = spans[first].start;
+        let end = shift(spans[last].end);
+
+        let line = source[..start].matches('\n').count() + 1;
+        let column = start - source[..start].rfind('\n').map_or(0, |index| index + 1) + 1;
+
+        let mut lexer = Lexer::new(&source[start..end]);
+        lexer.current_position = Position(line, column);
+
+        let mut parser = parser(lexer);
+        let statements = parser.parse_program()?;
+
+        let mut result = program[..first].to_vec();
+        result.extend(statements);
+        result.extend_from_slice(&program[last + 1..]);
+
+        let mut result_spans = spans[..first].to_vec();
+        result_spans.extend(
+            parser
+                .spans
+                .iter()
+                .map(|span| span.start + start..span.end + start),
+        );
+        result_spans.extend(
+            spans[last + 1..]
+                .iter()
+                .map(|span| shift(span.start)..shift(span.end)),
+        );
+
+        Ok((result, result_spans))
+    }
+
     fn parse_statement(&mut self) -> ParseResult<Statement> {
         if let Some(Statement::ReturnExpressionStatement(_)) = self.previous_statement {
             return Err(ParsingError::unexpected_token(
@@ -199,10 +419,44 @@ impl<'a> Parser<'a> {
             ));
         }
 
+        if let Some(keyword) = self.braceless.take() {
+            if self.position.0 > keyword.0 && self.position.1 > keyword.1 {
+                self.dangling.push(self.position);
+            }
+        }
+
         let statement = match self.current_token.kind {
             TokenKind::Let => Statement::LetStatement(self.parse_let_statement(false)?),
             TokenKind::Mut => Statement::LetStatement(self.parse_let_statement(true)?),
             TokenKind::Return => Statement::ReturnStatement(self.parse_return_statement()?),
+            TokenKind::Throw => Statement::ThrowStatement(self.parse_throw_statement()?),
+            TokenKind::While => Statement::WhileStatement(self.parse_while_statement(None)?),
+            TokenKind::IDENT(label) if self.peek_token(&TokenKind::Colon) => {
+                let label = Identifier {
+                    value: label.to_string(),
+                    position: self.position,
+                };
+                self.next_token();
+                self.next_token();
+
+                if self.current_token.kind != TokenKind::While {
+                    return Err(ParsingError::expected_next_token(
+                        TokenKind::While.to_string(),
+                        self.current_token.kind.to_string(),
+                        self.position,
+                    ));
+                }
+
+                Statement::WhileStatement(self.parse_while_statement(Some(label))?)
+            }
+            TokenKind::Break => {
+                let (label, position) = self.parse_loop_label()?;
+                Statement::BreakStatement(BreakStatement { label, position })
+            }
+            TokenKind::Continue => {
+                let (label, position) = self.parse_loop_label()?;
+                Statement::ContinueStatement(ContinueStatement { label, position })
+            }
             TokenKind::Type => Statement::TypeStatement(self.parse_type_statement()?),
             TokenKind::Declare => Statement::DeclareStatement(self.parse_declare_statement()?),
             TokenKind::Struct => Statement::StructStatement(self.parse_struct_statement()?),
@@ -213,6 +467,30 @@ impl<'a> Parser<'a> {
         Ok(statement)
     }
 
+    /// Parses a top-level `#if FLAG { ... }` block, whose statements are only returned if `FLAG` is defined.
+    fn parse_conditional(&mut self) -> ParseResult<Vec<Statement>> {
+        self.next_token();
+        self.expect_token(&TokenKind::If)?;
+
+        let flag = ident_token_to_string! { self };
+        self.next_token();
+
+        if self.current_token.kind != TokenKind::LBrace {
+            return Err(ParsingError::expected_next_token(
+                TokenKind::LBrace.to_string(),
+                self.current_token.kind.to_string(),
+                self.position,
+            ));
+        }
+
+        let block = self.parse_block_expression()?;
+
+        Ok(match self.defines.contains(&flag) {
+            true => block.statements,
+            false => Vec::new(),
+        })
+    }
+
     fn parse_let_statement(&mut self, is_mutable: bool) -> ParseResult<LetStatement> {
         self.next_token();
 
@@ -231,56 +509,127 @@ impl<'a> Parser<'a> {
 
         self.expect_token(&TokenKind::Assign)?;
 
-        if let Ok(expression) = self.parse_expression(&Priority::Lowest) {
-            return if self.peek_token(&TokenKind::Semicolon) {
-                self.next_token();
+        let expression = self.parse_expression(&Priority::Lowest)?;
 
-                Ok(LetStatement {
-                    identifier: ident,
-                    value: expression,
-                    data_type,
-                    is_mutable,
-                    position: self.position,
-                })
-            } else {
-                Err(ParsingError::expected_next_token(
-                    TokenKind::Semicolon.to_string(),
-                    self.current_token.kind.to_string(),
-                    self.position,
-                ))
-            };
-        }
+        if self.peek_token(&TokenKind::Semicolon) {
+            self.next_token();
 
-        Err(ParsingError::unexpected_token(
-            self.current_token.kind.to_string(),
-            self.position,
-        ))
+            Ok(LetStatement {
+                identifier: ident,
+                value: expression,
+                data_type,
+                is_mutable,
+                position: self.position,
+            })
+        } else {
+            Err(ParsingError::expected_next_token(
+                TokenKind::Semicolon.to_string(),
+                self.peek_token.kind.to_string(),
+                self.position,
+            ))
+        }
     }
 
     fn parse_return_statement(&mut self) -> ParseResult<ReturnStatement> {
+        if self.peek_token(&TokenKind::Semicolon) {
+            self.next_token();
+
+            return Ok(ReturnStatement {
+                value: None,
+                position: self.position,
+            });
+        }
+
         self.next_token();
 
-        if let Ok(expression) = self.parse_expression(&Priority::Lowest) {
-            return if self.peek_token(&TokenKind::Semicolon) {
-                self.next_token();
+        let expression = Some(self.parse_expression(&Priority::Lowest)?);
 
-                Ok(ReturnStatement {
-                    value: expression,
+        if self.peek_token(&TokenKind::Semicolon) {
+            self.next_token();
+
+            Ok(ReturnStatement {
+                value: expression,
+                position: self.position,
+            })
+        } else {
+            Err(ParsingError::expected_next_token(
+                TokenKind::Semicolon.to_string(),
+                self.peek_token.kind.to_string(),
+                self.position,
+            ))
+        }
+    }
+
+    fn parse_throw_statement(&mut self) -> ParseResult<ThrowStatement> {
+        self.next_token();
+
+        let value = self.parse_expression(&Priority::Lowest)?;
+
+        if !self.peek_token(&TokenKind::Semicolon) {
+            return Err(ParsingError::expected_next_token(
+                TokenKind::Semicolon.to_string(),
+                self.peek_token.kind.to_string(),
+                self.position,
+            ));
+        }
+
+        self.next_token();
+
+        Ok(ThrowStatement {
+            value,
+            position: self.position,
+        })
+    }
+
+    /// The semicolon after the body is optional.
+    fn parse_while_statement(&mut self, label: Option<Identifier>) -> ParseResult<WhileStatement> {
+        let position = self.position;
+        self.next_token();
+
+        let condition = self.parse_expression(&Priority::Lowest)?;
+        self.next_token();
+
+        let body = self.parse_if_arm(position)?;
+
+        if self.peek_token(&TokenKind::Semicolon) {
+            self.next_token();
+        }
+
+        Ok(WhileStatement {
+            label,
+            condition,
+            body,
+            position,
+        })
+    }
+
+    /// Parses the optional label of `break` or `continue` and the semicolon after it.
+    fn parse_loop_label(&mut self) -> ParseResult<(Option<Identifier>, Position)> {
+        let position = self.position;
+        self.next_token();
+
+        let label = match self.current_token.kind {
+            TokenKind::IDENT(label) => {
+                let label = Identifier {
+                    value: label.to_string(),
                     position: self.position,
-                })
-            } else {
-                Err(ParsingError::expected_next_token(
-                    TokenKind::Semicolon.to_string(),
-                    self.current_token.kind.to_string(),
-                    self.position,
-                ))
-            };
+                };
+                self.next_token();
+
+                Some(label)
+            }
+            _ => None,
+        };
+
+        if self.current_token.kind != TokenKind::Semicolon {
+            return Err(ParsingError::expected_next_token(
+                TokenKind::Semicolon.to_string(),
+                self.current_token.kind.to_string(),
+                self.position,
+            ));
         }
 
-        Err(ParsingError::unexpected_token(
-            self.current_token.kind.to_string(),
-            self.position,
-        ))
+        Ok((label, position))
     }
 
     fn parse_type_statement(&mut self) -> ParseResult<TypeStatement> {
@@ -368,7 +717,7 @@ impl<'a> Parser<'a> {
 
         let mut fields = Vec::new();
 
-        while self.current_token.kind != TokenKind::RBrace {
+        while !matches!(self.current_token.kind, TokenKind::RBrace | TokenKind::EOF) {
             let key = Identifier {
                 value: ident_token_to_string! { self },
                 position: self.position,
@@ -385,10 +734,10 @@ impl<'a> Parser<'a> {
                 break;
             }
 
-            self.expect_token(&TokenKind::Comma)?;
+            self.expect_separator(&TokenKind::RBrace)?;
         }
 
-        self.expect_token(&TokenKind::RBrace)?;
+        self.expect_closing_brace()?;
 
         Ok(StructStatement {
             identifier: Identifier {
@@ -401,11 +750,15 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /// An `if` whose last arm is a statement without braces (e.g. `if (c) return 1;`) ends with the arm's semicolon.
     fn parse_expression_statement(&mut self) -> ParseResult<Statement> {
         let expression = self.parse_expression(&Priority::Lowest)?;
 
-        if self.peek_token(&TokenKind::Semicolon) {
-            self.next_token();
+        if self.current_token.kind == TokenKind::Semicolon || self.peek_token(&TokenKind::Semicolon)
+        {
+            if self.current_token.kind != TokenKind::Semicolon {
+                self.next_token();
+            }
 
             Ok(Statement::ExpressionStatement(ExpressionStatement {
                 expression,
@@ -422,6 +775,21 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_expression(&mut self, priority: &Priority) -> ParseResult<Expression> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            return Err(ParsingError::nesting_too_deep(
+                MAX_NESTING_DEPTH,
+                self.position,
+            ));
+        }
+
+        self.depth += 1;
+        let expression = self.parse_expression_without_depth(priority);
+        self.depth -= 1;
+
+        expression
+    }
+
+    fn parse_expression_without_depth(&mut self, priority: &Priority) -> ParseResult<Expression> {
         let left_expression = match self.current_token.kind.clone() {
             TokenKind::IDENT(value) => {
                 Some(Ok(Expression::Literal(Literal::Identifier(Identifier {
@@ -435,18 +803,19 @@ impl<'a> Parser<'a> {
                     position: self.position,
                 },
             )))),
-            TokenKind::String(value) => Some(Ok(Expression::Literal(Literal::StringLiteral(
-                StringLiteral {
-                    value: value.to_string(),
-                    position: self.position,
-                },
-            )))),
+            TokenKind::InvalidNumber(literal) => {
+                Some(Err(ParsingError::invalid_number(literal, self.position)))
+            }
+            TokenKind::String(value) => Some(self.parse_string(value)),
             TokenKind::Boolean(value) => Some(Ok(Expression::Literal(Literal::BooleanLiteral(
                 BooleanLiteral {
                     value,
                     position: self.position,
                 },
             )))),
+            TokenKind::Null => Some(Ok(Expression::Literal(Literal::NullLiteral(NullLiteral {
+                position: self.position,
+            })))),
             TokenKind::Bang | TokenKind::Minus => {
                 let operator: PrefixOperator = self.current_token.kind.clone().into();
                 self.next_token();
@@ -457,35 +826,24 @@ impl<'a> Parser<'a> {
                     position: self.position,
                 })))
             }
-            TokenKind::LParen => {
-                self.next_token();
-
-                let expression = self.parse_expression(&Priority::Lowest);
-                self.next_token();
-
-                if self.current_token.kind != TokenKind::RParen {
-                    return Err(ParsingError::expected_next_token(
-                        TokenKind::RParen.to_string(),
-                        self.current_token.kind.to_string(),
-                        self.position,
-                    ));
-                }
-
-                Some(expression)
-            }
+            TokenKind::LParen => Some(self.parse_grouped_expression()),
             TokenKind::LBrace => Some(Ok(Expression::BlockExpression(
                 self.parse_block_expression()?,
             ))),
             TokenKind::LBracket => Some(Ok(Expression::Literal(Literal::ArrayLiteral(
                 self.parse_array_literal()?,
             )))),
-            TokenKind::Function => Some(Ok(Expression::Literal(Literal::FunctionLiteral(
-                self.parse_function_literal()?,
-            )))),
+            TokenKind::Function | TokenKind::Const => Some(Ok(Expression::Literal(
+                Literal::FunctionLiteral(Box::new(self.parse_function_literal()?)),
+            ))),
             TokenKind::Struct => Some(Ok(Expression::Literal(Literal::StructLiteral(
                 self.parse_struct_literal()?,
             )))),
             TokenKind::If => Some(Ok(Expression::IfExpression(self.parse_if_expression()?))),
+            TokenKind::Try => Some(Ok(Expression::TryExpression(self.parse_try_expression()?))),
+            TokenKind::Match => Some(Ok(Expression::MatchExpression(
+                self.parse_match_expression()?,
+            ))),
             TokenKind::Typeof => {
                 self.next_token();
 
@@ -505,16 +863,23 @@ impl<'a> Parser<'a> {
             _ => None,
         };
 
-        if left_expression.is_none() && self.current_token.kind != TokenKind::Semicolon {
-            return Err(ParsingError::unexpected_token(
-                self.current_token.kind.to_string(),
-                self.position,
-            ));
-        }
-
-        let mut left_expression = left_expression.ok_or_else(|| {
-            ParsingError::unexpected_token(self.current_token.kind.to_string(), self.position)
-        })?;
+        let mut left_expression = match left_expression {
+            Some(left_expression) => left_expression,
+            None => match self.current_token.kind {
+                TokenKind::Semicolon
+                | TokenKind::Comma
+                | TokenKind::RParen
+                | TokenKind::RBracket
+                | TokenKind::RBrace
+                | TokenKind::EOF => {
+                    return Err(ParsingError::unexpected_token(
+                        self.current_token.kind.to_string(),
+                        self.position,
+                    ))
+                }
+                _ => return Ok(self.recover_expression()),
+            },
+        };
 
         if self.peek_token(&TokenKind::Assign) {
             let identifier = Identifier {
@@ -539,29 +904,45 @@ impl<'a> Parser<'a> {
                 TokenKind::Plus
                 | TokenKind::Minus
                 | TokenKind::Dot
+                | TokenKind::QuestionDot
+                | TokenKind::DoubleQuestion
                 | TokenKind::Slash
                 | TokenKind::Asterisk
+                | TokenKind::Pow
                 | TokenKind::Percent
                 | TokenKind::EQ
                 | TokenKind::NEQ
                 | TokenKind::LT
                 | TokenKind::GT
                 | TokenKind::LTE
-                | TokenKind::GTE => {
+                | TokenKind::GTE
+                | TokenKind::In => {
                     let operator: InfixOperator = self.current_token.kind.clone().into();
+                    let position = self.position;
 
-                    let priority = self.current_priority();
+                    let priority = precedence::right_operand_priority(&self.current_token.kind);
                     self.next_token();
+
+                    if matches!(operator, InfixOperator::Dot | InfixOperator::OptionalDot)
+                        && !matches!(self.current_token.kind, TokenKind::IDENT(_))
+                    {
+                        return Err(ParsingError::unexpected_token(
+                            self.current_token.kind.to_string(),
+                            self.position,
+                        ));
+                    }
+
                     let right = Box::new(self.parse_expression(&priority)?);
 
                     Ok(Expression::InfixExpression(InfixExpression {
                         left: Box::new(left_expression?),
                         operator,
                         right,
-                        position: self.position,
+                        position,
                     }))
                 }
                 TokenKind::LParen => {
+                    let position = self.position;
                     self.next_token();
 
                     let mut arguments = Vec::new();
@@ -582,7 +963,7 @@ impl<'a> Parser<'a> {
                                 break;
                             }
 
-                            self.expect_token(&TokenKind::Comma)?;
+                            self.expect_separator(&TokenKind::RParen)?;
                         }
 
                         if self.current_token.kind != TokenKind::RParen {
@@ -597,13 +978,27 @@ impl<'a> Parser<'a> {
                     Ok(Expression::CallExpression(CallExpression {
                         function: Box::new(left_expression?),
                         arguments,
-                        position: self.position,
+                        position,
+                    }))
+                }
+                TokenKind::As => {
+                    let position = self.position;
+                    self.next_token();
+
+                    let data_type =
+                        DataType::new(self.parse_data_type_without_next()?, self.position);
+
+                    Ok(Expression::CastExpression(CastExpression {
+                        expression: Box::new(left_expression?),
+                        data_type,
+                        position,
                     }))
                 }
                 TokenKind::LBracket => {
+                    let position = self.position;
                     self.next_token();
 
-                    let index = self.parse_expression(&Priority::Lowest)?;
+                    let index = self.parse_index()?;
                     self.next_token();
 
                     if self.current_token.kind != TokenKind::RBracket {
@@ -617,7 +1012,7 @@ impl<'a> Parser<'a> {
                     Ok(Expression::IndexExpression(IndexExpression {
                         left: Box::new(left_expression?),
                         index: Box::new(index),
-                        position: self.position,
+                        position,
                     }))
                 }
                 _ => Err(ParsingError::unexpected_token(
@@ -630,16 +1025,211 @@ impl<'a> Parser<'a> {
         left_expression
     }
 
+    /// Parses the contents of a string token, resolving escapes (`\{`, `\}`, `\"`, `\\`) and `${...}` interpolations.
+    fn parse_string(&mut self, value: &'a str) -> ParseResult<Expression> {
+        let position = self.position;
+
+        let mut parts = Vec::new();
+        let mut string = String::new();
+        let mut chars = value.char_indices().peekable();
+
+        while let Some((index, char)) = chars.next() {
+            match char {
+                '\\' => match chars.next() {
+                    Some((_, char @ ('{' | '}' | '"' | '\\'))) => string.push(char),
+                    Some((_, char)) => {
+                        string.push('\\');
+                        string.push(char);
+                    }
+                    None => string.push('\\'),
+                },
+                '$' if matches!(chars.peek(), Some((_, '{'))) => {
+                    let start = index + 2;
+                    // The value doesn't include the opening quote.
+                    let interpolation_position = Position(position.0, position.1 + start + 1);
+
+                    if !Lexer::new(&value[index + 1..]).read_interpolation() {
+                        return Err(ParsingError::expected_next_token(
+                            TokenKind::RBrace.to_string(),
+                            TokenKind::EOF.to_string(),
+                            interpolation_position,
+                        ));
+                    }
+
+                    let mut lexer = Lexer::new(&value[start..]);
+                    lexer.current_position = interpolation_position;
+
+                    let mut parser = Parser {
+                        depth: self.depth,
+                        ..Parser::new(lexer)
+                    };
+                    parser.next_token();
+                    parser.next_token();
+
+                    let expression = parser.parse_expression(&Priority::Lowest)?;
+                    self.errors.append(&mut parser.errors);
+
+                    if parser.peek_token.kind != TokenKind::RBrace {
+                        return Err(ParsingError::expected_next_token(
+                            TokenKind::RBrace.to_string(),
+                            parser.peek_token.kind.to_string(),
+                            interpolation_position,
+                        ));
+                    }
+
+                    if !string.is_empty() {
+                        parts.push(Expression::Literal(Literal::StringLiteral(StringLiteral {
+                            value: std::mem::take(&mut string),
+                            position,
+                        })));
+                    }
+                    parts.push(expression);
+
+                    let end = start + parser.lexer.position;
+                    while chars.next_if(|(index, _)| *index < end).is_some() {}
+                }
+                char => string.push(char),
+            }
+        }
+
+        if parts.is_empty() {
+            return Ok(Expression::Literal(Literal::StringLiteral(StringLiteral {
+                value: string,
+                position,
+            })));
+        }
+
+        if !string.is_empty() {
+            parts.push(Expression::Literal(Literal::StringLiteral(StringLiteral {
+                value: string,
+                position,
+            })));
+        }
+
+        Ok(Expression::InterpolationExpression(
+            InterpolationExpression { parts, position },
+        ))
+    }
+
+    fn parse_index(&mut self) -> ParseResult<Expression> {
+        let position = self.position;
+
+        let start = if self.current_token.kind == TokenKind::DoubleDot {
+            None
+        } else {
+            let start = self.parse_expression(&Priority::Lowest)?;
+
+            if !self.peek_token(&TokenKind::DoubleDot) {
+                return Ok(start);
+            }
+
+            self.next_token();
+            Some(Box::new(start))
+        };
+
+        let end = if self.peek_token(&TokenKind::RBracket) {
+            None
+        } else {
+            self.next_token();
+            Some(Box::new(self.parse_expression(&Priority::Lowest)?))
+        };
+
+        Ok(Expression::RangeExpression(RangeExpression {
+            start,
+            end,
+            position,
+        }))
+    }
+
+    /// Records an unexpected token error and skips to the end of the malformed expression.
+    fn recover_expression(&mut self) -> Expression {
+        let position = self.position;
+        self.errors.push(ParsingError::unexpected_token(
+            self.current_token.kind.to_string(),
+            position,
+        ));
+
+        let mut depth = 0;
+
+        loop {
+            match self.peek_token.kind {
+                TokenKind::EOF => break,
+                TokenKind::LParen | TokenKind::LBracket | TokenKind::LBrace => depth += 1,
+                TokenKind::RParen | TokenKind::RBracket | TokenKind::RBrace if depth > 0 => {
+                    depth -= 1
+                }
+                TokenKind::RParen
+                | TokenKind::RBracket
+                | TokenKind::RBrace
+                | TokenKind::Semicolon
+                | TokenKind::Comma
+                    if depth == 0 =>
+                {
+                    break
+                }
+                _ => {}
+            }
+
+            self.next_token();
+        }
+
+        Expression::Error(position)
+    }
+
+    /// Parses `(a)`, or a sequence `(a, b, c)` which evaluates every expression in order and yields the last one.
+    fn parse_grouped_expression(&mut self) -> ParseResult<Expression> {
+        let position = self.position;
+        self.next_token();
+
+        let mut expression = self.parse_expression(&Priority::Lowest);
+        let mut expressions = Vec::new();
+
+        while self.peek_token(&TokenKind::Comma) {
+            expressions.push(expression?);
+
+            self.next_token();
+            self.next_token();
+            expression = self.parse_expression(&Priority::Lowest);
+        }
+
+        self.next_token();
+
+        if self.current_token.kind != TokenKind::RParen {
+            return Err(ParsingError::expected_next_token(
+                TokenKind::RParen.to_string(),
+                self.current_token.kind.to_string(),
+                self.position,
+            ));
+        }
+
+        if expressions.is_empty() {
+            return expression;
+        }
+
+        expressions.push(expression?);
+
+        Ok(Expression::SequenceExpression(SequenceExpression {
+            expressions,
+            position,
+        }))
+    }
+
+    /// A trailing expression without a semicolon is kept as a `ReturnExpressionStatement`, which is the block's value.
     fn parse_block_expression(&mut self) -> ParseResult<BlockExpression> {
         self.next_token();
 
         let mut statements = Vec::new();
+        let enclosing_statement = self.previous_statement.take();
 
         while self.current_token.kind != TokenKind::RBrace {
             statements.push(self.parse_statement()?);
             self.next_token();
         }
 
+        // A body without braces at the end of the block can't be followed by a statement of its own.
+        self.braceless = None;
+        self.previous_statement = enclosing_statement;
+
         Ok(BlockExpression {
             statements,
             position: self.position,
@@ -666,7 +1256,7 @@ impl<'a> Parser<'a> {
                 break;
             }
 
-            self.expect_token(&TokenKind::Comma)?;
+            self.expect_separator(&TokenKind::RBracket)?;
         }
 
         if self.current_token.kind != TokenKind::RBracket {
@@ -692,10 +1282,33 @@ impl<'a> Parser<'a> {
 
         let mut fields = Vec::new();
 
-        while self.current_token.kind != TokenKind::RBrace {
-            let key = Identifier {
-                value: ident_token_to_string! { self },
-                position: self.position,
+        while !matches!(self.current_token.kind, TokenKind::RBrace | TokenKind::EOF) {
+            let key = match self.current_token.kind {
+                TokenKind::String(value) => FieldKey::Named(Identifier {
+                    value: value.to_string(),
+                    position: self.position,
+                }),
+                TokenKind::LBracket => {
+                    let position = self.position;
+                    self.next_token();
+
+                    let key = self.parse_expression(&Priority::Lowest)?;
+                    self.next_token();
+
+                    if self.current_token.kind != TokenKind::RBracket {
+                        return Err(ParsingError::expected_next_token(
+                            TokenKind::RBracket.to_string(),
+                            self.current_token.kind.to_string(),
+                            self.position,
+                        ));
+                    }
+
+                    FieldKey::Computed(key, position)
+                }
+                _ => FieldKey::Named(Identifier {
+                    value: ident_token_to_string! { self },
+                    position: self.position,
+                }),
             };
             self.next_token();
 
@@ -710,16 +1323,10 @@ impl<'a> Parser<'a> {
                 break;
             }
 
-            self.expect_token(&TokenKind::Comma)?;
+            self.expect_separator(&TokenKind::RBrace)?;
         }
 
-        if self.current_token.kind != TokenKind::RBrace {
-            return Err(ParsingError::expected_next_token(
-                TokenKind::RBrace.to_string(),
-                self.current_token.kind.to_string(),
-                self.position,
-            ));
-        }
+        self.expect_closing_brace()?;
 
         Ok(StructLiteral {
             identifier: Identifier {
@@ -732,6 +1339,20 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_function_literal(&mut self) -> ParseResult<FunctionLiteral> {
+        let is_const = self.current_token.kind == TokenKind::Const;
+
+        if is_const {
+            self.next_token();
+
+            if self.current_token.kind != TokenKind::Function {
+                return Err(ParsingError::expected_next_token(
+                    TokenKind::Function.to_string(),
+                    self.current_token.kind.to_string(),
+                    self.position,
+                ));
+            }
+        }
+
         self.next_token();
 
         let generics = if self.current_token.kind == TokenKind::LT {
@@ -755,7 +1376,33 @@ impl<'a> Parser<'a> {
                 ParameterKind::Normal
             };
 
-            if let TokenKind::IDENT(identifier) = self.current_token.kind.clone() {
+            let position = self.position;
+
+            let pattern = match self.current_token.kind {
+                TokenKind::LBrace | TokenKind::LBracket
+                    if parameter_kind == ParameterKind::Normal =>
+                {
+                    Some(self.parse_pattern()?)
+                }
+                _ => None,
+            };
+
+            if let Some(pattern) = pattern {
+                self.expect_token(&TokenKind::Colon)?;
+
+                let data_type = self.parse_data_type()?;
+
+                parameters.push(Parameter {
+                    identifier: Identifier {
+                        value: pattern.to_string(),
+                        position,
+                    },
+                    data_type,
+                    kind: parameter_kind,
+                    pattern: Some(pattern),
+                    position,
+                });
+            } else if let TokenKind::IDENT(identifier) = self.current_token.kind.clone() {
                 self.next_token();
                 self.expect_token(&TokenKind::Colon)?;
 
@@ -768,6 +1415,7 @@ impl<'a> Parser<'a> {
                     },
                     data_type,
                     kind: parameter_kind,
+                    pattern: None,
                     position: self.position,
                 });
             } else {
@@ -782,13 +1430,18 @@ impl<'a> Parser<'a> {
                 break;
             }
 
-            self.expect_token(&TokenKind::Comma)?;
+            self.expect_separator(&TokenKind::RParen)?;
         }
 
         self.expect_token(&TokenKind::RParen)?;
-        self.expect_token(&TokenKind::Arrow)?;
 
-        let return_type = self.parse_data_type()?;
+        // The return type can be omitted for functions returning nothing: `fn() {}`.
+        let return_type = if self.current_token.kind == TokenKind::LBrace {
+            None
+        } else {
+            self.expect_token(&TokenKind::Arrow)?;
+            Some(self.parse_data_type()?)
+        };
 
         let body = match self.current_token.kind {
             TokenKind::LBrace => self.parse_block_expression()?,
@@ -797,7 +1450,7 @@ impl<'a> Parser<'a> {
 
                 BlockExpression {
                     statements: vec![Statement::ReturnStatement(ReturnStatement {
-                        value: self.parse_expression(&Priority::Lowest)?,
+                        value: Some(self.parse_expression(&Priority::Lowest)?),
                         position: self.position,
                     })],
                     position: self.position,
@@ -817,32 +1470,81 @@ impl<'a> Parser<'a> {
             parameters,
             return_type,
             body,
+            is_const,
             position: self.position,
         })
     }
 
+    /// Parses a destructuring pattern of a parameter: `{x, y}` or `[a, b]`.
+    fn parse_pattern(&mut self) -> ParseResult<Pattern> {
+        let is_struct = self.current_token.kind == TokenKind::LBrace;
+        let closing = match is_struct {
+            true => TokenKind::RBrace,
+            false => TokenKind::RBracket,
+        };
+
+        self.next_token();
+
+        let mut identifiers = Vec::new();
+
+        while self.current_token.kind != closing {
+            match self.current_token.kind.clone() {
+                TokenKind::IDENT(identifier) => identifiers.push(Identifier {
+                    value: identifier.to_string(),
+                    position: self.position,
+                }),
+                kind => {
+                    return Err(ParsingError::expected_next_token(
+                        TokenKind::IDENT("").to_string(),
+                        kind.to_string(),
+                        self.position,
+                    ))
+                }
+            }
+
+            self.next_token();
+
+            if self.current_token.kind == closing {
+                break;
+            }
+
+            self.expect_separator(&closing)?;
+        }
+
+        self.expect_token(&closing)?;
+
+        Ok(match is_struct {
+            true => Pattern::Struct(identifiers),
+            false => Pattern::Array(identifiers),
+        })
+    }
+
     fn parse_if_expression(&mut self) -> ParseResult<IfExpression> {
+        let keyword = self.position;
         self.next_token();
 
         let condition = self.parse_expression(&Priority::Lowest)?;
         self.next_token();
 
-        let consequence = self.parse_block_expression()?;
+        let consequence = self.parse_if_arm(keyword)?;
 
         let alternative = if self.peek_token.kind == TokenKind::Else {
             self.next_token();
             self.next_token();
 
+            // `else if` is an `else` block whose value is the nested `if`.
             if self.current_token.kind == TokenKind::If {
                 Some(Box::new(BlockExpression {
-                    statements: vec![Statement::ExpressionStatement(ExpressionStatement {
-                        expression: Expression::IfExpression(self.parse_if_expression()?),
-                        position: self.position,
-                    })],
+                    statements: vec![Statement::ReturnExpressionStatement(
+                        ReturnExpressionStatement {
+                            value: Expression::IfExpression(self.parse_if_expression()?),
+                            position: self.position,
+                        },
+                    )],
                     position: self.position,
                 }))
             } else {
-                Some(Box::new(self.parse_block_expression()?))
+                Some(Box::new(self.parse_if_arm(keyword)?))
             }
         } else {
             None
@@ -856,56 +1558,301 @@ impl<'a> Parser<'a> {
         })
     }
 
-    fn parse_data_type(&mut self) -> ParseResult<DataType> {
+    /// Parses a block, or a single statement or expression (e.g. `if (c) return 1;`) wrapped in one.
+    fn parse_if_arm(&mut self, keyword: Position) -> ParseResult<BlockExpression> {
+        if self.current_token.kind == TokenKind::LBrace {
+            return self.parse_block_expression();
+        }
+
         let position = self.position;
+        let statement = match self.current_token.kind {
+            TokenKind::Return | TokenKind::Throw | TokenKind::Break | TokenKind::Continue => {
+                self.parse_statement()?
+            }
+            _ => Statement::ReturnExpressionStatement(ReturnExpressionStatement {
+                value: self.parse_expression(&Priority::Lowest)?,
+                position: self.position,
+            }),
+        };
 
-        let result = self.parse_data_type_without_next();
-        self.next_token();
+        self.braceless = Some(keyword);
 
-        result.map(|data_type| DataType {
-            kind: data_type,
+        Ok(BlockExpression {
+            statements: vec![statement],
             position,
         })
     }
 
-    fn parse_data_type_without_next(&mut self) -> ParseResult<DataTypeKind> {
-        let mut data_type = match self.current_token.kind {
-            TokenKind::NumberType => Ok(DataTypeKind::Number),
-            TokenKind::StringType => Ok(DataTypeKind::String),
-            TokenKind::BooleanType => Ok(DataTypeKind::Boolean),
-            TokenKind::Function => Ok(DataTypeKind::Fn(self.parse_function_type()?)),
-            TokenKind::IDENT(ref ident) => Ok(DataTypeKind::Custom(ident.to_string())),
-            _ => Err(ParsingError::expected_next_token(
-                TokenKind::NumberType.to_string(),
-                self.current_token.kind.to_string(),
-                self.position,
-            )),
-        };
+    fn parse_try_expression(&mut self) -> ParseResult<TryExpression> {
+        self.next_token();
 
-        if self.peek_token(&TokenKind::LT) {
-            let generics = self.parse_generic()?;
+        let body = self.parse_block_expression()?;
+        self.next_token();
 
-            data_type = Ok(DataTypeKind::Generic(generics));
-        }
+        self.expect_token(&TokenKind::Catch)?;
+        self.expect_token(&TokenKind::LParen)?;
 
-        while self.peek_token(&TokenKind::LBracket) {
-            self.next_token();
-            self.next_token();
+        let identifier = Identifier {
+            value: ident_token_to_string! { self },
+            position: self.position,
+        };
+        self.next_token();
 
-            if self.current_token.kind != TokenKind::RBracket {
-                return Err(ParsingError::expected_next_token(
-                    TokenKind::RBracket.to_string(),
-                    self.current_token.kind.to_string(),
-                    self.position,
-                ));
-            }
+        self.expect_token(&TokenKind::RParen)?;
 
-            data_type = data_type.map(|data_type| {
-                DataTypeKind::Array(Box::new(DataType {
-                    kind: data_type,
-                    position: self.position,
-                }))
-            });
+        if self.current_token.kind != TokenKind::LBrace {
+            return Err(ParsingError::expected_next_token(
+                TokenKind::LBrace.to_string(),
+                self.current_token.kind.to_string(),
+                self.position,
+            ));
+        }
+
+        let handler = self.parse_block_expression()?;
+
+        Ok(TryExpression {
+            body,
+            identifier,
+            handler,
+            position: self.position,
+        })
+    }
+
+    fn parse_match_expression(&mut self) -> ParseResult<MatchExpression> {
+        let position = self.position;
+        self.next_token();
+
+        let value = self.parse_expression(&Priority::Lowest)?;
+        self.next_token();
+
+        self.expect_token(&TokenKind::LBrace)?;
+
+        let mut arms = Vec::new();
+
+        while !matches!(self.current_token.kind, TokenKind::RBrace | TokenKind::EOF) {
+            arms.push(self.parse_match_arm()?);
+            self.next_token();
+
+            if self.current_token.kind == TokenKind::RBrace {
+                break;
+            }
+
+            self.expect_separator(&TokenKind::RBrace)?;
+        }
+
+        self.expect_closing_brace()?;
+
+        Ok(MatchExpression {
+            value: Box::new(value),
+            arms,
+            position,
+        })
+    }
+
+    /// The body of an arm is a block, or a single expression which is its value.
+    fn parse_match_arm(&mut self) -> ParseResult<MatchArm> {
+        let position = self.position;
+
+        let pattern = self.parse_match_pattern()?;
+        self.next_token();
+
+        let guard = match self.current_token.kind {
+            TokenKind::If => {
+                self.next_token();
+                let guard = self.parse_expression(&Priority::Lowest)?;
+                self.next_token();
+
+                Some(guard)
+            }
+            _ => None,
+        };
+
+        self.expect_token(&TokenKind::DoubleArrow)?;
+
+        let body = match self.current_token.kind {
+            TokenKind::LBrace => self.parse_block_expression()?,
+            _ => {
+                let position = self.position;
+
+                BlockExpression {
+                    statements: vec![Statement::ReturnExpressionStatement(
+                        ReturnExpressionStatement {
+                            value: self.parse_expression(&Priority::Lowest)?,
+                            position: self.position,
+                        },
+                    )],
+                    position,
+                }
+            }
+        };
+
+        Ok(MatchArm {
+            pattern,
+            guard,
+            body,
+            position,
+        })
+    }
+
+    /// A pattern is `_`, a name to bind the value to, or a literal without interpolations (a number may be negative).
+    fn parse_match_pattern(&mut self) -> ParseResult<MatchPattern> {
+        let position = self.position;
+
+        let literal = match self.current_token.kind {
+            TokenKind::IDENT("_") => return Ok(MatchPattern::Wildcard(position)),
+            TokenKind::IDENT(name) => {
+                return Ok(MatchPattern::Binding(Identifier {
+                    value: name.to_string(),
+                    position,
+                }))
+            }
+            TokenKind::Number(value) => Literal::NumberLiteral(NumberLiteral { value, position }),
+            TokenKind::Minus => match self.peek_token.kind {
+                TokenKind::Number(value) => {
+                    self.next_token();
+
+                    Literal::NumberLiteral(NumberLiteral {
+                        value: -value,
+                        position,
+                    })
+                }
+                _ => {
+                    return Err(ParsingError::unexpected_token(
+                        self.peek_token.kind.to_string(),
+                        self.peek_token.position,
+                    ))
+                }
+            },
+            TokenKind::String(value) => match self.parse_string(value)? {
+                Expression::Literal(literal) => literal,
+                _ => return Err(ParsingError::unexpected_token("${", position)),
+            },
+            TokenKind::Boolean(value) => {
+                Literal::BooleanLiteral(BooleanLiteral { value, position })
+            }
+            TokenKind::Null => Literal::NullLiteral(NullLiteral { position }),
+            _ => {
+                return Err(ParsingError::unexpected_token(
+                    self.current_token.kind.to_string(),
+                    position,
+                ))
+            }
+        };
+
+        Ok(MatchPattern::Literal(literal))
+    }
+
+    fn parse_data_type(&mut self) -> ParseResult<DataType> {
+        let position = self.position;
+
+        let result = self.parse_data_type_without_next();
+        self.next_token();
+
+        result.map(|data_type| DataType {
+            kind: data_type,
+            position,
+        })
+    }
+
+    /// Parses a data type, which can be nested (e.g. `(fn(number) -> number[])[]`) as deep as expressions.
+    fn parse_data_type_without_next(&mut self) -> ParseResult<DataTypeKind> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            return Err(ParsingError::nesting_too_deep(
+                MAX_NESTING_DEPTH,
+                self.position,
+            ));
+        }
+
+        self.depth += 1;
+        let data_type = self.parse_data_type_without_depth();
+        self.depth -= 1;
+
+        data_type
+    }
+
+    fn parse_data_type_without_depth(&mut self) -> ParseResult<DataTypeKind> {
+        let mut data_type = match self.current_token.kind {
+            TokenKind::NumberType => Ok(DataTypeKind::Number),
+            TokenKind::StringType => Ok(DataTypeKind::String),
+            TokenKind::BooleanType => Ok(DataTypeKind::Boolean),
+            TokenKind::VoidType => Ok(DataTypeKind::Void),
+            TokenKind::NeverType => Ok(DataTypeKind::Never),
+            TokenKind::Function => Ok(DataTypeKind::Fn(self.parse_function_type()?)),
+            TokenKind::IDENT(ref ident) => Ok(DataTypeKind::Custom(ident.to_string())),
+            // Parentheses group a type, e.g. `(fn() -> number)[]` is an array of functions.
+            TokenKind::LParen => {
+                self.next_token();
+                let data_type = self.parse_data_type_without_next()?;
+                self.next_token();
+
+                if self.current_token.kind != TokenKind::RParen {
+                    return Err(ParsingError::expected_next_token(
+                        TokenKind::RParen.to_string(),
+                        self.current_token.kind.to_string(),
+                        self.position,
+                    ));
+                }
+
+                Ok(data_type)
+            }
+            _ => Err(ParsingError::expected_next_token(
+                TokenKind::NumberType.to_string(),
+                self.current_token.kind.to_string(),
+                self.position,
+            )),
+        };
+
+        if self.peek_token(&TokenKind::LT) {
+            let generics = self.parse_generic()?;
+
+            data_type = Ok(DataTypeKind::Generic(generics));
+        }
+
+        while self.peek_token(&TokenKind::LBracket) {
+            self.next_token();
+            self.next_token();
+
+            let size = match self.current_token.kind {
+                TokenKind::RBracket => None,
+                _ => {
+                    let size = self.parse_expression(&Priority::Lowest)?;
+                    self.next_token();
+
+                    Some(Box::new(size))
+                }
+            };
+
+            if self.current_token.kind != TokenKind::RBracket {
+                return Err(ParsingError::expected_next_token(
+                    TokenKind::RBracket.to_string(),
+                    self.current_token.kind.to_string(),
+                    self.position,
+                ));
+            }
+
+            data_type = data_type.map(|data_type| {
+                let element = Box::new(DataType {
+                    kind: data_type,
+                    position: self.position,
+                });
+
+                match size {
+                    Some(size) => DataTypeKind::FixedArray(element, size),
+                    None => DataTypeKind::Array(element),
+                }
+            });
+        }
+
+        // `number[]?` is an optional array, an array of optionals is written `(number?)[]`.
+        if self.peek_token(&TokenKind::Question) {
+            self.next_token();
+
+            data_type = data_type.map(|data_type| {
+                DataTypeKind::Optional(Box::new(DataType {
+                    kind: data_type,
+                    position: self.position,
+                }))
+            });
         }
 
         data_type
@@ -941,7 +1888,7 @@ impl<'a> Parser<'a> {
                 break;
             }
 
-            self.expect_token(&TokenKind::Comma)?;
+            self.expect_separator(&TokenKind::RParen)?;
         }
 
         self.expect_token(&TokenKind::RParen)?;
@@ -977,7 +1924,7 @@ impl<'a> Parser<'a> {
                 break;
             }
 
-            self.expect_token(&TokenKind::Comma)?;
+            self.expect_separator(&TokenKind::GT)?;
         }
 
         Ok(Generic::new(
@@ -1007,9 +1954,1116 @@ impl<'a> Parser<'a> {
                 break;
             }
 
-            self.expect_token(&TokenKind::Comma)?;
+            self.expect_separator(&TokenKind::GT)?;
         }
 
         Ok(generics)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string_literal(value: &str) -> Expression {
+        Expression::Literal(Literal::StringLiteral(StringLiteral {
+            value: value.to_string(),
+            position: Position::default(),
+        }))
+    }
+
+    fn parse_expression(source: &str) -> ParseResult<Expression> {
+        Parser::from(source).parse_single_expression()
+    }
+
+    #[test]
+    fn test_string_escaped_braces() {
+        assert_eq!(
+            parse_expression(r#""\{x\} \"y\"""#),
+            Ok(string_literal(r#"{x} "y""#))
+        );
+    }
+
+    #[test]
+    fn test_string_interpolation() {
+        assert_eq!(
+            parse_expression(r#""a ${"b ${1}"} c""#),
+            Ok(Expression::InterpolationExpression(
+                InterpolationExpression {
+                    parts: vec![
+                        string_literal("a "),
+                        Expression::InterpolationExpression(InterpolationExpression {
+                            parts: vec![
+                                string_literal("b "),
+                                Expression::Literal(Literal::NumberLiteral(NumberLiteral {
+                                    value: 1.0,
+                                    position: Position::default(),
+                                })),
+                            ],
+                            position: Position::default(),
+                        }),
+                        string_literal(" c"),
+                    ],
+                    position: Position::default(),
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_string_interpolation_unclosed() {
+        assert_eq!(
+            parse_expression(r#""a ${1""#).map_err(|error| error.kind),
+            Err(ParsingErrorKind::ExpectedNextToken(
+                TokenKind::RBrace.to_string(),
+                TokenKind::EOF.to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_string_interpolation_position() {
+        let Ok(Expression::InterpolationExpression(expression)) = parse_expression(r#""ab ${y}""#)
+        else {
+            panic!("expected an interpolation");
+        };
+        let Expression::Literal(Literal::Identifier(identifier)) = &expression.parts[1] else {
+            panic!("expected an identifier");
+        };
+
+        assert_eq!((identifier.position.0, identifier.position.1), (1, 7));
+
+        let error = parse_expression(r#""ab ${1""#).unwrap_err();
+        assert_eq!((error.position.0, error.position.1), (1, 7));
+    }
+
+    #[test]
+    fn test_string_interpolation_nesting_too_deep() {
+        let source =
+            (0..MAX_NESTING_DEPTH).fold("1".to_string(), |source, _| format!(r#""${{{source}}}""#));
+
+        assert_eq!(
+            parse_expression(&source).map_err(|error| error.kind),
+            Err(ParsingErrorKind::NestingTooDeep(MAX_NESTING_DEPTH))
+        );
+    }
+
+    #[test]
+    fn test_spans() {
+        let source = "let x = 1;\nlet y = 2;\n";
+
+        let mut parser = Parser::from(source);
+        parser.parse_program().unwrap();
+
+        assert_eq!(parser.spans, vec![0..11, 11..22]);
+    }
+
+    #[test]
+    fn test_reparse() {
+        let source = "let x = 1;\nlet y = 2;\nlet z = 3;\n";
+
+        let mut parser = Parser::from(source);
+        let program = parser.parse_program().unwrap();
+
+        let edited = "let x = 1;\nlet y = 20 + 2;\nlet z = 3;\n";
+        let (reparsed, spans) = parser.reparse(edited, &program, 19..20, 6).unwrap();
+
+        assert_eq!(reparsed[0], program[0]);
+        assert_eq!(reparsed[2], program[2]);
+        assert_eq!(reparsed, Parser::from(edited).parse_program().unwrap());
+        assert_eq!(spans, vec![0..11, 11..27, 27..38]);
+    }
+
+    #[test]
+    fn test_reparse_defines() {
+        let source = "let x = 1;
+#if DEBUG { let y = 2; }
+";
+
+        let mut parser = Parser::from(source);
+        parser.defines = vec![String::from("DEBUG")];
+        let program = parser.parse_program().unwrap();
+
+        let edited = "let x = 1;
+#if DEBUG { let y = 20; }
+";
+        let (reparsed, _) = parser.reparse(edited, &program, 31..32, 2).unwrap();
+
+        assert_eq!(reparsed.len(), 2);
+        assert!(
+            matches!(&reparsed[1], Statement::LetStatement(statement) if statement.identifier.value == "y")
+        );
+    }
+
+    #[test]
+    fn test_parse_single_expression() {
+        assert!(parse_expression("x + 1").is_ok());
+        assert!(parse_expression("x + 1;").is_ok());
+        assert_eq!(
+            parse_expression("x + 1; y").map_err(|error| error.kind),
+            Err(ParsingErrorKind::UnexpectedToken("y".to_string()))
+        );
+    }
+
+    fn number(value: f64) -> Expression {
+        Expression::Literal(Literal::NumberLiteral(NumberLiteral {
+            value,
+            position: Position::default(),
+        }))
+    }
+
+    fn infix(left: Expression, operator: InfixOperator, right: Expression) -> Expression {
+        Expression::InfixExpression(InfixExpression {
+            left: Box::new(left),
+            operator,
+            right: Box::new(right),
+            position: Position::default(),
+        })
+    }
+
+    #[test]
+    fn test_pow_right_associative() {
+        assert_eq!(
+            parse_expression("2 ** 3 ** 2"),
+            Ok(infix(
+                number(2.0),
+                InfixOperator::Pow,
+                infix(number(3.0), InfixOperator::Pow, number(2.0))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_pow_priority() {
+        assert_eq!(
+            parse_expression("-2 ** 2"),
+            Ok(Expression::PrefixExpression(PrefixExpression {
+                operator: PrefixOperator::Minus,
+                right: Box::new(infix(number(2.0), InfixOperator::Pow, number(2.0))),
+                position: Position::default(),
+            }))
+        );
+
+        assert_eq!(
+            parse_expression("2 * 3 ** 2"),
+            Ok(infix(
+                number(2.0),
+                InfixOperator::Asterisk,
+                infix(number(3.0), InfixOperator::Pow, number(2.0))
+            ))
+        );
+    }
+
+    fn parse_error(source: &str) -> ParsingErrorKind {
+        Parser::from(source).parse_program().unwrap_err()[0]
+            .kind
+            .clone()
+    }
+
+    #[test]
+    fn test_unterminated_comment() {
+        for (source, line, column) in [
+            ("let x = 1;\n  /* a", 2, 3),
+            ("let x = 1 /* a", 1, 11),
+            ("let x = /* a */ 1; /* b /* c */", 1, 20),
+        ] {
+            let error = Parser::from(source).parse_program().unwrap_err()[0].clone();
+
+            assert_eq!(
+                error.kind,
+                ParsingErrorKind::UnterminatedComment,
+                "{source}"
+            );
+            assert_eq!(
+                (error.position.0, error.position.1),
+                (line, column),
+                "{source}"
+            );
+        }
+
+        assert_eq!(
+            Parser::from("1 + /* 2")
+                .parse_single_expression()
+                .unwrap_err()
+                .kind,
+            ParsingErrorKind::UnterminatedComment
+        );
+    }
+
+    #[test]
+    fn test_expected_closing_paren() {
+        assert_eq!(
+            parse_error("let x = (1 + 2;"),
+            ParsingErrorKind::ExpectedNextToken(String::from(")"), String::from(";"))
+        );
+    }
+
+    #[test]
+    fn test_expected_assign() {
+        assert_eq!(
+            parse_error("let x 5;"),
+            ParsingErrorKind::ExpectedNextToken(String::from("="), String::from("5"))
+        );
+    }
+
+    #[test]
+    fn test_expected_separator() {
+        assert_eq!(
+            parse_error("let x = [1, 2 3];"),
+            ParsingErrorKind::ExpectedOneOf(
+                vec![String::from(","), String::from("]")],
+                String::from("3")
+            )
+        );
+    }
+
+    fn identifier(value: &str) -> Expression {
+        Expression::Literal(Literal::Identifier(Identifier {
+            value: value.to_string(),
+            position: Position::default(),
+        }))
+    }
+
+    #[test]
+    fn test_member_access_chain() {
+        assert_eq!(
+            parse_expression("a.b.c[0].d"),
+            Ok(infix(
+                Expression::IndexExpression(IndexExpression {
+                    left: Box::new(infix(
+                        infix(identifier("a"), InfixOperator::Dot, identifier("b")),
+                        InfixOperator::Dot,
+                        identifier("c")
+                    )),
+                    index: Box::new(number(0.0)),
+                    position: Position::default(),
+                }),
+                InfixOperator::Dot,
+                identifier("d")
+            ))
+        );
+
+        assert_eq!(
+            parse_expression("a.b + 1"),
+            Ok(infix(
+                infix(identifier("a"), InfixOperator::Dot, identifier("b")),
+                InfixOperator::Plus,
+                number(1.0)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_range_index() {
+        let range = |start: Option<f64>, end: Option<f64>| {
+            Ok(Expression::IndexExpression(IndexExpression {
+                left: Box::new(identifier("a")),
+                index: Box::new(Expression::RangeExpression(RangeExpression {
+                    start: start.map(|value| Box::new(number(value))),
+                    end: end.map(|value| Box::new(number(value))),
+                    position: Position::default(),
+                })),
+                position: Position::default(),
+            }))
+        };
+
+        assert_eq!(parse_expression("a[1..2]"), range(Some(1.0), Some(2.0)));
+        assert_eq!(parse_expression("a[..2]"), range(None, Some(2.0)));
+        assert_eq!(parse_expression("a[1..]"), range(Some(1.0), None));
+        assert_eq!(parse_expression("a[..]"), range(None, None));
+
+        // A range is only valid as an index, so it never reaches the analyzer on its own.
+        assert_eq!(
+            parse_error("let x = 1..2;"),
+            ParsingErrorKind::ExpectedNextToken(
+                TokenKind::Semicolon.to_string(),
+                TokenKind::DoubleDot.to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_nullish_coalescing() {
+        assert_eq!(
+            parse_expression("a?.b ?? 1 + 2"),
+            Ok(infix(
+                infix(identifier("a"), InfixOperator::OptionalDot, identifier("b")),
+                InfixOperator::NullishCoalescing,
+                infix(number(1.0), InfixOperator::Plus, number(2.0))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_bare_return() {
+        assert_eq!(
+            Parser::from("return;").parse_program(),
+            Ok(vec![Statement::ReturnStatement(ReturnStatement {
+                value: None,
+                position: Position::default(),
+            })])
+        );
+    }
+
+    #[test]
+    fn test_empty_function() {
+        assert_eq!(
+            parse_expression("fn() {}"),
+            Ok(Expression::Literal(Literal::FunctionLiteral(Box::new(
+                FunctionLiteral {
+                    parameters: Vec::new(),
+                    body: BlockExpression {
+                        statements: Vec::new(),
+                        position: Position::default(),
+                    },
+                    generics: None,
+                    return_type: None,
+                    is_const: false,
+                    position: Position::default(),
+                }
+            ))))
+        );
+    }
+
+    #[test]
+    fn test_const_function() {
+        let is_const = |source| match parse_expression(source) {
+            Ok(Expression::Literal(Literal::FunctionLiteral(function))) => function.is_const,
+            other => panic!("expected a function literal, got {other:?}"),
+        };
+
+        assert!(is_const("const fn(x: number) -> number { x * x }"));
+        assert!(!is_const("fn(x: number) -> number { x * x }"));
+        assert_eq!(
+            parse_error("let x = const 1;"),
+            ParsingErrorKind::ExpectedNextToken(String::from("fn"), String::from("1"))
+        );
+    }
+
+    #[test]
+    fn test_cast() {
+        let cast = |expression, kind| {
+            Expression::CastExpression(CastExpression {
+                expression: Box::new(expression),
+                data_type: DataType::new(kind, Position::default()),
+                position: Position::default(),
+            })
+        };
+
+        assert_eq!(
+            parse_expression("1 + -x as string"),
+            Ok(infix(
+                number(1.0),
+                InfixOperator::Plus,
+                cast(
+                    Expression::PrefixExpression(PrefixExpression {
+                        operator: PrefixOperator::Minus,
+                        right: Box::new(identifier("x")),
+                        position: Position::default(),
+                    }),
+                    DataTypeKind::String
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn test_block_value() {
+        let block = |source| match parse_expression(source) {
+            Ok(Expression::BlockExpression(block)) => block.statements,
+            other => panic!("expected a block expression, got {other:?}"),
+        };
+
+        assert_eq!(
+            block("{ 1; 2 }"),
+            vec![
+                Statement::ExpressionStatement(ExpressionStatement {
+                    expression: number(1.0),
+                    position: Position::default(),
+                }),
+                Statement::ReturnExpressionStatement(ReturnExpressionStatement {
+                    value: number(2.0),
+                    position: Position::default(),
+                }),
+            ]
+        );
+        assert_eq!(
+            block("{ return 2; }"),
+            vec![Statement::ReturnStatement(ReturnStatement {
+                value: Some(number(2.0)),
+                position: Position::default(),
+            })]
+        );
+        assert!(matches!(
+            parse_expression("{ 1 2 }"),
+            Err(ParsingError {
+                kind: ParsingErrorKind::UnexpectedToken(_),
+                ..
+            })
+        ));
+        assert!(parse_expression("[fn() -> number { 1 }, fn() -> number { 2 }]").is_ok());
+    }
+
+    #[test]
+    fn test_sequence_expression() {
+        assert_eq!(
+            parse_expression("(1, f(x), x + 1)"),
+            Ok(Expression::SequenceExpression(SequenceExpression {
+                expressions: vec![
+                    number(1.0),
+                    Expression::CallExpression(CallExpression {
+                        function: Box::new(identifier("f")),
+                        arguments: vec![identifier("x")],
+                        position: Position::default(),
+                    }),
+                    infix(identifier("x"), InfixOperator::Plus, number(1.0)),
+                ],
+                position: Position::default(),
+            }))
+        );
+        assert_eq!(parse_expression("(1)"), Ok(number(1.0)));
+        assert!(matches!(
+            parse_expression("(1, 2"),
+            Err(ParsingError {
+                kind: ParsingErrorKind::ExpectedNextToken(..),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_expression_recovery() {
+        let errors = Parser::from(
+            r#"
+            let a = 1 + * 2;
+            let b = f((/ 3), 4);
+            let c = 5;
+            let d = ;
+            "#,
+        )
+        .parse_program()
+        .unwrap_err();
+
+        assert_eq!(
+            errors
+                .into_iter()
+                .map(|error| error.kind)
+                .collect::<Vec<_>>(),
+            vec![
+                ParsingErrorKind::UnexpectedToken(String::from("*")),
+                ParsingErrorKind::UnexpectedToken(String::from("/")),
+                ParsingErrorKind::UnexpectedToken(String::from(";")),
+            ]
+        );
+        assert_eq!(
+            parse_expression("1 + * 2"),
+            Err(ParsingError::unexpected_token("*", Position::default()))
+        );
+    }
+
+    #[test]
+    fn test_fixed_array_type() {
+        assert_eq!(
+            Parser::from("let a: number[1 + 2] = [1, 2, 3];").parse_program(),
+            Ok(vec![Statement::LetStatement(LetStatement {
+                identifier: Identifier {
+                    value: String::from("a"),
+                    position: Position::default(),
+                },
+                value: Expression::Literal(Literal::ArrayLiteral(ArrayLiteral {
+                    elements: vec![number(1.0), number(2.0), number(3.0)],
+                    position: Position::default(),
+                })),
+                data_type: Some(DataType::new(
+                    DataTypeKind::FixedArray(
+                        Box::new(DataType::new(DataTypeKind::Number, Position::default())),
+                        Box::new(infix(number(1.0), InfixOperator::Plus, number(2.0))),
+                    ),
+                    Position::default(),
+                )),
+                is_mutable: false,
+                position: Position::default(),
+            })])
+        );
+    }
+
+    #[test]
+    fn test_try_expression() {
+        let block = |statements| BlockExpression {
+            statements,
+            position: Position::default(),
+        };
+        let value = |expression| {
+            Statement::ReturnExpressionStatement(ReturnExpressionStatement {
+                value: expression,
+                position: Position::default(),
+            })
+        };
+
+        assert_eq!(
+            parse_expression("try { 1 } catch (e) { e }"),
+            Ok(Expression::TryExpression(TryExpression {
+                body: block(vec![value(number(1.0))]),
+                identifier: Identifier {
+                    value: String::from("e"),
+                    position: Position::default(),
+                },
+                handler: block(vec![value(identifier("e"))]),
+                position: Position::default(),
+            }))
+        );
+        assert_eq!(
+            parse_error("try { 1 } (e) { e };"),
+            ParsingErrorKind::ExpectedNextToken(String::from("catch"), String::from("("))
+        );
+    }
+
+    #[test]
+    fn test_match_expression() {
+        let block = |expression| BlockExpression {
+            statements: vec![Statement::ReturnExpressionStatement(
+                ReturnExpressionStatement {
+                    value: expression,
+                    position: Position::default(),
+                },
+            )],
+            position: Position::default(),
+        };
+        let arm = |pattern, guard, body| MatchArm {
+            pattern,
+            guard,
+            body: block(body),
+            position: Position::default(),
+        };
+
+        assert_eq!(
+            parse_expression(r#"match n { -1 => "a", x if x > 0 => { "b" }, _ => "c", }"#),
+            Ok(Expression::MatchExpression(MatchExpression {
+                value: Box::new(identifier("n")),
+                arms: vec![
+                    arm(
+                        MatchPattern::Literal(Literal::NumberLiteral(NumberLiteral {
+                            value: -1.0,
+                            position: Position::default(),
+                        })),
+                        None,
+                        string_literal("a")
+                    ),
+                    arm(
+                        MatchPattern::Binding(Identifier {
+                            value: String::from("x"),
+                            position: Position::default(),
+                        }),
+                        Some(infix(identifier("x"), InfixOperator::GT, number(0.0))),
+                        string_literal("b")
+                    ),
+                    arm(
+                        MatchPattern::Wildcard(Position::default()),
+                        None,
+                        string_literal("c")
+                    ),
+                ],
+                position: Position::default(),
+            }))
+        );
+        assert_eq!(
+            parse_error("let x = match n { 1 2 };"),
+            ParsingErrorKind::ExpectedNextToken(String::from("=>"), String::from("2"))
+        );
+        assert_eq!(
+            parse_error("let x = match n { 1 => 2"),
+            ParsingErrorKind::ExpectedOneOf(
+                vec![String::from(","), String::from("}")],
+                String::from("EOF")
+            )
+        );
+        assert_eq!(
+            parse_error("let x = match n { n + 1 => 2 };"),
+            ParsingErrorKind::ExpectedNextToken(String::from("=>"), String::from("+"))
+        );
+    }
+
+    #[test]
+    fn test_throw_statement() {
+        assert_eq!(
+            Parser::from(r#"throw "oops";"#).parse_program(),
+            Ok(vec![Statement::ThrowStatement(ThrowStatement {
+                value: string_literal("oops"),
+                position: Position::default(),
+            })])
+        );
+        assert_eq!(
+            parse_error(r#"throw "oops""#),
+            ParsingErrorKind::ExpectedNextToken(String::from(";"), String::from("EOF"))
+        );
+    }
+
+    #[test]
+    fn test_too_many_errors() {
+        let source = "let = 1;".repeat(10);
+        let mut parser = Parser::from(source.as_str());
+        parser.max_errors = Some(3);
+
+        let errors = parser.parse_program().unwrap_err();
+
+        assert_eq!(errors.len(), 4);
+        assert_eq!(
+            errors.last().unwrap().kind,
+            ParsingErrorKind::TooManyErrors(3)
+        );
+        assert!(Parser::from("let = 1;").parse_program().unwrap_err().len() < MAX_ERRORS);
+
+        let mut parser = Parser {
+            max_errors: None,
+            ..Parser::from(source.as_str())
+        };
+        assert_eq!(parser.parse_program().unwrap_err().len(), 10);
+
+        let mut parser = Parser {
+            lexer: Lexer::new("let x = 1;"),
+            ..Default::default()
+        };
+        assert!(parser.parse_program().is_ok());
+    }
+
+    #[test]
+    fn test_nested_data_types() {
+        fn data_type(source: &str) -> Result<DataTypeKind, ParsingErrorKind> {
+            match Parser::from(format!("declare x = {source};").as_str()).parse_program() {
+                Ok(program) => match &program[0] {
+                    Statement::DeclareStatement(statement) => Ok(statement.data_type.kind.clone()),
+                    statement => panic!("expected a declare statement, got {statement:?}"),
+                },
+                Err(errors) => Err(errors[0].kind.clone()),
+            }
+        }
+        let data_type_of = |kind| Box::new(DataType::new(kind, Position::default()));
+        let function = |parameters: Vec<DataTypeKind>, return_type| {
+            DataTypeKind::Fn(FunctionType {
+                generics: None,
+                parameters: parameters
+                    .into_iter()
+                    .map(|kind| {
+                        (
+                            DataType::new(kind, Position::default()),
+                            ParameterKind::Normal,
+                        )
+                    })
+                    .collect(),
+                return_type: data_type_of(return_type),
+                position: Position::default(),
+            })
+        };
+
+        assert_eq!(
+            data_type("number[]?"),
+            Ok(DataTypeKind::Optional(data_type_of(DataTypeKind::Array(
+                data_type_of(DataTypeKind::Number)
+            ))))
+        );
+        assert_eq!(
+            data_type("(number?)[]"),
+            Ok(DataTypeKind::Array(data_type_of(DataTypeKind::Optional(
+                data_type_of(DataTypeKind::Number)
+            ))))
+        );
+        assert_eq!(
+            data_type("(fn(number) -> number)[]"),
+            Ok(DataTypeKind::Array(data_type_of(function(
+                vec![DataTypeKind::Number],
+                DataTypeKind::Number
+            ))))
+        );
+        assert_eq!(
+            data_type("fn(number) -> number[]"),
+            Ok(function(
+                vec![DataTypeKind::Number],
+                DataTypeKind::Array(data_type_of(DataTypeKind::Number))
+            ))
+        );
+        assert_eq!(
+            data_type("fn(number, string) -> Result"),
+            Ok(function(
+                vec![DataTypeKind::Number, DataTypeKind::String],
+                DataTypeKind::Custom(String::from("Result"))
+            ))
+        );
+        assert_eq!(
+            data_type("fn(fn(number) -> (fn() -> string)[]) -> ((boolean))"),
+            Ok(function(
+                vec![function(
+                    vec![DataTypeKind::Number],
+                    DataTypeKind::Array(data_type_of(function(vec![], DataTypeKind::String)))
+                )],
+                DataTypeKind::Boolean
+            ))
+        );
+        assert_eq!(
+            data_type("(number"),
+            Err(ParsingErrorKind::ExpectedNextToken(
+                String::from(")"),
+                String::from(";")
+            ))
+        );
+
+        let deep = format!(
+            "{}number{}",
+            "(".repeat(MAX_NESTING_DEPTH),
+            ")".repeat(MAX_NESTING_DEPTH)
+        );
+
+        assert_eq!(
+            data_type(&deep),
+            Err(ParsingErrorKind::NestingTooDeep(MAX_NESTING_DEPTH))
+        );
+    }
+
+    #[test]
+    fn test_if_without_braces() {
+        let block = |expression| BlockExpression {
+            statements: vec![Statement::ReturnExpressionStatement(
+                ReturnExpressionStatement {
+                    value: expression,
+                    position: Position::default(),
+                },
+            )],
+            position: Position::default(),
+        };
+        let if_expression = |condition, consequence, alternative: Option<BlockExpression>| {
+            Expression::IfExpression(IfExpression {
+                condition: Box::new(condition),
+                consequence: Box::new(consequence),
+                alternative: alternative.map(Box::new),
+                position: Position::default(),
+            })
+        };
+
+        assert_eq!(
+            parse_expression("if (c) x else y"),
+            Ok(if_expression(
+                identifier("c"),
+                block(identifier("x")),
+                Some(block(identifier("y")))
+            ))
+        );
+        assert_eq!(
+            parse_expression("if (c) x + 1"),
+            Ok(if_expression(
+                identifier("c"),
+                block(infix(identifier("x"), InfixOperator::Plus, number(1.0))),
+                None
+            ))
+        );
+        assert_eq!(
+            parse_expression("if (a) 1 else if (b) { 2 } else 3"),
+            Ok(if_expression(
+                identifier("a"),
+                block(number(1.0)),
+                Some(block(if_expression(
+                    identifier("b"),
+                    block(number(2.0)),
+                    Some(block(number(3.0)))
+                )))
+            ))
+        );
+        assert_eq!(
+            parse_expression("if a { 1 } else 2"),
+            Ok(if_expression(
+                identifier("a"),
+                block(number(1.0)),
+                Some(block(number(2.0)))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_statement_without_braces() {
+        let program = Parser::from("if (c) return 1; while (c) break; x")
+            .parse_program()
+            .unwrap();
+
+        assert_eq!(
+            program[0],
+            Statement::ExpressionStatement(ExpressionStatement {
+                expression: Expression::IfExpression(IfExpression {
+                    condition: Box::new(identifier("c")),
+                    consequence: Box::new(BlockExpression {
+                        statements: vec![Statement::ReturnStatement(ReturnStatement {
+                            value: Some(number(1.0)),
+                            position: Position::default(),
+                        })],
+                        position: Position::default(),
+                    }),
+                    alternative: None,
+                    position: Position::default(),
+                }),
+                position: Position::default(),
+            })
+        );
+        assert!(matches!(
+            &program[1],
+            Statement::WhileStatement(WhileStatement { body, .. })
+                if matches!(body.statements[..], [Statement::BreakStatement(_)])
+        ));
+        assert_eq!(program.len(), 3);
+    }
+
+    #[test]
+    fn test_dangling_statement() {
+        let dangling = |source| {
+            let mut parser = Parser::from(source);
+            parser.parse_program().unwrap();
+            parser
+                .dangling
+                .iter()
+                .map(|Position(line, column)| (*line, *column))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            dangling("if (c)\n    return 1;\n    f();\ng();"),
+            vec![(3, 5)]
+        );
+        assert_eq!(
+            dangling("while (c)\n    i = i + 1;\n    j = j + 1;"),
+            vec![(3, 5)]
+        );
+        assert!(dangling("if (c)\n    return 1;\nf();").is_empty());
+        assert!(dangling("if (c) {\n    return 1;\n};\n    f();").is_empty());
+        assert!(dangling("if (c) return 1; else { 2 };\n    f();").is_empty());
+        assert!(dangling("{ if (c) return 1; };\n    f();").is_empty());
+    }
+
+    #[test]
+    fn test_percent_priority() {
+        assert_eq!(
+            parse_expression("1 + 4 % 3"),
+            Ok(infix(
+                number(1.0),
+                InfixOperator::Plus,
+                infix(number(4.0), InfixOperator::Percent, number(3.0))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_struct_literal_keys() {
+        let named = |name: &str| {
+            FieldKey::Named(Identifier {
+                value: name.to_string(),
+                position: Position::default(),
+            })
+        };
+
+        assert_eq!(
+            parse_expression(r#"struct P { x: 1, "with space": 2, [k + "y"]: 3 }"#),
+            Ok(Expression::Literal(Literal::StructLiteral(StructLiteral {
+                identifier: Identifier {
+                    value: String::from("P"),
+                    position: Position::default(),
+                },
+                fields: vec![
+                    (named("x"), number(1.0)),
+                    (named("with space"), number(2.0)),
+                    (
+                        FieldKey::Computed(
+                            infix(identifier("k"), InfixOperator::Plus, string_literal("y")),
+                            Position::default()
+                        ),
+                        number(3.0)
+                    ),
+                ],
+                position: Position::default(),
+            })))
+        );
+        assert_eq!(
+            parse_error("let p = struct P { [k: 1 };"),
+            ParsingErrorKind::ExpectedNextToken(String::from("]"), String::from(":"))
+        );
+    }
+
+    #[test]
+    fn test_unclosed_struct() {
+        for source in [
+            "struct P {",
+            "struct P { x: number,",
+            "let p = struct P {",
+            "let p = struct P { x: 1,",
+        ] {
+            assert_eq!(
+                parse_error(source),
+                ParsingErrorKind::ExpectedNextToken(String::from("}"), String::from("EOF")),
+                "{source}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_conditional() {
+        let source = "let x = 1; #if DEBUG { let y = 2; let z = 3; } let w = 4;";
+        let names = |defines: &[&str]| {
+            let mut parser = Parser::from(source);
+            parser.defines = defines.iter().map(|flag| flag.to_string()).collect();
+
+            let program = parser.parse_program().unwrap();
+            assert_eq!(program.len(), parser.spans.len());
+
+            program
+                .iter()
+                .map(|statement| match statement {
+                    Statement::LetStatement(statement) => statement.identifier.value.clone(),
+                    statement => panic!("unexpected statement {statement:?}"),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(&["DEBUG"]), vec!["x", "y", "z", "w"]);
+        assert_eq!(names(&[]), vec!["x", "w"]);
+        assert_eq!(names(&["RELEASE"]), vec!["x", "w"]);
+
+        let mut parser = Parser::from("#if DEBUG { let y = ; }");
+        assert!(parser.parse_program().is_err());
+    }
+
+    #[test]
+    fn test_misplaced_operators() {
+        assert_eq!(
+            parse_error("let x = a -> b;"),
+            ParsingErrorKind::UnexpectedToken(TokenKind::Arrow.to_string())
+        );
+        assert_eq!(
+            parse_error("let x = f(a) = c;"),
+            ParsingErrorKind::UnexpectedToken(TokenKind::Assign.to_string())
+        );
+    }
+
+    #[test]
+    fn test_operator_positions() {
+        let position = |expression: Expression| match expression {
+            Expression::InfixExpression(expression) => expression.position,
+            Expression::CallExpression(expression) => expression.position,
+            Expression::IndexExpression(expression) => expression.position,
+            Expression::CastExpression(expression) => expression.position,
+            expression => panic!("unexpected expression {expression:?}"),
+        };
+        let at = |source: &str| {
+            let Position(line, column) = position(parse_expression(source).unwrap());
+            (line, column)
+        };
+
+        assert_eq!(at("a +\n  b * c"), (1, 3));
+        assert_eq!(at("f(1,\n  2)"), (1, 2));
+        assert_eq!(at("xs[\n  i]"), (1, 3));
+        assert_eq!(at("x\n  as string"), (2, 3));
+    }
+
+    #[test]
+    fn test_display_with_positions() {
+        let display = |source| {
+            parse_expression(source)
+                .unwrap()
+                .display_with_positions()
+                .to_string()
+        };
+
+        assert_eq!(display("1 + x"), "(1@1:1 + x@1:5)@1:3");
+        assert_eq!(
+            display("f(a,\n  -b)[0]"),
+            "f@1:1(a@1:3, (-b@2:4)@2:4)@1:2[0@2:7]@2:6"
+        );
+    }
+
+    #[test]
+    fn test_parameter_patterns() {
+        let parameters = |source| match parse_expression(source) {
+            Ok(Expression::Literal(Literal::FunctionLiteral(function))) => function
+                .parameters
+                .iter()
+                .map(|parameter| {
+                    (
+                        parameter.identifier.value.clone(),
+                        parameter.pattern.clone(),
+                    )
+                })
+                .collect::<Vec<_>>(),
+            other => panic!("expected a function literal, got {other:?}"),
+        };
+        let identifier = |value: &str| Identifier {
+            value: value.to_string(),
+            position: Position::default(),
+        };
+
+        assert_eq!(
+            parameters("fn({x, y}: Point, [a, b,]: number[], c: number) {}"),
+            vec![
+                (
+                    String::from("{x, y}"),
+                    Some(Pattern::Struct(vec![identifier("x"), identifier("y")]))
+                ),
+                (
+                    String::from("[a, b]"),
+                    Some(Pattern::Array(vec![identifier("a"), identifier("b")]))
+                ),
+                (String::from("c"), None),
+            ]
+        );
+        assert_eq!(
+            parse_error("let f = fn({x, 1}: Point) {};"),
+            ParsingErrorKind::ExpectedNextToken(
+                TokenKind::IDENT("").to_string(),
+                String::from("1")
+            )
+        );
+    }
+
+    #[test]
+    fn test_while_statement() {
+        let program = Parser::from(
+            "outer: while true { while x < 3 { break outer; continue; }; } while false {}",
+        )
+        .parse_program()
+        .unwrap();
+
+        let label = |label: &Option<Identifier>| label.as_ref().map(|label| label.value.clone());
+
+        match &program[..] {
+            [Statement::WhileStatement(outer), Statement::WhileStatement(other)] => {
+                assert_eq!(label(&outer.label), Some(String::from("outer")));
+                assert!(matches!(
+                    outer.condition,
+                    Expression::Literal(Literal::BooleanLiteral(BooleanLiteral {
+                        value: true,
+                        ..
+                    }))
+                ));
+                assert_eq!(label(&other.label), None);
+
+                match &outer.body.statements[..] {
+                    [Statement::WhileStatement(inner)] => {
+                        assert_eq!(
+                            inner.condition,
+                            infix(identifier("x"), InfixOperator::LT, number(3.0))
+                        );
+                        assert!(matches!(
+                            &inner.body.statements[..],
+                            [Statement::BreakStatement(BreakStatement { label: Some(target), .. }),
+                             Statement::ContinueStatement(ContinueStatement { label: None, .. })]
+                                if target.value == "outer"
+                        ));
+                    }
+                    statements => panic!("unexpected statements {statements:?}"),
+                }
+            }
+            statements => panic!("unexpected statements {statements:?}"),
+        }
+
+        assert_eq!(
+            parse_error("outer: let x = 1;"),
+            ParsingErrorKind::ExpectedNextToken(String::from("while"), String::from("let"))
+        );
+        assert_eq!(
+            parse_error("while true { break outer }"),
+            ParsingErrorKind::ExpectedNextToken(String::from(";"), String::from("}"))
+        );
+    }
+}
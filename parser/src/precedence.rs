@@ -0,0 +1,132 @@
+//! The precedence of the infix and postfix operators, which the parser groups expressions by.
+
+use crate::tokenizer::TokenKind;
+
+/// How tightly an operator binds its operands, from the loosest to the tightest.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord)]
+pub enum Priority {
+    Lowest,
+    Nullish,
+    Equals,
+    LessGreater,
+    Sum,
+    Product,
+    Cast,
+    Prefix,
+    Power,
+    Call,
+    Index,
+    Dot,
+}
+
+impl Priority {
+    /// Returns the next looser priority. `Lowest` has none, so it is returned as is.
+    pub fn lower(self) -> Self {
+        match self {
+            Priority::Lowest | Priority::Nullish => Priority::Lowest,
+            Priority::Equals => Priority::Nullish,
+            Priority::LessGreater => Priority::Equals,
+            Priority::Sum => Priority::LessGreater,
+            Priority::Product => Priority::Sum,
+            Priority::Cast => Priority::Product,
+            Priority::Prefix => Priority::Cast,
+            Priority::Power => Priority::Prefix,
+            Priority::Call => Priority::Power,
+            Priority::Index => Priority::Call,
+            Priority::Dot => Priority::Index,
+        }
+    }
+}
+
+/// Which side a chain of operators with the same priority groups from: `a - b - c` is `(a - b) - c`,
+/// while `a ** b ** c` is `a ** (b ** c)`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
+/// Every infix and postfix operator (`(` for calls, `[` for indexing) with its priority and associativity.
+/// The prefix operators `-` and `!` bind with `Priority::Prefix`. `=` and `->` are never infix operators, but
+/// binding like `==` and `.` makes the parser report them where they appear, e.g. `a -> b`.
+#[rustfmt::skip]
+pub const OPERATORS: &[(TokenKind<'static>, Priority, Associativity)] = &[
+    (TokenKind::Dot, Priority::Dot, Associativity::Left),
+    (TokenKind::Arrow, Priority::Dot, Associativity::Left),
+    (TokenKind::QuestionDot, Priority::Dot, Associativity::Left),
+    (TokenKind::LBracket, Priority::Index, Associativity::Left),
+    (TokenKind::LParen, Priority::Call, Associativity::Left),
+    (TokenKind::Pow, Priority::Power, Associativity::Right),
+    (TokenKind::As, Priority::Cast, Associativity::Left),
+    (TokenKind::Asterisk, Priority::Product, Associativity::Left),
+    (TokenKind::Slash, Priority::Product, Associativity::Left),
+    (TokenKind::Percent, Priority::Product, Associativity::Left),
+    (TokenKind::Plus, Priority::Sum, Associativity::Left),
+    (TokenKind::Minus, Priority::Sum, Associativity::Left),
+    (TokenKind::LT, Priority::LessGreater, Associativity::Left),
+    (TokenKind::GT, Priority::LessGreater, Associativity::Left),
+    (TokenKind::LTE, Priority::LessGreater, Associativity::Left),
+    (TokenKind::GTE, Priority::LessGreater, Associativity::Left),
+    (TokenKind::In, Priority::LessGreater, Associativity::Left),
+    (TokenKind::Assign, Priority::Equals, Associativity::Right),
+    (TokenKind::EQ, Priority::Equals, Associativity::Left),
+    (TokenKind::NEQ, Priority::Equals, Associativity::Left),
+    (TokenKind::DoubleQuestion, Priority::Nullish, Associativity::Left),
+];
+
+/// Returns the priority and associativity of the operator, or `None` if the token is not an operator.
+pub fn precedence(token: &TokenKind) -> Option<(Priority, Associativity)> {
+    OPERATORS
+        .iter()
+        .find(|(operator, ..)| operator == token)
+        .map(|(_, priority, associativity)| (*priority, *associativity))
+}
+
+/// Returns the priority the right operand of the operator is parsed with. It is one step looser for a
+/// right-associative operator, so the next operator of the same priority takes the rest of the chain.
+pub fn right_operand_priority(token: &TokenKind) -> Priority {
+    match precedence(token) {
+        Some((priority, Associativity::Left)) => priority,
+        Some((priority, Associativity::Right)) => priority.lower(),
+        None => Priority::Lowest,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_precedence() {
+        let priority = |token| precedence(&token).unwrap().0;
+
+        assert!(priority(TokenKind::Asterisk) > priority(TokenKind::Plus));
+        assert!(priority(TokenKind::Pow) > priority(TokenKind::Asterisk));
+        assert!(priority(TokenKind::Plus) > priority(TokenKind::LT));
+        assert!(priority(TokenKind::EQ) > priority(TokenKind::DoubleQuestion));
+        assert_eq!(priority(TokenKind::Percent), priority(TokenKind::Slash));
+
+        assert_eq!(
+            precedence(&TokenKind::Pow),
+            Some((Priority::Power, Associativity::Right))
+        );
+        assert_eq!(
+            precedence(&TokenKind::Minus),
+            Some((Priority::Sum, Associativity::Left))
+        );
+        assert_eq!(right_operand_priority(&TokenKind::Pow), Priority::Prefix);
+        assert_eq!(right_operand_priority(&TokenKind::Minus), Priority::Sum);
+        assert_eq!(Priority::Equals.lower(), Priority::Nullish);
+        assert_eq!(Priority::Lowest.lower(), Priority::Lowest);
+        assert_eq!(precedence(&TokenKind::Semicolon), None);
+
+        assert_eq!(
+            precedence(&TokenKind::Assign),
+            Some((Priority::Equals, Associativity::Right))
+        );
+        assert_eq!(
+            precedence(&TokenKind::Arrow),
+            Some((Priority::Dot, Associativity::Left))
+        );
+    }
+}
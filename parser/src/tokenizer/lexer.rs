@@ -82,63 +82,180 @@ impl<'a> Lexer<'a> {
         &self.input[position..self.position]
     }
 
-    fn read_number(&mut self) -> f64 {
+    /// Reads a number literal, returning the position of the first invalid digit if it is malformed.
+    fn read_number(&mut self) -> Result<f64, Position> {
+        let radix = match (self.current_char, self.peek_char()) {
+            ('0', 'x') => Some(16),
+            ('0', 'o') => Some(8),
+            ('0', 'b') => Some(2),
+            _ => None,
+        };
+
+        if let Some(radix) = radix {
+            return self.read_radix_number(radix);
+        }
+
+        let start = self.current_position;
         let position = self.position;
         let mut has_dot = false;
+        let mut previous = '\0';
+        let mut invalid = None;
+
+        while self.current_char.is_numeric() || matches!(self.current_char, '.' | '_') {
+            match self.current_char {
+                '.' if has_dot || self.peek_char() == '.' => break,
+                '.' => has_dot = true,
+                '_' if !previous.is_numeric() || !self.peek_char().is_numeric() => {
+                    invalid.get_or_insert(self.current_position);
+                }
+                _ => {}
+            }
 
-        while self.current_char.is_numeric() || self.current_char == '.' {
-            if self.current_char == '.' {
-                if has_dot {
-                    break;
+            previous = self.current_char;
+            self.read_char();
+        }
+
+        match invalid {
+            Some(position) => Err(position),
+            None => self.input[position..self.position]
+                .replace('_', "")
+                .parse()
+                .map_err(|_| start),
+        }
+    }
+
+    /// Reads an integer literal with a `0x`, `0o` or `0b` prefix.
+    fn read_radix_number(&mut self, radix: u32) -> Result<f64, Position> {
+        let position = self.current_position;
+        let mut value = 0.0;
+        let mut digits = 0;
+        let mut invalid = None;
+
+        self.read_char();
+        self.read_char();
+
+        while self.current_char.is_alphanumeric() || self.current_char == '_' {
+            match self.current_char {
+                '_' if digits == 0 || !self.peek_char().is_alphanumeric() => {
+                    invalid.get_or_insert(self.current_position);
                 }
+                '_' => {}
+                char => {
+                    match char.to_digit(radix) {
+                        Some(digit) => value = value * radix as f64 + digit as f64,
+                        None => {
+                            invalid.get_or_insert(self.current_position);
+                        }
+                    }
 
-                has_dot = true;
+                    digits += 1;
+                }
             }
 
             self.read_char();
         }
 
-        self.input[position..self.position]
-            .parse()
-            .unwrap_or_else(|_| unimplemented!())
+        match invalid {
+            Some(position) => Err(position),
+            None if digits == 0 => Err(position),
+            None => Ok(value),
+        }
     }
 
     fn read_string(&mut self) -> &'a str {
         let position = self.position + 1;
-        while self.peek_char() != '"' && self.current_char != '\0' {
-            self.read_char();
-        }
 
         self.read_char();
-        &self.input[position..self.position]
+        self.read_string_content();
+
+        &self.input[position.min(self.input.len())..self.position.min(self.input.len())]
     }
 
-    fn read_comment(&mut self) {
-        if self.current_char == '/' && self.peek_char() == '*' {
+    /// Reads until the closing quote, skipping escaped characters and `${...}` interpolations (which may contain nested strings).
+    fn read_string_content(&mut self) {
+        while self.current_char != '"' && self.current_char != '\0' {
+            match self.current_char {
+                '\\' => self.read_char(),
+                '$' if self.peek_char() == '{' => {
+                    self.read_char();
+                    self.read_interpolation();
+                }
+                _ => {}
+            }
+
             self.read_char();
+        }
+    }
+
+    /// Reads an interpolation starting at its `{`, stopping at the matching `}` (or EOF if it is not closed).
+    pub(crate) fn read_interpolation(&mut self) -> bool {
+        let mut depth = 0;
+
+        loop {
+            match self.current_char {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+
+                    if depth == 0 {
+                        return true;
+                    }
+                }
+                '"' => {
+                    self.read_char();
+                    self.read_string_content();
+                }
+                _ => {}
+            }
+
+            if self.current_char == '\0' {
+                return false;
+            }
+
             self.read_char();
+        }
+    }
 
-            while self.current_char != '\0' && (self.current_char != '*' || self.peek_char() != '/')
-            {
-                if self.current_char == '\n' {
+    /// Skips a block comment starting at its `/*`, including nested `/* */` pairs. Returns false if it is not closed.
+    fn read_block_comment(&mut self) -> bool {
+        let mut depth = 0;
+
+        loop {
+            match (self.current_char, self.peek_char()) {
+                ('\0', _) => return false,
+                ('/', '*') => {
+                    depth += 1;
+                    self.read_char();
+                }
+                ('*', '/') => {
+                    depth -= 1;
+                    self.read_char();
+
+                    if depth == 0 {
+                        self.read_char();
+                        return true;
+                    }
+                }
+                ('\n', _) => {
                     self.current_position.0 += 1;
                     self.current_position.1 = 0;
                 }
-
-                self.read_char();
+                _ => {}
             }
 
             self.read_char();
-        } else if self.current_char == '/' && self.peek_char() == '/' {
-            self.read_char();
-            self.read_char();
+        }
+    }
 
-            while self.current_char != '\0' && self.current_char != '\n' {
-                self.read_char();
-            }
+    fn read_comment(&mut self) {
+        self.read_char();
+        self.read_char();
 
-            self.skip_whitespace();
+        while self.current_char != '\0' && self.current_char != '\n' {
+            self.read_char();
         }
+
+        self.skip_whitespace();
     }
 
     pub fn next_token(&mut self) -> Token<'a> {
@@ -146,6 +263,8 @@ impl<'a> Lexer<'a> {
 
         self.skip_whitespace();
 
+        let offset = self.position.min(self.input.len());
+
         macro_rules! match_token {
             ($($token:expr => $token_type:expr),*) => {{
                 let position = self.current_position;
@@ -178,11 +297,13 @@ impl<'a> Lexer<'a> {
 
         let token = match_token! {
             '+' => Plus,
-            '*' => Asterisk,
+            '*' => next!('*' => Pow; Asterisk),
             '%' => Percent,
-            '.' => Dot,
+            '.' => next!('.' => DoubleDot; Dot),
+            '?' => next!('?' => DoubleQuestion; next!('.' => QuestionDot; Question)),
             ',' => Comma,
             ';' => Semicolon,
+            '#' => Hash,
             ':' => Colon,
             '(' => LParen,
             ')' => RParen,
@@ -201,8 +322,14 @@ impl<'a> Lexer<'a> {
             '"' => String(self.read_string()),
 
             '/' => next!(@no_read '*' => {
-                self.read_comment();
-                self.next_token();
+                let position = self.current_position;
+
+                if !self.read_block_comment() {
+                    return Token {
+                        offset,
+                        ..Token::new(UnterminatedComment, position)
+                    };
+                }
 
                 return self.next_token();
             }; next!(@no_read '/' => {
@@ -214,18 +341,28 @@ impl<'a> Lexer<'a> {
             '\0' => EOF
         };
 
-        match self.current_char {
-            c if c.is_alphabetic() => {
-                let position = self.current_position;
-                Token::new(TokenKind::from(self.read_identifier()), position)
-            }
-            c if c.is_numeric() => {
-                let position = self.current_position;
-                Token::new(TokenKind::Number(self.read_number()), position)
-            }
-            _ => {
-                self.read_char();
-                token
+        Token {
+            offset,
+            ..match self.current_char {
+                c if c.is_alphabetic() || c == '_' => {
+                    let position = self.current_position;
+                    Token::new(TokenKind::from(self.read_identifier()), position)
+                }
+                c if c.is_numeric() => {
+                    let position = self.current_position;
+
+                    match self.read_number() {
+                        Ok(value) => Token::new(TokenKind::Number(value), position),
+                        Err(position) => Token::new(
+                            TokenKind::InvalidNumber(&self.input[offset..self.position]),
+                            position,
+                        ),
+                    }
+                }
+                _ => {
+                    self.read_char();
+                    token
+                }
             }
         }
     }
@@ -244,3 +381,217 @@ impl<'a> Iterator for Lexer<'a> {
         }
     }
 }
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub position: Position,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexErrorKind {
+    IllegalCharacter(char),
+    UnterminatedString,
+    UnterminatedComment,
+    InvalidNumber(String),
+}
+
+/// Tokenizes the whole input, stopping at the first lexical error.
+///
+/// ## Example
+///
+/// ```rust
+/// use parser::tokenizer::{tokenize, TokenKind};
+///
+/// let tokens = tokenize("let x = 5;").unwrap();
+///
+/// assert_eq!(tokens[0].kind, TokenKind::Let);
+/// ```
+pub fn tokenize(input: &str) -> Result<Vec<Token<'_>>, LexError> {
+    let mut tokens = Vec::new();
+
+    for token in Lexer::new(input) {
+        match token.kind {
+            TokenKind::ILLEGAL(char) => {
+                return Err(LexError {
+                    kind: LexErrorKind::IllegalCharacter(char),
+                    position: token.position,
+                })
+            }
+            // The closing quote is missing if the string runs until the end of the input.
+            TokenKind::String(value)
+                if input.as_bytes().get(token.offset + value.len() + 1) != Some(&b'"') =>
+            {
+                return Err(LexError {
+                    kind: LexErrorKind::UnterminatedString,
+                    position: token.position,
+                })
+            }
+            TokenKind::UnterminatedComment => {
+                return Err(LexError {
+                    kind: LexErrorKind::UnterminatedComment,
+                    position: token.position,
+                })
+            }
+            TokenKind::InvalidNumber(literal) => {
+                return Err(LexError {
+                    kind: LexErrorKind::InvalidNumber(literal.to_string()),
+                    position: token.position,
+                })
+            }
+            _ => tokens.push(token),
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `Position` always compares equal, so the position of an error is returned as its fields.
+    fn error(source: &str) -> (LexErrorKind, usize, usize) {
+        let error = tokenize(source).unwrap_err();
+
+        (error.kind, error.position.0, error.position.1)
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let kinds = tokenize(r#"let x = "a"; // comment"#)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Let,
+                TokenKind::IDENT("x"),
+                TokenKind::Assign,
+                TokenKind::String("a"),
+                TokenKind::Semicolon,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_unterminated_string() {
+        assert_eq!(
+            error(r#"let x = "abc;"#),
+            (LexErrorKind::UnterminatedString, 1, 9)
+        );
+        assert_eq!(
+            error("let x = 1 @ 2;"),
+            (LexErrorKind::IllegalCharacter('@'), 1, 11)
+        );
+    }
+
+    #[test]
+    fn test_radix_numbers() {
+        let kinds = tokenize("0xFF 0o17 0b1010 0")
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Number(255.0),
+                TokenKind::Number(15.0),
+                TokenKind::Number(10.0),
+                TokenKind::Number(0.0),
+            ]
+        );
+
+        assert_eq!(
+            error("let x = 0b102;"),
+            (LexErrorKind::InvalidNumber("0b102".to_string()), 1, 13)
+        );
+        assert_eq!(
+            error("let x = 0x;"),
+            (LexErrorKind::InvalidNumber("0x".to_string()), 1, 9)
+        );
+    }
+
+    #[test]
+    fn test_digit_separators() {
+        let kinds = tokenize("1_000_000 1_000.5 0b1010_1010 _1")
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Number(1_000_000.0),
+                TokenKind::Number(1000.5),
+                TokenKind::Number(170.0),
+                TokenKind::IDENT("_1"),
+            ]
+        );
+
+        assert_eq!(
+            error("1__0"),
+            (LexErrorKind::InvalidNumber("1__0".to_string()), 1, 2)
+        );
+        assert_eq!(
+            error("1_"),
+            (LexErrorKind::InvalidNumber("1_".to_string()), 1, 2)
+        );
+        assert_eq!(
+            error("1._5"),
+            (LexErrorKind::InvalidNumber("1._5".to_string()), 1, 3)
+        );
+        assert_eq!(
+            error("0x_ff"),
+            (LexErrorKind::InvalidNumber("0x_ff".to_string()), 1, 3)
+        );
+        assert_eq!(
+            error("x = 10_;"),
+            (LexErrorKind::InvalidNumber("10_".to_string()), 1, 7)
+        );
+    }
+
+    #[test]
+    fn test_underscore_identifiers() {
+        let kinds = tokenize("_ _x __a1 x_y")
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::IDENT("_"),
+                TokenKind::IDENT("_x"),
+                TokenKind::IDENT("__a1"),
+                TokenKind::IDENT("x_y"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nested_block_comment() {
+        let kinds = tokenize("a /* outer /* inner */ still in comment */ b")
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect::<Vec<_>>();
+
+        assert_eq!(kinds, vec![TokenKind::IDENT("a"), TokenKind::IDENT("b")]);
+    }
+
+    #[test]
+    fn test_unterminated_block_comment() {
+        assert_eq!(
+            error("let x = 1;\n  /* a /* b */"),
+            (LexErrorKind::UnterminatedComment, 2, 3)
+        );
+    }
+}
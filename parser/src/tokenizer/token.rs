@@ -6,19 +6,19 @@ use std::fmt;
 pub enum TokenKind<'a> {
     ILLEGAL(char), EOF, IDENT(&'a str),
 
-    Number(f64), String(&'a str), Boolean(bool), Comment,
+    Number(f64), String(&'a str), Boolean(bool), Null, Comment, UnterminatedComment, InvalidNumber(&'a str),
 
-    Assign, Plus, Minus, Bang, Asterisk, Slash, Percent, Arrow, DoubleArrow,
+    Assign, Plus, Minus, Bang, Asterisk, Pow, Slash, Percent, Arrow, DoubleArrow,
 
-    Dot, Comma, Colon, Semicolon,
+    Dot, DoubleDot, Question, QuestionDot, DoubleQuestion, Comma, Colon, Semicolon, Hash,
 
     LParen, RParen, LBrace, RBrace, LBracket, RBracket,
 
     LT, GT, LTE, GTE, EQ, NEQ,
 
-    Let, Mut, If, Else, Return, Function, Type, Declare, Struct, Typeof, Spread,
+    Let, Mut, If, Else, Return, Function, Type, Declare, Struct, Typeof, Spread, In, As, Try, Catch, Throw, While, Break, Continue, Const, Match,
 
-    NumberType, StringType, BooleanType,
+    NumberType, StringType, BooleanType, VoidType, NeverType,
 
     Debug
 }
@@ -37,11 +37,26 @@ impl<'a> From<&'a str> for TokenKind<'a> {
             "struct" => TokenKind::Struct,
             "typeof" => TokenKind::Typeof,
             "spread" => TokenKind::Spread,
+            "in" => TokenKind::In,
+            "as" => TokenKind::As,
+            "try" => TokenKind::Try,
+            "catch" => TokenKind::Catch,
+            "throw" => TokenKind::Throw,
+            "while" => TokenKind::While,
+            "break" => TokenKind::Break,
+            "continue" => TokenKind::Continue,
+            "const" => TokenKind::Const,
+            "match" => TokenKind::Match,
             "true" => TokenKind::Boolean(true),
             "false" => TokenKind::Boolean(false),
+            "null" => TokenKind::Null,
+            "NaN" => TokenKind::Number(f64::NAN),
+            "Infinity" => TokenKind::Number(f64::INFINITY),
             "number" => TokenKind::NumberType,
             "string" => TokenKind::StringType,
             "boolean" => TokenKind::BooleanType,
+            "void" => TokenKind::VoidType,
+            "never" => TokenKind::NeverType,
             "debug" => TokenKind::Debug,
             s => TokenKind::IDENT(s),
         }
@@ -51,15 +66,32 @@ impl<'a> From<&'a str> for TokenKind<'a> {
 impl fmt::Display for TokenKind<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         macro_rules! to_s {
-            ($( $x:ident )*) => {
+            ($( $x:ident )*; $( $y:ident => $s:expr ),*) => {
                 match &self {
                     $( TokenKind::$x(x) => x.to_string(), )*
+                    $( TokenKind::$y => $s.to_string(), )*
                     _ => format!("{:?}", self)
                 }
             }
         }
 
-        write!(f, "{}", to_s! { IDENT String Number Boolean })
+        write!(
+            f,
+            "{}",
+            to_s! {
+                IDENT String Number Boolean InvalidNumber;
+                Assign => "=", Plus => "+", Minus => "-", Bang => "!", Asterisk => "*", Pow => "**",
+                Slash => "/", Percent => "%", Arrow => "->", DoubleArrow => "=>", Dot => ".",
+                DoubleDot => "..", Question => "?", QuestionDot => "?.", DoubleQuestion => "??", Null => "null", UnterminatedComment => "/*", Comma => ",", Colon => ":", Semicolon => ";", Hash => "#", LParen => "(",
+                RParen => ")", LBrace => "{", RBrace => "}", LBracket => "[", RBracket => "]",
+                LT => "<", GT => ">", LTE => "<=", GTE => ">=", EQ => "==", NEQ => "!=",
+                Let => "let", Mut => "mut", If => "if", Else => "else", Return => "return",
+                Function => "fn", Type => "type", Declare => "declare", Struct => "struct",
+                Typeof => "typeof", Spread => "spread", In => "in", As => "as", Try => "try", Catch => "catch", Throw => "throw", While => "while", Break => "break", Continue => "continue", Const => "const", Match => "match", NumberType => "number",
+                StringType => "string", BooleanType => "boolean", VoidType => "void", NeverType => "never",
+                Debug => "debug"
+            }
+        )
     }
 }
 
@@ -67,6 +99,8 @@ impl fmt::Display for TokenKind<'_> {
 pub struct Token<'a> {
     pub kind: TokenKind<'a>,
     pub position: Position,
+    /// The offset of the token in the source.
+    pub offset: usize,
 }
 
 impl Default for Token<'_> {
@@ -83,6 +117,10 @@ impl std::fmt::Display for Token<'_> {
 
 impl<'a> Token<'a> {
     pub fn new(kind: TokenKind<'a>, position: Position) -> Self {
-        Token { kind, position }
+        Token {
+            kind,
+            position,
+            offset: 0,
+        }
     }
 }
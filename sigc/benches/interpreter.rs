@@ -0,0 +1,83 @@
+//! Benchmarks of the evaluator's hot paths, mostly recursive calls. Run with `cargo bench`.
+
+use analyzer::analyzer::Analyzer;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use parser::{ast::Program, Parser};
+use sigc::{evaluator::Evaluator, run_source};
+
+const ARITHMETIC: &str = r#"
+let sum = fn(n: number, total: number) -> number {
+    if n == 0 { total } else { sum(n - 1, total + n * 2 / 3 - 1) }
+};
+sum(200, 0)
+"#;
+
+const RECURSION: &str = r#"
+let fib = fn(n: number) -> number {
+    if n <= 1 { n } else { fib(n - 1) + fib(n - 2) }
+};
+fib(15)
+"#;
+
+const ARRAYS: &str = r#"
+declare buffer = fn() -> number[];
+declare push = fn(number[], number) -> void;
+declare to_array = fn(number[]) -> number[];
+declare map = fn(number[], fn(number) -> number) -> number[];
+let xs = buffer();
+let fill = fn(n: number) -> number {
+    if n == 0 { 0 } else { push(xs, n); fill(n - 1) }
+};
+fill(200);
+map(to_array(xs), fn(x: number) -> number { x * 2 })
+"#;
+
+const CLOSURES: &str = r#"
+let adder = fn(x: number) -> fn(number) -> number {
+    fn(y: number) -> number { x + y }
+};
+let apply = fn(n: number, total: number) -> number {
+    if n == 0 { total } else { apply(n - 1, adder(n)(total)) }
+};
+apply(200, 0)
+"#;
+
+const PIPELINE: &str = r#"
+declare sort = fn(number[]) -> number[];
+let square = fn(x: number) -> number { x * x };
+let xs = sort([square(3), square(1), square(2)]);
+xs[0] + xs[1] + xs[2]
+"#;
+
+fn parse(source: &str) -> Program {
+    let program = Parser::from(source).parse_program().unwrap();
+    Analyzer::new(program.clone()).analyze().unwrap();
+
+    program
+}
+
+fn evaluate(criterion: &mut Criterion, name: &str, source: &str) {
+    let program = parse(source);
+
+    criterion.bench_function(name, |bencher| {
+        bencher.iter(|| {
+            Evaluator::new(black_box(program.clone()))
+                .evaluate()
+                .unwrap()
+        })
+    });
+}
+
+fn interpreter(criterion: &mut Criterion) {
+    evaluate(criterion, "arithmetic", ARITHMETIC);
+    evaluate(criterion, "recursion", RECURSION);
+    evaluate(criterion, "arrays", ARRAYS);
+    evaluate(criterion, "closures", CLOSURES);
+
+    criterion.bench_function("pipeline", |bencher| {
+        bencher.iter(|| run_source(black_box(PIPELINE)).unwrap())
+    });
+}
+
+criterion_group!(benches, interpreter);
+criterion_main!(benches);
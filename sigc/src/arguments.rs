@@ -1,5 +1,5 @@
-use clap::Parser;
-use std::path::PathBuf;
+use clap::{Parser, ValueEnum};
+use std::{io::IsTerminal, path::PathBuf};
 
 #[derive(Parser, Debug)]
 #[clap(bin_name = "sntkc", version = "0.0.0", arg_required_else_help = true)]
@@ -12,6 +12,77 @@ pub struct Cli {
     pub verbose: bool,
     #[arg(short, long, help = "Evaluate the source file")]
     pub eval: bool,
+    #[arg(long, help = "Print the symbol table after analysis")]
+    pub dump_symbols: bool,
+    #[arg(long, help = "Print the tokens of the source file, one per line")]
+    pub emit_tokens: bool,
+    #[arg(
+        long,
+        help = "Reject adding a number to a string instead of converting it"
+    )]
+    pub strict: bool,
+    #[arg(
+        long,
+        help = "Infer the return type of functions without one, as a union if their returns differ"
+    )]
+    pub unions: bool,
+    #[arg(
+        long,
+        help = "Exit with a non-zero code if the analysis reports any warnings"
+    )]
+    pub deny_warnings: bool,
+    #[arg(
+        long,
+        value_name = "BYTES",
+        help = "Stop the evaluation once print builtins write more than this many bytes"
+    )]
+    pub max_output: Option<usize>,
+    #[arg(
+        long,
+        value_name = "EPSILON",
+        help = "Consider numbers equal if they differ by at most this much"
+    )]
+    pub epsilon: Option<f64>,
+    #[arg(
+        short = 'D',
+        long = "define",
+        value_name = "FLAG",
+        help = "Enable the `#if FLAG { ... }` blocks of the source file"
+    )]
+    pub defines: Vec<String>,
     #[arg(short, long, value_name = "FILE", help = "Use a custom config file")]
     pub config: Option<PathBuf>,
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto, help = "Colorize diagnostics")]
+    pub color: ColorChoice,
+    #[arg(long, help = "Same as `--color never`")]
+    pub no_color: bool,
+}
+
+impl Cli {
+    pub fn color(&self) -> ColorChoice {
+        match self.no_color {
+            true => ColorChoice::Never,
+            false => self.color,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Colorize if stdout is a terminal and `NO_COLOR` is not set
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal()
+            }
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
 }
@@ -0,0 +1,2248 @@
+use super::{
+    json,
+    object::{Cache, Object},
+    EvaluateError, EvaluateErrorKind, EvaluateResult, Evaluator,
+};
+use parser::ast::Position;
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::hash_map::DefaultHasher,
+    fmt,
+    hash::{Hash, Hasher},
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+pub type BuiltinFunction = fn(&mut Evaluator, Vec<Object>, Position) -> EvaluateResult<Object>;
+
+/// The longest string (in bytes) builtins like `repeat` build, so that a huge count fails instead of
+/// aborting the process when the allocation does.
+pub const MAX_STRING_LENGTH: usize = 1 << 24;
+
+pub const BUILTINS: &[(&str, BuiltinFunction)] = &[
+    ("sort", sort),
+    ("sort_by", sort_by),
+    ("map", map),
+    ("memoize", memoize),
+    ("partition", partition),
+    ("take", take),
+    ("drop", drop),
+    ("take_while", take_while),
+    ("drop_while", drop_while),
+    ("count", count),
+    ("sum", sum),
+    ("product", product),
+    ("average", average),
+    ("group_by", group_by),
+    ("is_nan", is_nan),
+    ("approx_eq", approx_eq),
+    ("unique", unique),
+    ("flatten", flatten),
+    ("flatten_strict", flatten_strict),
+    ("zip", zip),
+    ("chunk", chunk),
+    ("windows", windows),
+    ("enumerate", enumerate),
+    ("reverse", reverse),
+    ("rotate", rotate),
+    ("now", now),
+    ("lines", lines),
+    ("chars", chars),
+    ("starts_with", starts_with),
+    ("ends_with", ends_with),
+    ("replace", replace),
+    ("repeat", repeat),
+    ("indent", indent),
+    ("dedent", dedent),
+    ("pad_left", pad_left),
+    ("pad_right", pad_right),
+    ("format", format),
+    ("json_parse", json_parse),
+    ("json_stringify", json_stringify),
+    ("buffer", buffer),
+    ("push", push),
+    ("pop", pop),
+    ("to_array", to_array),
+    ("todo", todo),
+    ("panic", panic),
+    ("exit", exit),
+    ("print", print),
+    ("println", println),
+];
+
+/// The time source of `now()`. It can be replaced on the evaluator, e.g. with a fixed time in tests.
+pub trait Clock: fmt::Debug {
+    /// Returns the milliseconds since the Unix epoch.
+    fn now(&self) -> f64;
+}
+
+#[derive(Debug)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> f64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |duration| duration.as_secs_f64() * 1000.0)
+    }
+}
+
+/// Where `print()` and `println()` write to. It can be replaced on the evaluator, e.g. to capture the output in tests.
+pub trait Output: fmt::Debug {
+    fn write(&mut self, text: &str);
+}
+
+#[derive(Debug)]
+pub struct Stdout;
+
+impl Output for Stdout {
+    fn write(&mut self, text: &str) {
+        print!("{text}");
+    }
+}
+
+/// Returns the builtin function object with the given name.
+pub fn get(name: &str) -> Option<Object> {
+    BUILTINS
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+        .map(|(builtin, _)| Object::Builtin(builtin))
+}
+
+/// Returns the implementation of the builtin function with the given name.
+pub fn lookup(name: &str) -> Option<BuiltinFunction> {
+    BUILTINS
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+        .map(|(_, function)| *function)
+}
+
+fn expect_arguments(arguments: &[Object], count: usize, position: Position) -> EvaluateResult<()> {
+    if arguments.len() != count {
+        return Err(EvaluateError::new(
+            EvaluateErrorKind::ArgumentCountMismatch(
+                count.to_string(),
+                arguments.len().to_string(),
+            ),
+            position,
+        ));
+    }
+
+    Ok(())
+}
+
+fn expect_array(object: Object, position: Position) -> EvaluateResult<Vec<Object>> {
+    match object {
+        Object::Array(elements) => Ok(elements),
+        object => Err(EvaluateError::new(
+            EvaluateErrorKind::TypeMismatch(object.type_name().to_string(), "array".to_string()),
+            position,
+        )),
+    }
+}
+
+fn expect_string(object: Object, position: Position) -> EvaluateResult<String> {
+    match object {
+        Object::String(string) => Ok(string),
+        object => Err(EvaluateError::new(
+            EvaluateErrorKind::TypeMismatch(object.type_name().to_string(), "string".to_string()),
+            position,
+        )),
+    }
+}
+
+fn expect_number(object: Object, position: Position) -> EvaluateResult<f64> {
+    match object {
+        Object::Number(number) => Ok(number),
+        object => Err(EvaluateError::new(
+            EvaluateErrorKind::TypeMismatch(object.type_name().to_string(), "number".to_string()),
+            position,
+        )),
+    }
+}
+
+fn expect_numbers(object: Object, position: Position) -> EvaluateResult<Vec<f64>> {
+    expect_array(object, position)?
+        .into_iter()
+        .map(|element| expect_number(element, position))
+        .collect()
+}
+
+fn expect_buffer(object: Object, position: Position) -> EvaluateResult<Rc<RefCell<Vec<Object>>>> {
+    match object {
+        Object::Buffer(elements) => Ok(elements),
+        object => Err(EvaluateError::new(
+            EvaluateErrorKind::TypeMismatch(object.type_name().to_string(), "buffer".to_string()),
+            position,
+        )),
+    }
+}
+
+/// `sort(array)`
+///
+/// Returns a new array of numbers or strings sorted in ascending order.
+fn sort(_: &mut Evaluator, arguments: Vec<Object>, position: Position) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+
+    let mut elements = expect_array(arguments[0].clone(), position)?;
+
+    if let Some(first) = elements.first() {
+        let expected = match first {
+            Object::Number(_) | Object::String(_) => first.type_name(),
+            object => {
+                return Err(EvaluateError::new(
+                    EvaluateErrorKind::TypeMismatch(
+                        object.type_name().to_string(),
+                        "number".to_string(),
+                    ),
+                    position,
+                ))
+            }
+        };
+
+        if let Some(element) = elements.iter().find(|x| x.type_name() != expected) {
+            return Err(EvaluateError::new(
+                EvaluateErrorKind::TypeMismatch(
+                    element.type_name().to_string(),
+                    expected.to_string(),
+                ),
+                position,
+            ));
+        }
+    }
+
+    elements.sort_by(|a, b| match (a, b) {
+        (Object::Number(a), Object::Number(b)) => a.total_cmp(b),
+        (Object::String(a), Object::String(b)) => a.cmp(b),
+        _ => Ordering::Equal,
+    });
+
+    Ok(Object::Array(elements))
+}
+
+/// `sort_by(array, comparator)`
+///
+/// Returns a new array stably sorted with the comparator, which returns a negative number, `0` or a positive number.
+fn sort_by(
+    evaluator: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 2, position)?;
+
+    let mut elements = expect_array(arguments[0].clone(), position)?;
+    let comparator = arguments[1].clone();
+
+    let mut error = None;
+
+    elements.sort_by(|a, b| {
+        if error.is_some() {
+            return Ordering::Equal;
+        }
+
+        match evaluator.call_function(comparator.clone(), vec![a.clone(), b.clone()], position) {
+            Ok(Object::Number(result)) => result.partial_cmp(&0.0).unwrap_or(Ordering::Equal),
+            Ok(result) => {
+                error = Some(EvaluateError::new(
+                    EvaluateErrorKind::TypeMismatch(
+                        result.type_name().to_string(),
+                        "number".to_string(),
+                    ),
+                    position,
+                ));
+                Ordering::Equal
+            }
+            Err(err) => {
+                error = Some(err);
+                Ordering::Equal
+            }
+        }
+    });
+
+    match error {
+        Some(error) => Err(error),
+        None => Ok(Object::Array(elements)),
+    }
+}
+
+/// `map(array, function)`
+///
+/// Returns a new array with the function applied to each element, in order.
+fn map(
+    evaluator: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 2, position)?;
+
+    let elements = expect_array(arguments[0].clone(), position)?;
+
+    Ok(Object::Array(
+        elements
+            .into_iter()
+            .map(|element| evaluator.call_function(arguments[1].clone(), vec![element], position))
+            .collect::<EvaluateResult<Vec<_>>>()?,
+    ))
+}
+
+/// `memoize(function)`
+///
+/// Returns a function that caches the result of the given one for each distinct list of arguments.
+fn memoize(
+    _: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+
+    match &arguments[0] {
+        function @ (Object::Function(..) | Object::Builtin(_) | Object::Memoized(..)) => {
+            Ok(Object::Memoized(
+                Box::new(function.clone()),
+                Rc::new(RefCell::new(Cache::new())),
+            ))
+        }
+        argument => Err(EvaluateError::new(
+            EvaluateErrorKind::TypeMismatch(
+                argument.type_name().to_string(),
+                "function".to_string(),
+            ),
+            position,
+        )),
+    }
+}
+
+/// Calls a memoized function, looking the arguments up in its cache first.
+/// The cache isn't borrowed during the call, so a memoized function can call itself recursively.
+pub fn call_memoized(
+    evaluator: &mut Evaluator,
+    function: Object,
+    cache: Rc<RefCell<Cache>>,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    let key = {
+        let mut hasher = DefaultHasher::new();
+        arguments.hash(&mut hasher);
+        hasher.finish()
+    };
+
+    let cached = cache.borrow().get(&key).and_then(|entries| {
+        entries
+            .iter()
+            .find(|(cached, _)| *cached == arguments)
+            .map(|(_, result)| result.clone())
+    });
+
+    if let Some(result) = cached {
+        return Ok(result);
+    }
+
+    let result = evaluator.call_function(function, arguments.clone(), position)?;
+    cache
+        .borrow_mut()
+        .entry(key)
+        .or_default()
+        .push((arguments, result.clone()));
+
+    Ok(result)
+}
+
+/// Calls a predicate with an element, which must return a boolean.
+fn call_predicate(
+    evaluator: &mut Evaluator,
+    predicate: &Object,
+    element: &Object,
+    position: Position,
+) -> EvaluateResult<bool> {
+    match evaluator.call_function(predicate.clone(), vec![element.clone()], position)? {
+        Object::Boolean(result) => Ok(result),
+        result => Err(EvaluateError::new(
+            EvaluateErrorKind::TypeMismatch(result.type_name().to_string(), "boolean".to_string()),
+            position,
+        )),
+    }
+}
+
+/// `partition(array, predicate)`
+///
+/// Returns `[matching, nonmatching]`: the elements for which the predicate returns `true`, and the rest, in order.
+fn partition(
+    evaluator: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 2, position)?;
+
+    let elements = expect_array(arguments[0].clone(), position)?;
+    let (mut matching, mut nonmatching) = (Vec::new(), Vec::new());
+
+    for element in elements {
+        match call_predicate(evaluator, &arguments[1], &element, position)? {
+            true => matching.push(element),
+            false => nonmatching.push(element),
+        }
+    }
+
+    Ok(Object::Array(vec![
+        Object::Array(matching),
+        Object::Array(nonmatching),
+    ]))
+}
+
+/// `take(array, n)`
+///
+/// Returns the first `n` elements, or the whole array if it is shorter.
+fn take(_: &mut Evaluator, arguments: Vec<Object>, position: Position) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 2, position)?;
+
+    let elements = expect_array(arguments[0].clone(), position)?;
+    let n = expect_count(arguments[1].clone(), position)?;
+
+    Ok(Object::Array(elements.into_iter().take(n).collect()))
+}
+
+/// `drop(array, n)`
+///
+/// Returns the elements after the first `n`, or an empty array if it is shorter.
+fn drop(_: &mut Evaluator, arguments: Vec<Object>, position: Position) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 2, position)?;
+
+    let elements = expect_array(arguments[0].clone(), position)?;
+    let n = expect_count(arguments[1].clone(), position)?;
+
+    Ok(Object::Array(elements.into_iter().skip(n).collect()))
+}
+
+/// Returns how many leading elements of the array `take_while` and `drop_while` split off.
+fn leading_matches(
+    evaluator: &mut Evaluator,
+    arguments: &[Object],
+    position: Position,
+) -> EvaluateResult<(Vec<Object>, usize)> {
+    expect_arguments(arguments, 2, position)?;
+
+    let elements = expect_array(arguments[0].clone(), position)?;
+    let mut count = 0;
+
+    for element in &elements {
+        if !call_predicate(evaluator, &arguments[1], element, position)? {
+            break;
+        }
+
+        count += 1;
+    }
+
+    Ok((elements, count))
+}
+
+/// `take_while(array, predicate)`
+///
+/// Returns the leading elements for which the predicate returns `true`.
+fn take_while(
+    evaluator: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    let (mut elements, count) = leading_matches(evaluator, &arguments, position)?;
+    elements.truncate(count);
+
+    Ok(Object::Array(elements))
+}
+
+/// `drop_while(array, predicate)`
+///
+/// Returns the elements from the first one for which the predicate returns `false`.
+fn drop_while(
+    evaluator: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    let (mut elements, count) = leading_matches(evaluator, &arguments, position)?;
+    elements.drain(..count);
+
+    Ok(Object::Array(elements))
+}
+
+/// `count(array, predicate)`
+///
+/// Returns how many elements the predicate returns `true` for.
+fn count(
+    evaluator: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 2, position)?;
+
+    let mut count = 0;
+
+    for element in expect_array(arguments[0].clone(), position)? {
+        if call_predicate(evaluator, &arguments[1], &element, position)? {
+            count += 1;
+        }
+    }
+
+    Ok(Object::Number(count as f64))
+}
+
+/// `sum(array)`
+///
+/// Returns the sum of an array of numbers, which is `0` for an empty array.
+fn sum(_: &mut Evaluator, arguments: Vec<Object>, position: Position) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+
+    let numbers = expect_numbers(arguments[0].clone(), position)?;
+
+    Ok(Object::Number(numbers.iter().sum()))
+}
+
+/// `product(array)`
+///
+/// Returns the product of an array of numbers, which is `1` for an empty array.
+fn product(
+    _: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+
+    let numbers = expect_numbers(arguments[0].clone(), position)?;
+
+    Ok(Object::Number(numbers.iter().product()))
+}
+
+/// `average(array)`
+///
+/// Returns the mean of a non-empty array of numbers.
+fn average(
+    _: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+
+    let numbers = expect_numbers(arguments[0].clone(), position)?;
+
+    if numbers.is_empty() {
+        return Err(EvaluateError::new(
+            EvaluateErrorKind::EmptyArray("average".to_string()),
+            position,
+        ));
+    }
+
+    Ok(Object::Number(
+        numbers.iter().sum::<f64>() / numbers.len() as f64,
+    ))
+}
+
+/// `group_by(array, key)`
+///
+/// Groups the elements into a `Groups` struct with a field for each result of the key function.
+fn group_by(
+    evaluator: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 2, position)?;
+
+    let elements = expect_array(arguments[0].clone(), position)?;
+    let mut groups: Vec<(String, Object)> = Vec::new();
+
+    for element in elements {
+        let key =
+            match evaluator.call_function(arguments[1].clone(), vec![element.clone()], position)? {
+                key @ (Object::Number(_) | Object::String(_) | Object::Boolean(_)) => {
+                    key.to_string()
+                }
+                key => {
+                    return Err(EvaluateError::new(
+                        EvaluateErrorKind::TypeMismatch(
+                            key.type_name().to_string(),
+                            "string".to_string(),
+                        ),
+                        position,
+                    ))
+                }
+            };
+
+        match groups.iter_mut().find(|(name, _)| *name == key) {
+            Some((_, Object::Array(group))) => group.push(element),
+            _ => groups.push((key, Object::Array(vec![element]))),
+        }
+    }
+
+    Ok(Object::Struct("Groups".to_string(), groups))
+}
+
+/// `is_nan(number)`
+fn is_nan(_: &mut Evaluator, arguments: Vec<Object>, position: Position) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+
+    match &arguments[0] {
+        Object::Number(number) => Ok(Object::Boolean(number.is_nan())),
+        object => Err(EvaluateError::new(
+            EvaluateErrorKind::TypeMismatch(object.type_name().to_string(), "number".to_string()),
+            position,
+        )),
+    }
+}
+
+/// `approx_eq(a, b, epsilon)`
+///
+/// Returns whether the numbers differ by at most `epsilon`, e.g. `approx_eq(0.1 + 0.2, 0.3, 1e-9)` is `true`.
+fn approx_eq(
+    _: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 3, position)?;
+
+    let a = expect_number(arguments[0].clone(), position)?;
+    let b = expect_number(arguments[1].clone(), position)?;
+    let epsilon = expect_number(arguments[2].clone(), position)?;
+
+    Ok(Object::Boolean(a == b || (a - b).abs() <= epsilon))
+}
+
+/// `unique(array)`
+///
+/// Returns a new array without structurally equal duplicates, keeping the first occurrence of each element.
+fn unique(_: &mut Evaluator, arguments: Vec<Object>, position: Position) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+
+    let mut elements: Vec<Object> = Vec::new();
+
+    for element in expect_array(arguments[0].clone(), position)? {
+        if !elements.contains(&element) {
+            elements.push(element);
+        }
+    }
+
+    Ok(Object::Array(elements))
+}
+
+/// `flatten(array)`
+///
+/// Flattens one level of nested arrays, keeping the elements that are not arrays.
+fn flatten(
+    _: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+
+    Ok(Object::Array(
+        expect_array(arguments[0].clone(), position)?
+            .into_iter()
+            .flat_map(|element| match element {
+                Object::Array(elements) => elements,
+                element => vec![element],
+            })
+            .collect(),
+    ))
+}
+
+/// `flatten_strict(array)`
+///
+/// Same as `flatten`, but every element must be an array.
+fn flatten_strict(
+    _: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+
+    let mut elements = Vec::new();
+
+    for element in expect_array(arguments[0].clone(), position)? {
+        elements.extend(expect_array(element, position)?);
+    }
+
+    Ok(Object::Array(elements))
+}
+
+/// Returns the size argument of `chunk` and `windows`, truncated to an integer. It must be a finite number of at least `1`.
+fn expect_size(object: Object, position: Position) -> EvaluateResult<usize> {
+    let size = expect_number(object, position)?;
+
+    if !(size >= 1.0 && size.is_finite()) {
+        return Err(EvaluateError::new(
+            EvaluateErrorKind::InvalidSize(size.to_string()),
+            position,
+        ));
+    }
+
+    Ok(size as usize)
+}
+
+/// `chunk(array, n)`
+///
+/// Splits the array into arrays of `n` elements, the last of which may be shorter.
+fn chunk(_: &mut Evaluator, arguments: Vec<Object>, position: Position) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 2, position)?;
+
+    let elements = expect_array(arguments[0].clone(), position)?;
+    let size = expect_size(arguments[1].clone(), position)?;
+
+    Ok(Object::Array(
+        elements
+            .chunks(size)
+            .map(|chunk| Object::Array(chunk.to_vec()))
+            .collect(),
+    ))
+}
+
+/// `windows(array, n)`
+///
+/// Returns every run of `n` consecutive elements, in order.
+fn windows(
+    _: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 2, position)?;
+
+    let elements = expect_array(arguments[0].clone(), position)?;
+    let size = expect_size(arguments[1].clone(), position)?;
+
+    Ok(Object::Array(
+        elements
+            .windows(size)
+            .map(|window| Object::Array(window.to_vec()))
+            .collect(),
+    ))
+}
+
+/// `zip(a, b)`
+///
+/// Pairs the elements of two arrays into `[a, b]` arrays, truncating to the shorter one.
+fn zip(_: &mut Evaluator, arguments: Vec<Object>, position: Position) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 2, position)?;
+
+    let a = expect_array(arguments[0].clone(), position)?;
+    let b = expect_array(arguments[1].clone(), position)?;
+
+    Ok(Object::Array(
+        a.into_iter()
+            .zip(b)
+            .map(|(a, b)| Object::Array(vec![a, b]))
+            .collect(),
+    ))
+}
+
+/// `enumerate(array)`
+///
+/// Pairs each element with its index into `[index, element]` arrays.
+fn enumerate(
+    _: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+
+    Ok(Object::Array(
+        expect_array(arguments[0].clone(), position)?
+            .into_iter()
+            .enumerate()
+            .map(|(index, element)| Object::Array(vec![Object::Number(index as f64), element]))
+            .collect(),
+    ))
+}
+
+/// `reverse(array | string)`
+fn reverse(
+    _: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+
+    match arguments[0].clone() {
+        Object::String(string) => Ok(Object::String(string.chars().rev().collect())),
+        object => {
+            let mut elements = expect_array(object, position)?;
+            elements.reverse();
+
+            Ok(Object::Array(elements))
+        }
+    }
+}
+
+/// `rotate(array, n)`
+///
+/// Rotates the elements `n` positions to the right, or to the left if `n` is negative.
+fn rotate(_: &mut Evaluator, arguments: Vec<Object>, position: Position) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 2, position)?;
+
+    let mut elements = expect_array(arguments[0].clone(), position)?;
+
+    let n = expect_number(arguments[1].clone(), position)? as i64;
+
+    if !elements.is_empty() {
+        let n = n.rem_euclid(elements.len() as i64) as usize;
+        elements.rotate_right(n);
+    }
+
+    Ok(Object::Array(elements))
+}
+
+/// `now()`
+///
+/// Returns the current time in milliseconds since the Unix epoch.
+fn now(
+    evaluator: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 0, position)?;
+
+    Ok(Object::Number(evaluator.clock.now()))
+}
+
+/// `lines(string)`
+///
+/// Splits the string on `\n` (and `\r\n`) into an array of strings, without a trailing empty line.
+fn lines(_: &mut Evaluator, arguments: Vec<Object>, position: Position) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+
+    let string = expect_string(arguments[0].clone(), position)?;
+
+    Ok(Object::Array(
+        string
+            .lines()
+            .map(|line| Object::String(line.to_string()))
+            .collect(),
+    ))
+}
+
+/// `chars(string)`
+///
+/// Splits the string into an array of one-character strings, one for each Unicode scalar value.
+fn chars(_: &mut Evaluator, arguments: Vec<Object>, position: Position) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+
+    let string = expect_string(arguments[0].clone(), position)?;
+
+    Ok(Object::Array(
+        string
+            .chars()
+            .map(|c| Object::String(c.to_string()))
+            .collect(),
+    ))
+}
+
+/// `starts_with(string, prefix)`
+fn starts_with(
+    _: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 2, position)?;
+
+    let string = expect_string(arguments[0].clone(), position)?;
+    let prefix = expect_string(arguments[1].clone(), position)?;
+
+    Ok(Object::Boolean(string.starts_with(&prefix)))
+}
+
+/// `ends_with(string, suffix)`
+fn ends_with(
+    _: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 2, position)?;
+
+    let string = expect_string(arguments[0].clone(), position)?;
+    let suffix = expect_string(arguments[1].clone(), position)?;
+
+    Ok(Object::Boolean(string.ends_with(&suffix)))
+}
+
+/// `replace(string, from, to)`
+///
+/// Replaces all non-overlapping occurrences of `from`, scanning from the left.
+fn replace(
+    _: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 3, position)?;
+
+    let string = expect_string(arguments[0].clone(), position)?;
+    let from = expect_string(arguments[1].clone(), position)?;
+    let to = expect_string(arguments[2].clone(), position)?;
+
+    Ok(Object::String(string.replace(&from, &to)))
+}
+
+/// `repeat(string, n)`
+///
+/// Returns the string repeated `n` times.
+fn repeat(_: &mut Evaluator, arguments: Vec<Object>, position: Position) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 2, position)?;
+
+    let string = expect_string(arguments[0].clone(), position)?;
+    let n = expect_count(arguments[1].clone(), position)?;
+    expect_length(string.len().checked_mul(n), position)?;
+
+    Ok(Object::String(string.repeat(n)))
+}
+
+/// Checks the length of a string before it is built, which is `None` if computing it overflowed.
+fn expect_length(length: Option<usize>, position: Position) -> EvaluateResult<usize> {
+    match length {
+        Some(length) if length <= MAX_STRING_LENGTH => Ok(length),
+        _ => Err(EvaluateError::new(
+            EvaluateErrorKind::StringTooLong(MAX_STRING_LENGTH.to_string()),
+            position,
+        )),
+    }
+}
+
+fn expect_count(object: Object, position: Position) -> EvaluateResult<usize> {
+    let n = expect_number(object, position)?;
+
+    if n < 0.0 {
+        return Err(EvaluateError::new(
+            EvaluateErrorKind::NegativeCount(n.to_string()),
+            position,
+        ));
+    }
+
+    Ok(n as usize)
+}
+
+/// `indent(string, n)`
+///
+/// Prefixes each non-empty line of the string with `n` spaces.
+fn indent(_: &mut Evaluator, arguments: Vec<Object>, position: Position) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 2, position)?;
+
+    let string = expect_string(arguments[0].clone(), position)?;
+    let n = expect_count(arguments[1].clone(), position)?;
+    let lines = string.split('\n').filter(|line| !line.is_empty()).count();
+    expect_length(
+        lines
+            .checked_mul(n)
+            .and_then(|length| length.checked_add(string.len())),
+        position,
+    )?;
+    let prefix = " ".repeat(n);
+
+    Ok(Object::String(
+        string
+            .split('\n')
+            .map(|line| match line.is_empty() {
+                true => line.to_string(),
+                false => format!("{prefix}{line}"),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    ))
+}
+
+/// `dedent(string)`
+///
+/// Removes the leading whitespace common to every line that isn't blank.
+fn dedent(_: &mut Evaluator, arguments: Vec<Object>, position: Position) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+
+    let string = expect_string(arguments[0].clone(), position)?;
+    let is_blank = |line: &str| line.trim().is_empty();
+
+    let common = string
+        .split('\n')
+        .filter(|line| !is_blank(line))
+        .map(|line| &line[..line.len() - line.trim_start().len()])
+        .reduce(|common, indentation| {
+            let length = common
+                .chars()
+                .zip(indentation.chars())
+                .take_while(|(a, b)| a == b)
+                .map(|(c, _)| c.len_utf8())
+                .sum();
+
+            &common[..length]
+        })
+        .unwrap_or_default();
+
+    Ok(Object::String(
+        string
+            .split('\n')
+            .map(|line| match is_blank(line) {
+                true => "",
+                false => &line[common.len()..],
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    ))
+}
+
+/// Returns the arguments of `pad_left` and `pad_right`: the string, and the padding needed to reach the width.
+fn padding(arguments: Vec<Object>, position: Position) -> EvaluateResult<(String, String)> {
+    expect_arguments(&arguments, 3, position)?;
+
+    let string = expect_string(arguments[0].clone(), position)?;
+    let width = expect_count(arguments[1].clone(), position)?;
+    let fill = expect_string(arguments[2].clone(), position)?;
+
+    let mut chars = fill.chars();
+    let fill = match (chars.next(), chars.next()) {
+        (Some(fill), None) => fill,
+        _ => {
+            return Err(EvaluateError::new(
+                EvaluateErrorKind::InvalidCharacter(fill),
+                position,
+            ))
+        }
+    };
+
+    let missing = width.saturating_sub(string.chars().count());
+    expect_length(
+        fill.len_utf8()
+            .checked_mul(missing)
+            .and_then(|length| length.checked_add(string.len())),
+        position,
+    )?;
+    let padding = fill.to_string().repeat(missing);
+
+    Ok((string, padding))
+}
+
+/// `pad_left(string, width, char)`
+///
+/// Pads the start of the string with the character up to `width` characters.
+fn pad_left(
+    _: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    let (string, padding) = padding(arguments, position)?;
+
+    Ok(Object::String(padding + &string))
+}
+
+/// `pad_right(string, width, char)`
+///
+/// Pads the end of the string with the character up to `width` characters.
+fn pad_right(
+    _: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    let (string, padding) = padding(arguments, position)?;
+
+    Ok(Object::String(string + &padding))
+}
+
+/// `json_parse(string)`
+///
+/// Converts JSON text to a value: objects become structs named `Object`, and `null` becomes `null`.
+fn json_parse(
+    _: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+
+    let source = expect_string(arguments[0].clone(), position)?;
+
+    json::parse(&source)
+        .map_err(|error| EvaluateError::new(EvaluateErrorKind::InvalidJson(error), position))
+}
+
+/// `json_stringify(value)`
+///
+/// Converts a value to compact JSON text.
+fn json_stringify(
+    _: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+
+    let kind = match json::stringify(&arguments[0]) {
+        Ok(text) => return Ok(Object::String(text)),
+        Err(json::StringifyError::NotSerializable(type_name)) => {
+            EvaluateErrorKind::NotSerializable(type_name)
+        }
+        Err(json::StringifyError::TooDeep) => {
+            EvaluateErrorKind::ValueTooDeep(json::MAX_DEPTH.to_string())
+        }
+    };
+
+    Err(EvaluateError::new(kind, position))
+}
+
+/// `format(template, ...arguments)`
+///
+/// Replaces each `{}` in the template with the next argument. `{{` and `}}` are literal braces.
+fn format(_: &mut Evaluator, arguments: Vec<Object>, position: Position) -> EvaluateResult<Object> {
+    let mismatch = |expected: usize| {
+        EvaluateError::new(
+            EvaluateErrorKind::ArgumentCountMismatch(
+                (expected + 1).to_string(),
+                arguments.len().to_string(),
+            ),
+            position,
+        )
+    };
+
+    let Some(template) = arguments.first() else {
+        return Err(mismatch(0));
+    };
+    let template = expect_string(template.clone(), position)?;
+
+    let mut result = String::new();
+    let mut values = arguments[1..].iter();
+    let mut placeholders = 0;
+    let mut chars = template.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        match (char, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                result.push(char);
+            }
+            ('{', Some('}')) => {
+                chars.next();
+                placeholders += 1;
+
+                if let Some(value) = values.next() {
+                    result.push_str(&value.to_string());
+                }
+            }
+            ('{' | '}', _) => {
+                return Err(EvaluateError::new(
+                    EvaluateErrorKind::UnbalancedBrace(char.to_string()),
+                    position,
+                ))
+            }
+            _ => result.push(char),
+        }
+    }
+
+    if placeholders != arguments.len() - 1 {
+        return Err(mismatch(placeholders));
+    }
+
+    Ok(Object::String(result))
+}
+
+/// `buffer()`
+///
+/// Returns a new, empty buffer, which `push` and `pop` mutate in place.
+fn buffer(_: &mut Evaluator, arguments: Vec<Object>, position: Position) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 0, position)?;
+
+    Ok(Object::Buffer(Rc::new(RefCell::new(Vec::new()))))
+}
+
+/// `push(buffer, value)`
+///
+/// Appends the value to the buffer in place and returns the new length.
+fn push(_: &mut Evaluator, arguments: Vec<Object>, position: Position) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 2, position)?;
+
+    let elements = expect_buffer(arguments[0].clone(), position)?;
+    elements.borrow_mut().push(arguments[1].clone());
+
+    let length = elements.borrow().len();
+    Ok(Object::Number(length as f64))
+}
+
+/// `pop(buffer)`
+///
+/// Removes the last element of the buffer in place and returns it, or `null` if the buffer is empty.
+fn pop(_: &mut Evaluator, arguments: Vec<Object>, position: Position) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+
+    let elements = expect_buffer(arguments[0].clone(), position)?;
+    let element = elements.borrow_mut().pop();
+
+    Ok(element.unwrap_or(Object::Null))
+}
+
+/// `to_array(buffer)`
+///
+/// Returns an array with the current elements of the buffer.
+fn to_array(
+    _: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+
+    let elements = expect_buffer(arguments[0].clone(), position)?;
+    let elements = elements.borrow().clone();
+
+    Ok(Object::Array(elements))
+}
+
+/// `todo()`
+///
+/// Stubs out code that is not written yet, failing when it is reached.
+fn todo(_: &mut Evaluator, arguments: Vec<Object>, position: Position) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 0, position)?;
+
+    Err(EvaluateError::new(
+        EvaluateErrorKind::NotImplemented,
+        position,
+    ))
+}
+
+/// `panic(message)`
+///
+/// Fails with the given message, like `throw message;`.
+fn panic(_: &mut Evaluator, arguments: Vec<Object>, position: Position) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+    let message = expect_string(arguments[0].clone(), position)?;
+
+    Err(EvaluateError::new(
+        EvaluateErrorKind::UserError(message),
+        position,
+    ))
+}
+
+/// `exit(code)`
+///
+/// Stops the program with the given integer status code, as an `Exit` error that `try` does not catch.
+fn exit(_: &mut Evaluator, arguments: Vec<Object>, position: Position) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+    let code = expect_number(arguments[0].clone(), position)?;
+
+    if code.fract() != 0.0 || code < i32::MIN as f64 || code > i32::MAX as f64 {
+        return Err(EvaluateError::new(
+            EvaluateErrorKind::InvalidExitCode(code.to_string()),
+            position,
+        ));
+    }
+
+    Err(EvaluateError::new(
+        EvaluateErrorKind::Exit(code as i32),
+        position,
+    ))
+}
+
+/// Writes the text to the evaluator's output, unless it would cross `max_output` bytes in total.
+fn write_output(evaluator: &mut Evaluator, text: &str, position: Position) -> EvaluateResult<()> {
+    let written = evaluator.written + text.len();
+
+    if let Some(limit) = evaluator.max_output {
+        if written > limit {
+            return Err(EvaluateError::new(
+                EvaluateErrorKind::OutputLimitExceeded(limit.to_string()),
+                position,
+            ));
+        }
+    }
+
+    evaluator.written = written;
+    evaluator.output.write(text);
+
+    Ok(())
+}
+
+/// `print(value)`
+///
+/// Writes the value to the output.
+fn print(
+    evaluator: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+    write_output(evaluator, &arguments[0].to_string(), position)?;
+
+    Ok(Object::Void)
+}
+
+/// `println(value)`
+///
+/// Writes the value and a newline to the output.
+fn println(
+    evaluator: &mut Evaluator,
+    arguments: Vec<Object>,
+    position: Position,
+) -> EvaluateResult<Object> {
+    expect_arguments(&arguments, 1, position)?;
+    write_output(evaluator, &format!("{}\n", arguments[0]), position)?;
+
+    Ok(Object::Void)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::tests::evaluate;
+
+    #[test]
+    fn test_sort_numbers() {
+        let evaluator = evaluate("let x = sort([3, 1, 2]);").unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("x"),
+            Some(Object::Array(vec![
+                Object::Number(1.0),
+                Object::Number(2.0),
+                Object::Number(3.0),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_sort_strings() {
+        let evaluator = evaluate(r#"let x = sort(["b", "c", "a"]);"#).unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("x"),
+            Some(Object::Array(vec![
+                Object::String("a".to_string()),
+                Object::String("b".to_string()),
+                Object::String("c".to_string()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_sort_mixed_types() {
+        let error = evaluate(r#"let x = sort([1, "a"]);"#).unwrap_err();
+
+        assert_eq!(
+            error.kind,
+            EvaluateErrorKind::TypeMismatch("string".to_string(), "number".to_string())
+        );
+    }
+
+    #[test]
+    fn test_sort_by() {
+        let evaluator = evaluate(
+            "let x = sort_by([1, 3, 2], fn(a: number, b: number) -> number { return b - a; });",
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("x"),
+            Some(Object::Array(vec![
+                Object::Number(3.0),
+                Object::Number(2.0),
+                Object::Number(1.0),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_nan_comparison() {
+        let evaluator = evaluate(
+            r#"
+            let nan = 0 / 0;
+            let a = nan == nan;
+            let b = NaN != NaN;
+            let c = Infinity == 1 / 0;
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(evaluator.environment.get("a"), Some(Object::Boolean(false)));
+        assert_eq!(evaluator.environment.get("b"), Some(Object::Boolean(true)));
+        assert_eq!(evaluator.environment.get("c"), Some(Object::Boolean(true)));
+    }
+
+    #[test]
+    fn test_is_nan() {
+        let evaluator = evaluate(
+            r#"
+            let a = is_nan(0 / 0);
+            let b = is_nan(NaN);
+            let c = is_nan(Infinity);
+            let d = is_nan(1);
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(evaluator.environment.get("a"), Some(Object::Boolean(true)));
+        assert_eq!(evaluator.environment.get("b"), Some(Object::Boolean(true)));
+        assert_eq!(evaluator.environment.get("c"), Some(Object::Boolean(false)));
+        assert_eq!(evaluator.environment.get("d"), Some(Object::Boolean(false)));
+    }
+
+    fn numbers(numbers: &[f64]) -> Object {
+        Object::Array(numbers.iter().map(|x| Object::Number(*x)).collect())
+    }
+
+    #[test]
+    fn test_approx_eq() {
+        let evaluator = evaluate(
+            "
+            let a = approx_eq(0.1 + 0.2, 0.3, 0.000001);
+            let b = approx_eq(1, 1.1, 0.01);
+            let c = approx_eq(Infinity, Infinity, 0);
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(evaluator.environment.get("a"), Some(Object::Boolean(true)));
+        assert_eq!(evaluator.environment.get("b"), Some(Object::Boolean(false)));
+        assert_eq!(evaluator.environment.get("c"), Some(Object::Boolean(true)));
+    }
+
+    #[test]
+    fn test_unique() {
+        let evaluator = evaluate(
+            r#"
+            let x = unique([1, 2, 1, 3, 2]);
+            let y = unique([[1, 2], [3], [1, 2]]);
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("x"),
+            Some(numbers(&[1.0, 2.0, 3.0]))
+        );
+        assert_eq!(
+            evaluator.environment.get("y"),
+            Some(Object::Array(vec![numbers(&[1.0, 2.0]), numbers(&[3.0])]))
+        );
+    }
+
+    #[test]
+    fn test_flatten() {
+        let evaluator = evaluate(
+            r#"
+            let x = flatten([[1, 2], [[3]], 4]);
+            let y = flatten_strict([[1], [2, 3]]);
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("x"),
+            Some(Object::Array(vec![
+                Object::Number(1.0),
+                Object::Number(2.0),
+                numbers(&[3.0]),
+                Object::Number(4.0),
+            ]))
+        );
+        assert_eq!(
+            evaluator.environment.get("y"),
+            Some(numbers(&[1.0, 2.0, 3.0]))
+        );
+
+        assert_eq!(
+            evaluate("let x = flatten_strict([[1], 2]);")
+                .unwrap_err()
+                .kind,
+            EvaluateErrorKind::TypeMismatch("number".to_string(), "array".to_string())
+        );
+    }
+
+    #[test]
+    fn test_zip() {
+        let evaluator = evaluate(
+            r#"
+            let x = zip([1, 2], [3, 4]);
+            let y = zip([1, 2, 3], [4]);
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("x"),
+            Some(Object::Array(vec![
+                numbers(&[1.0, 3.0]),
+                numbers(&[2.0, 4.0])
+            ]))
+        );
+        assert_eq!(
+            evaluator.environment.get("y"),
+            Some(Object::Array(vec![numbers(&[1.0, 4.0])]))
+        );
+    }
+
+    #[test]
+    fn test_chunk_and_windows() {
+        let evaluator = evaluate(
+            r#"
+            let even = chunk([1, 2, 3, 4], 2);
+            let remainder = chunk([1, 2, 3, 4, 5], 2);
+            let sliding = windows([1, 2, 3, 4], 3);
+            let short = windows([1, 2], 3);
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("even"),
+            Some(Object::Array(vec![
+                numbers(&[1.0, 2.0]),
+                numbers(&[3.0, 4.0])
+            ]))
+        );
+        assert_eq!(
+            evaluator.environment.get("remainder"),
+            Some(Object::Array(vec![
+                numbers(&[1.0, 2.0]),
+                numbers(&[3.0, 4.0]),
+                numbers(&[5.0])
+            ]))
+        );
+        assert_eq!(
+            evaluator.environment.get("sliding"),
+            Some(Object::Array(vec![
+                numbers(&[1.0, 2.0, 3.0]),
+                numbers(&[2.0, 3.0, 4.0])
+            ]))
+        );
+        assert_eq!(
+            evaluator.environment.get("short"),
+            Some(Object::Array(vec![]))
+        );
+
+        assert_eq!(
+            evaluate("chunk([1], 0);").unwrap_err().kind,
+            EvaluateErrorKind::InvalidSize("0".to_string())
+        );
+        assert_eq!(
+            evaluate("windows([1], 0.5);").unwrap_err().kind,
+            EvaluateErrorKind::InvalidSize("0.5".to_string())
+        );
+        assert_eq!(
+            evaluate("chunk([1], 0 / 0);").unwrap_err().kind,
+            EvaluateErrorKind::InvalidSize("NaN".to_string())
+        );
+        assert_eq!(
+            evaluate("windows([1], 1 / 0);").unwrap_err().kind,
+            EvaluateErrorKind::InvalidSize("inf".to_string())
+        );
+    }
+
+    #[test]
+    fn test_enumerate() {
+        let evaluator = evaluate(r#"let x = enumerate(["a", "b"]);"#).unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("x"),
+            Some(Object::Array(vec![
+                Object::Array(vec![Object::Number(0.0), Object::String("a".to_string())]),
+                Object::Array(vec![Object::Number(1.0), Object::String("b".to_string())]),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_reverse() {
+        let evaluator = evaluate(
+            r#"
+            let x = reverse([1, 2, 3]);
+            let y = reverse("abc");
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("x"),
+            Some(numbers(&[3.0, 2.0, 1.0]))
+        );
+        assert_eq!(
+            evaluator.environment.get("y"),
+            Some(Object::String("cba".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_rotate() {
+        let evaluator = evaluate(
+            r#"
+            let a = rotate([1, 2, 3, 4], 1);
+            let b = rotate([1, 2, 3, 4], -1);
+            let c = rotate([1, 2, 3, 4], 6);
+            let d = rotate([], 3);
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("a"),
+            Some(numbers(&[4.0, 1.0, 2.0, 3.0]))
+        );
+        assert_eq!(
+            evaluator.environment.get("b"),
+            Some(numbers(&[2.0, 3.0, 4.0, 1.0]))
+        );
+        assert_eq!(
+            evaluator.environment.get("c"),
+            Some(numbers(&[3.0, 4.0, 1.0, 2.0]))
+        );
+        assert_eq!(evaluator.environment.get("d"), Some(numbers(&[])));
+    }
+
+    #[derive(Debug)]
+    struct FixedClock(f64);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> f64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_now() {
+        let program = parser::Parser::from("let x = now();")
+            .parse_program()
+            .unwrap();
+
+        let mut evaluator = Evaluator::new(program);
+        evaluator.clock = Box::new(FixedClock(1_700_000_000_000.0));
+        evaluator.evaluate().unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("x"),
+            Some(Object::Number(1_700_000_000_000.0))
+        );
+    }
+
+    #[test]
+    fn test_lines_and_chars() {
+        let strings = |strings: &[&str]| {
+            Object::Array(
+                strings
+                    .iter()
+                    .map(|x| Object::String(x.to_string()))
+                    .collect(),
+            )
+        };
+        let mut evaluator = evaluate(
+            "let a = lines(\"one\ntwo\r\n\nthree\n\");
+            let b = lines(\"\");
+            let d = chars(\"\");",
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("a"),
+            Some(strings(&["one", "two", "", "three"]))
+        );
+        assert_eq!(evaluator.environment.get("b"), Some(strings(&[])));
+        assert_eq!(
+            chars(
+                &mut evaluator,
+                vec![Object::String("añ한".to_string())],
+                Position::default()
+            ),
+            Ok(strings(&["a", "ñ", "한"]))
+        );
+        assert_eq!(evaluator.environment.get("d"), Some(strings(&[])));
+    }
+
+    #[test]
+    fn test_string_functions() {
+        let evaluator = evaluate(
+            r#"
+            let a = starts_with("sanetaka", "sane");
+            let b = ends_with("sanetaka", "sane");
+            let c = replace("a-b-c", "-", "+");
+            let d = replace("aaa", "aa", "b");
+            let e = repeat("ab", 3);
+            let f = repeat("ab", 0);
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(evaluator.environment.get("a"), Some(Object::Boolean(true)));
+        assert_eq!(evaluator.environment.get("b"), Some(Object::Boolean(false)));
+        assert_eq!(
+            evaluator.environment.get("c"),
+            Some(Object::String("a+b+c".to_string()))
+        );
+        assert_eq!(
+            evaluator.environment.get("d"),
+            Some(Object::String("ba".to_string()))
+        );
+        assert_eq!(
+            evaluator.environment.get("e"),
+            Some(Object::String("ababab".to_string()))
+        );
+        assert_eq!(
+            evaluator.environment.get("f"),
+            Some(Object::String(String::new()))
+        );
+        assert_eq!(
+            evaluate(r#"let x = repeat("ab", -1);"#).unwrap_err().kind,
+            EvaluateErrorKind::NegativeCount("-1".to_string())
+        );
+
+        for n in ["1_000_000_000_000_000_000", "Infinity"] {
+            assert_eq!(
+                evaluate(&format!(r#"let x = repeat("ab", {n});"#))
+                    .unwrap_err()
+                    .kind,
+                EvaluateErrorKind::StringTooLong(MAX_STRING_LENGTH.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_indentation() {
+        let evaluator = evaluate(
+            "
+            let a = indent(\"a\n  b\n\nc\", 2);
+            let b = dedent(\"    a\n      b\n  \n    c\");
+            let c = dedent(\"  a\n\tb\");
+            let d = indent(dedent(\"  x\n  y\"), 0);
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("a"),
+            Some(Object::String("  a\n    b\n\n  c".to_string()))
+        );
+        assert_eq!(
+            evaluator.environment.get("b"),
+            Some(Object::String("a\n  b\n\nc".to_string()))
+        );
+        assert_eq!(
+            evaluator.environment.get("c"),
+            Some(Object::String("  a\n\tb".to_string()))
+        );
+        assert_eq!(
+            evaluator.environment.get("d"),
+            Some(Object::String("x\ny".to_string()))
+        );
+        assert_eq!(
+            evaluate(r#"let x = indent("a", -1);"#).unwrap_err().kind,
+            EvaluateErrorKind::NegativeCount("-1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_padding() {
+        let evaluator = evaluate(
+            r#"
+            let a = pad_left("7", 3, "0");
+            let b = pad_right("ab", 4, ".");
+            let c = pad_left("long", 2, " ");
+            let d = pad_right("long", 4, " ");
+            "#,
+        )
+        .unwrap();
+
+        for source in [
+            r#"let x = pad_left("a", 1_000_000_000_000_000_000, " ");"#,
+            r#"let x = pad_right("a", Infinity, " ");"#,
+            r#"let x = indent("a\nb", 1_000_000_000_000_000_000);"#,
+        ] {
+            assert_eq!(
+                evaluate(source).unwrap_err().kind,
+                EvaluateErrorKind::StringTooLong(MAX_STRING_LENGTH.to_string())
+            );
+        }
+
+        assert_eq!(
+            evaluator.environment.get("a"),
+            Some(Object::String("007".to_string()))
+        );
+        assert_eq!(
+            evaluator.environment.get("b"),
+            Some(Object::String("ab..".to_string()))
+        );
+        assert_eq!(
+            evaluator.environment.get("c"),
+            Some(Object::String("long".to_string()))
+        );
+        assert_eq!(
+            evaluator.environment.get("d"),
+            Some(Object::String("long".to_string()))
+        );
+        assert_eq!(
+            evaluate(r#"let x = pad_left("a", 3, "ab");"#)
+                .unwrap_err()
+                .kind,
+            EvaluateErrorKind::InvalidCharacter("ab".to_string())
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let evaluator = evaluate(
+            r#"
+            let source = "{\"name\": \"sig\", \"tags\": [\"a\", {\"nested\": true}], \"n\": -1.5e2, \"none\": null}";
+            let value = json_parse(source);
+            let text = json_stringify(value);
+            let again = json_parse(text);
+            "#,
+        )
+        .unwrap();
+        let object = |fields: Vec<(&str, Object)>| {
+            Object::Struct(
+                json::OBJECT.to_string(),
+                fields
+                    .into_iter()
+                    .map(|(name, value)| (name.to_string(), value))
+                    .collect(),
+            )
+        };
+        let value = object(vec![
+            ("name", Object::String("sig".to_string())),
+            (
+                "tags",
+                Object::Array(vec![
+                    Object::String("a".to_string()),
+                    object(vec![("nested", Object::Boolean(true))]),
+                ]),
+            ),
+            ("n", Object::Number(-150.0)),
+            ("none", Object::Null),
+        ]);
+
+        assert_eq!(evaluator.environment.get("value"), Some(value.clone()));
+        assert_eq!(
+            evaluator.environment.get("text"),
+            Some(Object::String(
+                r#"{"name":"sig","tags":["a",{"nested":true}],"n":-150,"none":null}"#.to_string()
+            ))
+        );
+        assert_eq!(evaluator.environment.get("again"), Some(value));
+        assert_eq!(
+            evaluate("let x = json_stringify([1, fn() {}]);")
+                .unwrap_err()
+                .kind,
+            EvaluateErrorKind::NotSerializable("function".to_string())
+        );
+        assert_eq!(
+            evaluate(r#"let x = json_parse("[1, 2");"#)
+                .unwrap_err()
+                .kind,
+            EvaluateErrorKind::InvalidJson("expected `]`, got the end of the text".to_string())
+        );
+        assert_eq!(
+            evaluate("let b = buffer(); push(b, b); let x = json_stringify(b);")
+                .unwrap_err()
+                .kind,
+            EvaluateErrorKind::ValueTooDeep("128".to_string())
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        let evaluator = evaluate(
+            r#"
+            let a = format("{} + {} = {}", 1, 2, "three");
+            let b = format("{{}} {{{}}}", true);
+            let c = format("no placeholders");
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("a"),
+            Some(Object::String("1 + 2 = three".to_string()))
+        );
+        assert_eq!(
+            evaluator.environment.get("b"),
+            Some(Object::String("{} {true}".to_string()))
+        );
+        assert_eq!(
+            evaluator.environment.get("c"),
+            Some(Object::String("no placeholders".to_string()))
+        );
+
+        assert_eq!(
+            evaluate(r#"format("{} {}", 1);"#).unwrap_err().kind,
+            EvaluateErrorKind::ArgumentCountMismatch("3".to_string(), "2".to_string())
+        );
+        assert_eq!(
+            evaluate(r#"format("{}", 1, 2);"#).unwrap_err().kind,
+            EvaluateErrorKind::ArgumentCountMismatch("2".to_string(), "3".to_string())
+        );
+        assert_eq!(
+            evaluate("format();").unwrap_err().kind,
+            EvaluateErrorKind::ArgumentCountMismatch("1".to_string(), "0".to_string())
+        );
+
+        for (source, brace) in [
+            (r#"format("{", 1);"#, "{"),
+            (r#"format("a } b");"#, "}"),
+            (r#"format("{x}");"#, "{"),
+            (r#"format("{}}", 1);"#, "}"),
+        ] {
+            assert_eq!(
+                evaluate(source).unwrap_err().kind,
+                EvaluateErrorKind::UnbalancedBrace(brace.to_string()),
+                "{source}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_buffer() {
+        let evaluator = evaluate(
+            r#"
+            let a = buffer();
+            let b = a;
+            push(b, 1);
+            push(a, 2);
+            let snapshot = to_array(a);
+            let last = pop(b);
+            mut i = 0;
+            while i < 10000 {
+                push(a, i);
+                i = i + 1;
+            };
+            let elements = to_array(a);
+            let cyclic = buffer();
+            push(cyclic, cyclic);
+            let other = buffer();
+            push(other, other);
+            let equal = [cyclic == cyclic, cyclic == other, [cyclic] == [other]];
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("snapshot"),
+            Some(numbers(&[1.0, 2.0]))
+        );
+        assert_eq!(evaluator.environment.get("last"), Some(Object::Number(2.0)));
+        assert_eq!(
+            evaluator.environment.get("equal"),
+            Some(Object::Array(vec![Object::Boolean(true); 3]))
+        );
+
+        let elements = match evaluator.environment.get("elements") {
+            Some(Object::Array(elements)) => elements,
+            other => panic!("expected an array, got {other:?}"),
+        };
+
+        assert_eq!(elements.len(), 10_001);
+        assert_eq!(elements.last(), Some(&Object::Number(9_999.0)));
+    }
+
+    #[test]
+    fn test_todo() {
+        assert!(evaluate("let f = fn() -> number { todo() };").is_ok());
+
+        let error = evaluate("let x = 1;\n  todo();").unwrap_err();
+
+        assert_eq!(error.kind, EvaluateErrorKind::NotImplemented);
+        assert_eq!((error.position.0, error.position.1), (2, 7));
+    }
+
+    #[test]
+    fn test_panic() {
+        let evaluator =
+            evaluate(r#"let message = try { panic("oops") } catch (e) { e };"#).unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("message"),
+            Some(Object::String("oops".to_string()))
+        );
+        assert_eq!(
+            evaluate("panic(1);").unwrap_err().kind,
+            EvaluateErrorKind::TypeMismatch("number".to_string(), "string".to_string())
+        );
+    }
+
+    #[test]
+    fn test_exit() {
+        let error = evaluate(
+            r#"
+            let x = try { exit(3); 1 } catch (e) { 2 };
+            panic("unreachable");
+            "#,
+        )
+        .unwrap_err();
+
+        assert_eq!(error.kind, EvaluateErrorKind::Exit(3));
+
+        assert_eq!(
+            evaluate("exit(-1);").unwrap_err().kind,
+            EvaluateErrorKind::Exit(-1)
+        );
+
+        for (source, code) in [
+            ("exit(1.5);", "1.5"),
+            ("exit(2147483648);", "2147483648"),
+            ("exit(0 / 0);", "NaN"),
+        ] {
+            assert_eq!(
+                evaluate(source).unwrap_err().kind,
+                EvaluateErrorKind::InvalidExitCode(code.to_string()),
+                "{source}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_map() {
+        let evaluator =
+            evaluate("let xs = map([1, 2, 3], fn(x: number) -> number { return x * 2; });")
+                .unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("xs"),
+            Some(numbers(&[2.0, 4.0, 6.0]))
+        );
+    }
+
+    #[test]
+    fn test_memoize() {
+        let evaluator = evaluate(
+            r#"
+            mut calls = 0;
+            let fib = fn(n: number) -> number {
+                calls = calls + 1;
+                if n <= 1 { n } else { fib(n - 1) + fib(n - 2) }
+            };
+            let x = fib(15);
+            let plain_calls = calls;
+
+            calls = 0;
+            let memoized = memoize(fn(n: number) -> number {
+                calls = calls + 1;
+                if n <= 1 { n } else { memoized(n - 1) + memoized(n - 2) }
+            });
+            let y = memoized(15);
+            let z = memoized(15);
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(evaluator.environment.get("x"), Some(Object::Number(610.0)));
+        assert_eq!(evaluator.environment.get("y"), Some(Object::Number(610.0)));
+        assert_eq!(evaluator.environment.get("z"), Some(Object::Number(610.0)));
+        assert_eq!(
+            evaluator.environment.get("plain_calls"),
+            Some(Object::Number(1973.0))
+        );
+        assert_eq!(
+            evaluator.environment.get("calls"),
+            Some(Object::Number(16.0))
+        );
+        assert_eq!(
+            evaluate(
+                "let b = buffer(); push(b, b); let f = memoize(fn(x: number[]) -> number { 1 }); let x = f(b);"
+            )
+            .unwrap()
+            .environment
+            .get("x"),
+            Some(Object::Number(1.0))
+        );
+        assert_eq!(
+            evaluate("let f = memoize(1);").unwrap_err().kind,
+            EvaluateErrorKind::TypeMismatch("number".to_string(), "function".to_string())
+        );
+    }
+
+    #[test]
+    fn test_partition() {
+        let evaluator = evaluate(
+            "let x = partition([1, 2, 3, 4, 5], fn(x: number) -> boolean { x % 2 == 0 });",
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("x"),
+            Some(Object::Array(vec![
+                numbers(&[2.0, 4.0]),
+                numbers(&[1.0, 3.0, 5.0])
+            ]))
+        );
+        assert_eq!(
+            evaluate("let x = partition([1], fn(x: number) -> number { x });")
+                .unwrap_err()
+                .kind,
+            EvaluateErrorKind::TypeMismatch("number".to_string(), "boolean".to_string())
+        );
+    }
+
+    #[test]
+    fn test_reductions() {
+        let evaluator = evaluate(
+            "
+            let xs = [1, 2, 3, 4];
+            let a = sum(xs);
+            let b = product(xs);
+            let c = average(xs);
+            let d = count(xs, fn(x: number) -> boolean { x % 2 == 0 });
+            let e = sum([]);
+            let f = product([]);
+            let g = count([], fn(x: number) -> boolean { true });
+            ",
+        )
+        .unwrap();
+        let get = |name| evaluator.environment.get(name).unwrap();
+
+        assert_eq!(get("a"), Object::Number(10.0));
+        assert_eq!(get("b"), Object::Number(24.0));
+        assert_eq!(get("c"), Object::Number(2.5));
+        assert_eq!(get("d"), Object::Number(2.0));
+        assert_eq!(get("e"), Object::Number(0.0));
+        assert_eq!(get("f"), Object::Number(1.0));
+        assert_eq!(get("g"), Object::Number(0.0));
+        assert_eq!(
+            evaluate("let x = average([]);").unwrap_err().kind,
+            EvaluateErrorKind::EmptyArray("average".to_string())
+        );
+        assert_eq!(
+            evaluate(r#"let x = sum([1, "2"]);"#).unwrap_err().kind,
+            EvaluateErrorKind::TypeMismatch("string".to_string(), "number".to_string())
+        );
+    }
+
+    #[test]
+    fn test_take_and_drop() {
+        let evaluator = evaluate(
+            r#"
+            let xs = [1, 2, 3, 4];
+            let small = fn(x: number) -> boolean { x < 3 };
+            let none = fn(x: number) -> boolean { false };
+            let all = fn(x: number) -> boolean { true };
+
+            let a = take(xs, 2);
+            let b = take(xs, 10);
+            let c = drop(xs, 1);
+            let d = drop(xs, 10);
+            let e = take_while(xs, small);
+            let f = drop_while(xs, small);
+            let g = take_while(xs, none);
+            let h = drop_while(xs, none);
+            let i = take_while(xs, all);
+            let j = drop_while(xs, all);
+            let k = take_while([1, 5, 2], small);
+            "#,
+        )
+        .unwrap();
+        let get = |name| evaluator.environment.get(name).unwrap();
+
+        assert_eq!(get("a"), numbers(&[1.0, 2.0]));
+        assert_eq!(get("b"), numbers(&[1.0, 2.0, 3.0, 4.0]));
+        assert_eq!(get("c"), numbers(&[2.0, 3.0, 4.0]));
+        assert_eq!(get("d"), numbers(&[]));
+        assert_eq!(get("e"), numbers(&[1.0, 2.0]));
+        assert_eq!(get("f"), numbers(&[3.0, 4.0]));
+        assert_eq!(get("g"), numbers(&[]));
+        assert_eq!(get("h"), numbers(&[1.0, 2.0, 3.0, 4.0]));
+        assert_eq!(get("i"), numbers(&[1.0, 2.0, 3.0, 4.0]));
+        assert_eq!(get("j"), numbers(&[]));
+        assert_eq!(get("k"), numbers(&[1.0]));
+        assert_eq!(
+            evaluate("let x = take([1], -1);").unwrap_err().kind,
+            EvaluateErrorKind::NegativeCount("-1".to_string())
+        );
+        assert_eq!(
+            evaluate("let x = drop_while([1], fn(x: number) -> number { x });")
+                .unwrap_err()
+                .kind,
+            EvaluateErrorKind::TypeMismatch("number".to_string(), "boolean".to_string())
+        );
+    }
+
+    #[test]
+    fn test_group_by() {
+        let evaluator = evaluate(
+            r#"
+            let groups = group_by(["apple", "avocado", "banana", "cherry", "blueberry"], fn(x: string) -> string { x[0] });
+            let a = groups["a"];
+            "#,
+        )
+        .unwrap();
+
+        let strings = |strings: &[&str]| {
+            Object::Array(
+                strings
+                    .iter()
+                    .map(|string| Object::String(string.to_string()))
+                    .collect(),
+            )
+        };
+
+        assert_eq!(
+            evaluator.environment.get("groups"),
+            Some(Object::Struct(
+                "Groups".to_string(),
+                vec![
+                    ("a".to_string(), strings(&["apple", "avocado"])),
+                    ("b".to_string(), strings(&["banana", "blueberry"])),
+                    ("c".to_string(), strings(&["cherry"])),
+                ]
+            ))
+        );
+        assert_eq!(
+            evaluator.environment.get("a"),
+            Some(strings(&["apple", "avocado"]))
+        );
+        assert_eq!(
+            evaluate("let x = group_by([1, 2], fn(x: number) -> number { x % 2 });")
+                .unwrap()
+                .environment
+                .get("x"),
+            Some(Object::Struct(
+                "Groups".to_string(),
+                vec![
+                    ("1".to_string(), numbers(&[1.0])),
+                    ("0".to_string(), numbers(&[2.0])),
+                ]
+            ))
+        );
+        assert_eq!(
+            evaluate("let x = group_by([1], fn(x: number) -> number[] { [x] });")
+                .unwrap_err()
+                .kind,
+            EvaluateErrorKind::TypeMismatch("array".to_string(), "string".to_string())
+        );
+    }
+
+    #[derive(Debug)]
+    struct SharedOutput(Rc<RefCell<String>>);
+
+    impl Output for SharedOutput {
+        fn write(&mut self, text: &str) {
+            self.0.borrow_mut().push_str(text);
+        }
+    }
+
+    #[test]
+    fn test_print() {
+        let program = parser::Parser::from(r#"print("a"); println(1); println([2]);"#)
+            .parse_program()
+            .unwrap();
+        let output = Rc::new(RefCell::new(String::new()));
+
+        let mut evaluator = Evaluator::new(program);
+        evaluator.output = Box::new(SharedOutput(output.clone()));
+        evaluator.evaluate().unwrap();
+
+        assert_eq!(*output.borrow(), "a1\nArray\n");
+    }
+
+    #[test]
+    fn test_print_output_limit() {
+        let program = parser::Parser::from(
+            r#"
+            let spam = fn(n: number) -> number {
+                println("spam");
+                spam(n + 1)
+            };
+            spam(0);
+            "#,
+        )
+        .parse_program()
+        .unwrap();
+        let output = Rc::new(RefCell::new(String::new()));
+
+        let mut evaluator = Evaluator::new(program);
+        evaluator.output = Box::new(SharedOutput(output.clone()));
+        evaluator.max_output = Some(32);
+
+        assert_eq!(
+            evaluator.evaluate().unwrap_err().kind,
+            EvaluateErrorKind::OutputLimitExceeded("32".to_string())
+        );
+        assert_eq!(*output.borrow(), "spam\n".repeat(6));
+    }
+}
@@ -1,31 +1,34 @@
 use super::object::Object;
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
 
-#[derive(Debug, PartialEq, Clone)]
+/// A scope of bindings. Cloning an environment shares its bindings, which is how closures capture it by reference.
+#[derive(Clone)]
 pub struct Environment {
-    pub store: HashMap<String, Object>,
+    pub store: Rc<RefCell<HashMap<String, Object>>>,
     pub parent: Option<Box<Environment>>,
 }
 
 impl Environment {
     pub fn new(parent: Option<Environment>) -> Self {
         Self {
-            store: HashMap::new(),
+            store: Rc::new(RefCell::new(HashMap::new())),
             parent: parent.map(Box::new),
         }
     }
 
     pub fn insert(&mut self, name: &str, object: Object) -> Option<()> {
-        if self.store.contains_key(name) {
+        let mut store = self.store.borrow_mut();
+
+        if store.contains_key(name) {
             return None;
         }
 
-        self.store.insert(name.to_string(), object);
+        store.insert(name.to_string(), object);
         Some(())
     }
 
     pub fn get(&self, name: &str) -> Option<Object> {
-        match self.store.get(name) {
+        match self.store.borrow().get(name) {
             Some(object) => Some(object.clone()),
             None => match &self.parent {
                 Some(parent) => parent.get(name),
@@ -34,8 +37,13 @@ impl Environment {
         }
     }
 
+    /// Removes a binding from this scope (not from its parents), so the name can be declared again.
+    pub fn remove(&mut self, name: &str) -> Option<Object> {
+        self.store.borrow_mut().remove(name)
+    }
+
     pub fn set(&mut self, name: &str, new: Object) -> Option<()> {
-        match self.store.get_mut(name) {
+        match self.store.borrow_mut().get_mut(name) {
             Some(object) => {
                 *object = new;
                 Some(())
@@ -46,4 +54,80 @@ impl Environment {
             },
         }
     }
+
+    /// The number of scopes enclosing this one, so the global scope has a depth of `0`.
+    pub fn depth(&self) -> usize {
+        self.scopes().count() - 1
+    }
+
+    /// Whether a scope shares its bindings with one of its ancestors, so that looking a name up
+    /// could reach the same bindings twice.
+    pub fn has_cycle(&self) -> bool {
+        self.repeated_scope().is_some()
+    }
+
+    /// The depths (from this scope) of the first scope sharing its bindings with an ancestor, and of that ancestor.
+    pub(crate) fn repeated_scope(&self) -> Option<(usize, usize)> {
+        let scopes = self.scopes().collect::<Vec<_>>();
+
+        scopes.iter().enumerate().find_map(|(index, scope)| {
+            scopes[index + 1..]
+                .iter()
+                .position(|parent| Rc::ptr_eq(&scope.store, &parent.store))
+                .map(|depth| (index, index + depth + 1))
+        })
+    }
+
+    fn scopes(&self) -> impl Iterator<Item = &Environment> {
+        std::iter::successors(Some(self), |scope| scope.parent.as_deref())
+    }
+}
+
+/// Environments are compared by identity, since closures stored in them may refer back to them.
+impl PartialEq for Environment {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.store, &other.store) && self.parent == other.parent
+    }
+}
+
+/// Only the names are printed, for the same reason.
+impl fmt::Debug for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let store = self.store.borrow();
+        let mut names = store.keys().collect::<Vec<_>>();
+        names.sort();
+
+        f.debug_struct("Environment")
+            .field("store", &names)
+            .field("parent", &self.parent)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_depth() {
+        let global = Environment::new(None);
+        let function = Environment::new(Some(global.clone()));
+        let block = Environment::new(Some(function.clone()));
+
+        assert_eq!(global.depth(), 0);
+        assert_eq!(function.depth(), 1);
+        assert_eq!(block.depth(), 2);
+        assert!(!block.has_cycle());
+    }
+
+    #[test]
+    fn test_has_cycle() {
+        let global = Environment::new(None);
+        let function = Environment::new(Some(global.clone()));
+        let mut block = Environment::new(Some(function));
+        block.store = global.store.clone();
+
+        assert!(block.has_cycle());
+        assert_eq!(block.repeated_scope(), Some((0, 2)));
+    }
 }
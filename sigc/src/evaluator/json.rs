@@ -0,0 +1,348 @@
+//! Conversion between JSON text and values, used by the `json_parse` and `json_stringify` builtins.
+//! JSON objects become structs named `Object`, keeping the order of their fields.
+
+use super::object::Object;
+use std::{iter::Peekable, str::Chars};
+
+/// The name of the structs JSON objects are parsed into.
+pub const OBJECT: &str = "Object";
+
+/// How deeply arrays and objects can nest, so that parsing (or converting a buffer that contains itself)
+/// can't overflow the stack.
+pub const MAX_DEPTH: usize = 128;
+
+/// Why a value can't be converted to JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StringifyError {
+    /// The type name of the first part that has no JSON equivalent.
+    NotSerializable(String),
+    /// Arrays and structs are nested deeper than `MAX_DEPTH`, e.g. because a buffer contains itself.
+    TooDeep,
+}
+
+/// Parses a JSON text, returning a description of the first problem if it is malformed.
+pub fn parse(source: &str) -> Result<Object, String> {
+    let mut parser = JsonParser {
+        chars: source.chars().peekable(),
+        depth: 0,
+    };
+
+    let value = parser.value()?;
+    parser.whitespace();
+
+    match parser.chars.next() {
+        None => Ok(value),
+        Some(char) => Err(format!("unexpected `{char}` after the value")),
+    }
+}
+
+/// Converts a value to JSON text. Buffers are written as arrays, and structs as objects without their names.
+pub fn stringify(object: &Object) -> Result<String, StringifyError> {
+    stringify_nested(object, 0)
+}
+
+fn stringify_nested(object: &Object, depth: usize) -> Result<String, StringifyError> {
+    if depth > MAX_DEPTH {
+        return Err(StringifyError::TooDeep);
+    }
+
+    let list = |objects: &[Object]| {
+        objects
+            .iter()
+            .map(|object| stringify_nested(object, depth + 1))
+            .collect::<Result<Vec<_>, _>>()
+            .map(|elements| elements.join(","))
+    };
+
+    Ok(match object {
+        Object::Number(number) if number.is_finite() => number.to_string(),
+        Object::Number(number) => return Err(StringifyError::NotSerializable(number.to_string())),
+        Object::String(string) => quote(string),
+        Object::Boolean(boolean) => boolean.to_string(),
+        Object::Array(elements) => format!("[{}]", list(elements)?),
+        Object::Buffer(elements) => format!("[{}]", list(&elements.borrow())?),
+        Object::Struct(_, fields) => {
+            let fields = fields
+                .iter()
+                .map(|(name, value)| {
+                    Ok(format!(
+                        "{}:{}",
+                        quote(name),
+                        stringify_nested(value, depth + 1)?
+                    ))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            format!("{{{}}}", fields.join(","))
+        }
+        Object::Null => "null".to_string(),
+        object => {
+            return Err(StringifyError::NotSerializable(
+                object.type_name().to_string(),
+            ))
+        }
+    })
+}
+
+fn quote(string: &str) -> String {
+    let mut quoted = String::from('"');
+
+    for char in string.chars() {
+        match char {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            char if char.is_control() => quoted.push_str(&format!("\\u{:04x}", char as u32)),
+            char => quoted.push(char),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+struct JsonParser<'a> {
+    chars: Peekable<Chars<'a>>,
+    depth: usize,
+}
+
+impl JsonParser<'_> {
+    fn whitespace(&mut self) {
+        while self
+            .chars
+            .next_if(|char| matches!(char, ' ' | '\n' | '\r' | '\t'))
+            .is_some()
+        {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.chars.next() {
+            Some(char) if char == expected => Ok(()),
+            Some(char) => Err(format!("expected `{expected}`, got `{char}`")),
+            None => Err(format!("expected `{expected}`, got the end of the text")),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Object) -> Result<Object, String> {
+        for expected in keyword.chars() {
+            self.expect(expected)?;
+        }
+
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Object, String> {
+        self.whitespace();
+
+        match self.chars.peek() {
+            Some('{') => self.nested(Self::object),
+            Some('[') => self.nested(Self::array),
+            Some('"') => self.string().map(Object::String),
+            Some('t') => self.keyword("true", Object::Boolean(true)),
+            Some('f') => self.keyword("false", Object::Boolean(false)),
+            Some('n') => self.keyword("null", Object::Null),
+            Some('-' | '0'..='9') => self.number(),
+            Some(char) => Err(format!("unexpected `{char}`")),
+            None => Err("expected a value, got the end of the text".to_string()),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Object, String>) -> Result<Object, String> {
+        if self.depth >= MAX_DEPTH {
+            return Err(format!("nested deeper than {MAX_DEPTH} levels"));
+        }
+
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+
+        value
+    }
+
+    /// Parses the elements of `[...]` (or the fields of `{...}`) with `element`, which also consumes the separators.
+    fn elements(
+        &mut self,
+        closing: char,
+        mut element: impl FnMut(&mut Self) -> Result<(), String>,
+    ) -> Result<(), String> {
+        self.whitespace();
+
+        if self.chars.next_if_eq(&closing).is_some() {
+            return Ok(());
+        }
+
+        loop {
+            element(self)?;
+            self.whitespace();
+
+            match self.chars.next() {
+                Some(',') => continue,
+                Some(char) if char == closing => return Ok(()),
+                Some(char) => return Err(format!("expected `,` or `{closing}`, got `{char}`")),
+                None => return Err(format!("expected `{closing}`, got the end of the text")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Object, String> {
+        self.expect('[')?;
+
+        let mut elements = Vec::new();
+        self.elements(']', |parser| {
+            elements.push(parser.value()?);
+            Ok(())
+        })?;
+
+        Ok(Object::Array(elements))
+    }
+
+    fn object(&mut self) -> Result<Object, String> {
+        self.expect('{')?;
+
+        let mut fields: Vec<(String, Object)> = Vec::new();
+        self.elements('}', |parser| {
+            parser.whitespace();
+            let name = parser.string()?;
+            parser.whitespace();
+            parser.expect(':')?;
+            let value = parser.value()?;
+
+            // Like most parsers, a repeated key keeps the last value.
+            match fields.iter_mut().find(|(field, _)| *field == name) {
+                Some((_, field)) => *field = value,
+                None => fields.push((name, value)),
+            }
+
+            Ok(())
+        })?;
+
+        Ok(Object::Struct(OBJECT.to_string(), fields))
+    }
+
+    fn number(&mut self) -> Result<Object, String> {
+        let mut number = String::new();
+
+        while let Some(char) = self
+            .chars
+            .next_if(|char| matches!(char, '-' | '+' | '.' | 'e' | 'E' | '0'..='9'))
+        {
+            number.push(char);
+        }
+
+        // `parse` also accepts forms JSON doesn't, such as `1.` or `.5`.
+        let digits = number.trim_start_matches('-');
+        let starts_with_digit =
+            |digits: &str| digits.starts_with(|char: char| char.is_ascii_digit());
+        let leading_zero = digits.starts_with('0') && starts_with_digit(&digits[1..]);
+        let empty_fraction =
+            digits.ends_with('.') || digits.contains(".e") || digits.contains(".E");
+        let is_valid = starts_with_digit(digits) && !leading_zero && !empty_fraction;
+
+        match number.parse() {
+            Ok(value) if is_valid => Ok(Object::Number(value)),
+            _ => Err(format!("invalid number `{number}`")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+
+        let mut string = String::new();
+
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => match self.chars.next() {
+                    Some('"') => string.push('"'),
+                    Some('\\') => string.push('\\'),
+                    Some('/') => string.push('/'),
+                    Some('b') => string.push('\u{8}'),
+                    Some('f') => string.push('\u{c}'),
+                    Some('n') => string.push('\n'),
+                    Some('r') => string.push('\r'),
+                    Some('t') => string.push('\t'),
+                    Some('u') => string.push(self.unicode_escape()?),
+                    Some(char) => return Err(format!("invalid escape `\\{char}`")),
+                    None => break,
+                },
+                Some(char) if char.is_control() => {
+                    return Err(format!(
+                        "unescaped control character `{char:?}` in a string"
+                    ))
+                }
+                Some(char) => string.push(char),
+                None => break,
+            }
+        }
+
+        Err("unterminated string".to_string())
+    }
+
+    /// Parses the digits of a `\uXXXX` escape, combining a surrogate pair into one character.
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex()?;
+
+        let code = match high {
+            0xd800..=0xdbff => {
+                self.expect('\\')?;
+                self.expect('u')?;
+
+                match self.hex()? {
+                    low @ 0xdc00..=0xdfff => 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00),
+                    low => return Err(format!("invalid low surrogate `\\u{low:04x}`")),
+                }
+            }
+            code => code,
+        };
+
+        char::from_u32(code).ok_or_else(|| format!("invalid code point `\\u{code:04x}`"))
+    }
+
+    fn hex(&mut self) -> Result<u32, String> {
+        let digits = (0..4)
+            .map(|_| self.chars.next().filter(char::is_ascii_hexdigit))
+            .collect::<Option<String>>()
+            .ok_or_else(|| "expected 4 hexadecimal digits after `\\u`".to_string())?;
+
+        Ok(u32::from_str_radix(&digits, 16).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |source| parse(source).unwrap_err();
+
+        assert_eq!(error("[1,]"), "unexpected `]`");
+        assert_eq!(error("01"), "invalid number `01`");
+        assert_eq!(error("1."), "invalid number `1.`");
+        assert_eq!(error("{\"a\" 1}"), "expected `:`, got `1`");
+        assert_eq!(error("\"a"), "unterminated string");
+        assert_eq!(error("[] []"), "unexpected `[` after the value");
+        assert_eq!(
+            error(&"[".repeat(MAX_DEPTH + 1)),
+            format!("nested deeper than {MAX_DEPTH} levels")
+        );
+    }
+
+    #[test]
+    fn test_strings() {
+        assert_eq!(
+            parse(r#""a\"\\\né😀""#),
+            Ok(Object::String("a\"\\\né😀".to_string()))
+        );
+        assert_eq!(
+            stringify(&Object::String("a\"\\\n\u{1}".to_string())),
+            Ok(r#""a\"\\\n\u0001""#.to_string())
+        );
+        assert_eq!(
+            stringify(&Object::Number(f64::NAN)),
+            Err(StringifyError::NotSerializable("NaN".to_string()))
+        );
+    }
+}
@@ -1,12 +1,24 @@
+pub mod builtin;
 pub mod enviroment;
+pub mod json;
 pub mod object;
 
 use enviroment::Environment;
 use object::Object;
 use parser::ast::{
-    AssignmentExpression, Expression, InfixExpression, InfixOperator, LetStatement, Literal,
-    Position, PrefixExpression, PrefixOperator, Program, Statement,
+    AssignmentExpression, BlockExpression, CallExpression, CastExpression, DataType, DataTypeKind,
+    Expression, FieldKey, Identifier, IfExpression, IndexExpression, InfixExpression,
+    InfixOperator, LetStatement, Literal, MatchArm, MatchExpression, MatchPattern, ParameterKind,
+    Pattern, Position, PrefixExpression, PrefixOperator, Program, RangeExpression,
+    ReturnExpressionStatement, SequenceExpression, Statement, TryExpression, WhileStatement,
 };
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// How many statements and loop iterations run between two checks of the timeout.
+const TIMEOUT_CHECK_INTERVAL: usize = 1024;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct EvaluateError {
@@ -27,6 +39,35 @@ pub enum EvaluateErrorKind {
     IdentifierNotDefined(String),
     InvalidOperator1(String, String),
     InvalidOperator2(String, String, String),
+    TypeMismatch(String, String),
+    NotCallable(String),
+    ArgumentCountMismatch(String, String),
+    IndexOutOfBounds(String, String),
+    FieldNotDefined(String, String),
+    InvalidCast(String, String),
+    NegativeCount(String),
+    NotImplemented,
+    UserError(String),
+    DuplicateField(String),
+    OutputLimitExceeded(String),
+    /// Raised by `exit(code)` to stop the program. It is not caught by `try`.
+    Exit(i32),
+    InvalidSize(String),
+    InvalidCharacter(String),
+    PatternMismatch(String, String),
+    InvalidJson(String),
+    NotSerializable(String),
+    InvalidIndex(String),
+    EmptyArray(String),
+    ValueTooDeep(String),
+    StringTooLong(String),
+    NoMatchingArm(String),
+    InvalidExitCode(String),
+    UnbalancedBrace(String),
+    /// Raised once `evaluate_with_timeout` runs out of time. Like `Exit`, it is not caught by `try`.
+    Timeout(String),
+    /// An AST the evaluator doesn't expect, which the parser and analyzer shouldn't let through.
+    InternalError(String),
 }
 
 pub type EvaluateResult<T> = Result<T, EvaluateError>;
@@ -35,6 +76,56 @@ pub type EvaluateResult<T> = Result<T, EvaluateError>;
 pub struct Evaluator {
     pub program: Program,
     pub environment: Environment,
+    pub return_value: Option<Object>,
+    pub clock: Box<dyn builtin::Clock>,
+    /// Rejects adding a number to a string, which is otherwise converted to a string.
+    pub strict: bool,
+    pub output: Box<dyn builtin::Output>,
+    /// The most bytes `print()` and `println()` may write in total. `None` means no limit.
+    pub max_output: Option<usize>,
+    /// The tolerance within which `==` and `!=` consider two numbers equal. `None` compares them exactly.
+    pub epsilon: Option<f64>,
+    /// Checks the evaluator's own invariants before every statement and panics if one is broken.
+    /// It is meant for finding bugs in the evaluator, and is ignored in release builds.
+    pub check_invariants: bool,
+    breakpoints: Vec<Breakpoint>,
+    /// The instant at which `evaluate_with_timeout` stops, and its timeout.
+    deadline: Option<(Instant, Duration)>,
+    steps: usize,
+    written: usize,
+    loop_signal: Option<LoopSignal>,
+}
+
+/// Called with the position of a statement and the environment it runs in, before the statement runs.
+/// The evaluation resumes once it returns.
+pub type BreakpointCallback = Box<dyn FnMut(Position, &Environment)>;
+
+/// A callback for the statements starting on a line, set by `Evaluator::set_breakpoint`.
+struct Breakpoint {
+    line: usize,
+    callback: BreakpointCallback,
+}
+
+impl std::fmt::Debug for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Breakpoint")
+            .field("line", &self.line)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Set by `break` and `continue` (with their label, if any) until the loop they leave handles it.
+#[derive(Debug, Clone, PartialEq)]
+enum LoopSignal {
+    Break(Option<String>),
+    Continue(Option<String>),
+}
+
+/// The bindings of an evaluator's environment at some point, taken by `Evaluator::snapshot`.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    environment: Environment,
+    bindings: HashMap<String, Object>,
 }
 
 impl Evaluator {
@@ -42,25 +133,200 @@ impl Evaluator {
         Self {
             program,
             environment: Environment::new(None),
+            return_value: None,
+            clock: Box::new(builtin::SystemClock),
+            strict: false,
+            output: Box::new(builtin::Stdout),
+            max_output: None,
+            epsilon: None,
+            check_invariants: false,
+            breakpoints: Vec::new(),
+            deadline: None,
+            steps: 0,
+            written: 0,
+            loop_signal: None,
         }
     }
 
-    pub fn evaluate(&mut self) -> EvaluateResult<()> {
-        for statement in &self.program.clone() {
-            self.eval_statement(statement)?;
+    /// Pauses before every statement on the given line, including the statements of function bodies each time
+    /// they're called, and calls `callback` with the environment the statement runs in.
+    pub fn set_breakpoint<F>(&mut self, line: usize, callback: F)
+    where
+        F: FnMut(Position, &Environment) + 'static,
+    {
+        self.breakpoints.push(Breakpoint {
+            line,
+            callback: Box::new(callback),
+        });
+    }
+
+    /// Runs the program and returns its value: the value of the first top-level `return`,
+    /// or of the last statement if it is an expression (with or without a semicolon), or `Void` otherwise.
+    pub fn evaluate(&mut self) -> EvaluateResult<Object> {
+        let mut program = self.program.clone();
+
+        // A trailing expression is the value of the program, like the last expression of a block.
+        // It runs as a statement, so that breakpoints and the timeout apply to it too.
+        if let Some(Statement::ExpressionStatement(last)) = program.last_mut() {
+            let last = ReturnExpressionStatement {
+                value: last.expression.clone(),
+                position: last.position,
+            };
+            *program.last_mut().unwrap() = Statement::ReturnExpressionStatement(last);
+        }
+
+        let value = self.eval_statements(&program)?;
+
+        Ok(self.return_value.take().unwrap_or(value))
+    }
+
+    /// Runs the program like `evaluate`, but fails with `Timeout` once it has run for longer than `timeout`.
+    /// The time is only checked every `TIMEOUT_CHECK_INTERVAL` statements.
+    pub fn evaluate_with_timeout(&mut self, timeout: Duration) -> EvaluateResult<Object> {
+        self.deadline = Some((Instant::now() + timeout, timeout));
+        let result = self.evaluate();
+        self.deadline = None;
+
+        result
+    }
+
+    fn check_timeout(&mut self, position: Position) -> EvaluateResult<()> {
+        let Some((deadline, timeout)) = self.deadline else {
+            return Ok(());
+        };
+
+        self.steps += 1;
+
+        if self.steps.is_multiple_of(TIMEOUT_CHECK_INTERVAL) && Instant::now() >= deadline {
+            return Err(EvaluateError::new(
+                EvaluateErrorKind::Timeout(timeout.as_millis().to_string()),
+                position,
+            ));
         }
 
         Ok(())
     }
 
+    /// Forgets every binding and the output written so far, as if the evaluator was just created.
+    pub fn reset(&mut self) {
+        self.environment = Environment::new(None);
+        self.return_value = None;
+        self.loop_signal = None;
+        self.written = 0;
+    }
+
+    /// Copies the bindings of the current scope, e.g. before a REPL evaluates a line,
+    /// so `restore` can undo the line if it fails halfway.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            environment: self.environment.clone(),
+            bindings: self.environment.store.borrow().clone(),
+        }
+    }
+
+    /// Puts back the scope and its bindings from the snapshot. Closures that captured the scope see the restored
+    /// bindings, but buffers are shared with the snapshot, so pushes to them are not undone.
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        *snapshot.environment.store.borrow_mut() = snapshot.bindings;
+
+        self.environment = snapshot.environment;
+        self.return_value = None;
+        self.loop_signal = None;
+    }
+
+    /// Evaluates the statements until the first `return`, `break` or `continue`.
+    fn eval_statements(&mut self, statements: &[Statement]) -> EvaluateResult<Object> {
+        for statement in statements {
+            if cfg!(debug_assertions) && self.check_invariants {
+                self.assert_invariants();
+            }
+
+            let position = statement.position();
+            self.check_timeout(position)?;
+
+            for breakpoint in &mut self.breakpoints {
+                if breakpoint.line == position.0 {
+                    (breakpoint.callback)(position, &self.environment);
+                }
+            }
+
+            if let Statement::ReturnExpressionStatement(statement) = statement {
+                return self.eval_expression(&statement.value, statement.position);
+            }
+
+            self.eval_statement(statement)?;
+
+            if let Some(value) = &self.return_value {
+                return Ok(value.clone());
+            }
+
+            if self.loop_signal.is_some() {
+                return Ok(Object::Void);
+            }
+        }
+
+        Ok(Object::Void)
+    }
+
+    /// Checks that every scope has its own bindings, and that no `return`, `break` or `continue` is still unwinding.
+    fn assert_invariants(&self) {
+        if let Some((scope, ancestor)) = self.environment.repeated_scope() {
+            panic!(
+                "invariant violated: the scope at depth {scope} shares its bindings with its ancestor at depth {ancestor}"
+            );
+        }
+
+        assert!(
+            self.return_value.is_none(),
+            "invariant violated: a statement started while a `return` is unwinding"
+        );
+        assert!(
+            self.loop_signal.is_none(),
+            "invariant violated: a statement started while a `break` or `continue` is unwinding"
+        );
+    }
+
     fn eval_statement(&mut self, statement: &Statement) -> EvaluateResult<()> {
         match statement {
             Statement::LetStatement(statement) => self.eval_let_statement(statement),
-            Statement::ReturnStatement(_) => todo!(),
-            Statement::ReturnExpressionStatement(_) => todo!(),
-            Statement::TypeStatement(_) => todo!(),
-            Statement::DeclareStatement(_) => todo!(),
-            Statement::StructStatement(_) => todo!(),
+            Statement::ReturnStatement(statement) => {
+                let value = match &statement.value {
+                    Some(value) => self.eval_expression(value, statement.position)?,
+                    None => Object::Void,
+                };
+                self.return_value = Some(value);
+
+                Ok(())
+            }
+            Statement::ReturnExpressionStatement(statement) => {
+                self.eval_expression(&statement.value, statement.position)?;
+
+                Ok(())
+            }
+            Statement::ThrowStatement(statement) => {
+                let value = self.eval_expression(&statement.value, statement.position)?;
+
+                Err(EvaluateError::new(
+                    EvaluateErrorKind::UserError(value.to_string()),
+                    statement.position,
+                ))
+            }
+            Statement::WhileStatement(statement) => self.eval_while_statement(statement),
+            Statement::BreakStatement(statement) => {
+                let label = statement.label.as_ref().map(|label| label.value.clone());
+                self.loop_signal = Some(LoopSignal::Break(label));
+
+                Ok(())
+            }
+            Statement::ContinueStatement(statement) => {
+                let label = statement.label.as_ref().map(|label| label.value.clone());
+                self.loop_signal = Some(LoopSignal::Continue(label));
+
+                Ok(())
+            }
+            Statement::TypeStatement(_)
+            | Statement::DeclareStatement(_)
+            | Statement::StructStatement(_) => Ok(()),
             Statement::ExpressionStatement(statement) => {
                 self.eval_expression(&statement.expression, statement.position)?;
 
@@ -69,6 +335,57 @@ impl Evaluator {
         }
     }
 
+    /// Compares numbers within `epsilon` if it is set, and every other value exactly.
+    fn equals(&self, left: &Object, right: &Object) -> bool {
+        match (left, right, self.epsilon) {
+            (Object::Number(left), Object::Number(right), Some(epsilon)) => {
+                left == right || (left - right).abs() <= epsilon
+            }
+            _ => left == right,
+        }
+    }
+
+    /// A `break` or `continue` with another label stops this loop and is left for the enclosing loops.
+    fn eval_while_statement(&mut self, statement: &WhileStatement) -> EvaluateResult<()> {
+        let label = statement.label.as_ref().map(|label| label.value.clone());
+        let targets = |target: &Option<String>| target.is_none() || *target == label;
+
+        loop {
+            // An empty body has no statements to check the timeout.
+            self.check_timeout(statement.position)?;
+
+            match self.eval_expression(&statement.condition, statement.position)? {
+                Object::Boolean(true) => {}
+                Object::Boolean(false) => return Ok(()),
+                condition => {
+                    return Err(EvaluateError::new(
+                        EvaluateErrorKind::TypeMismatch(
+                            condition.type_name().to_string(),
+                            "boolean".to_string(),
+                        ),
+                        statement.position,
+                    ))
+                }
+            }
+
+            self.eval_block_expression(&statement.body)?;
+
+            if self.return_value.is_some() {
+                return Ok(());
+            }
+
+            match self.loop_signal.take() {
+                Some(LoopSignal::Break(target)) if targets(&target) => return Ok(()),
+                Some(LoopSignal::Continue(target)) if targets(&target) => {}
+                Some(signal) => {
+                    self.loop_signal = Some(signal);
+                    return Ok(());
+                }
+                None => {}
+            }
+        }
+    }
+
     fn eval_let_statement(&mut self, statement: &LetStatement) -> EvaluateResult<()> {
         let identifier = statement.identifier.value.clone();
         let value = self.eval_expression(&statement.value, statement.position)?;
@@ -92,13 +409,43 @@ impl Evaluator {
             Expression::AssignmentExpression(expression) => {
                 self.eval_assignment_expression(expression)
             }
-            Expression::BlockExpression(_) => todo!(),
+            Expression::BlockExpression(expression) => self.eval_block_expression(expression),
+            Expression::SequenceExpression(expression) => self.eval_sequence_expression(expression),
             Expression::PrefixExpression(expression) => self.eval_prefix_expression(expression),
             Expression::InfixExpression(expression) => self.eval_infix_expression(expression),
-            Expression::IfExpression(_) => todo!(),
-            Expression::CallExpression(_) => todo!(),
-            Expression::TypeofExpression(_) => todo!(),
-            Expression::IndexExpression(_) => todo!(),
+            Expression::IfExpression(expression) => self.eval_if_expression(expression),
+            Expression::TryExpression(expression) => self.eval_try_expression(expression),
+            Expression::MatchExpression(expression) => self.eval_match_expression(expression),
+            Expression::CallExpression(expression) => self.eval_call_expression(expression),
+            Expression::TypeofExpression(expression) => {
+                let value = self.eval_expression(&expression.expression, expression.position)?;
+
+                Ok(Object::String(match value.type_of() {
+                    Some(kind) => kind.to_string(),
+                    None => value.type_name().to_string(),
+                }))
+            }
+            Expression::CastExpression(expression) => self.eval_cast_expression(expression),
+            Expression::IndexExpression(expression) => self.eval_index_expression(expression),
+            Expression::RangeExpression(range) => Err(EvaluateError::new(
+                EvaluateErrorKind::InternalError(
+                    "a range can only be used as an index".to_string(),
+                ),
+                range.position,
+            )),
+            Expression::Error(position) => Err(EvaluateError::new(
+                EvaluateErrorKind::InternalError(
+                    "a malformed expression can't be evaluated".to_string(),
+                ),
+                *position,
+            )),
+            Expression::InterpolationExpression(expression) => Ok(Object::String(
+                expression
+                    .parts
+                    .iter()
+                    .map(|part| Ok(self.eval_expression(part, expression.position)?.to_string()))
+                    .collect::<EvaluateResult<String>>()?,
+            )),
             Expression::Literal(literal) => self.eval_literal(literal, position),
             Expression::Debug(expression, position) => {
                 let value = self.eval_expression(expression, *position)?;
@@ -127,6 +474,440 @@ impl Evaluator {
         Ok(value)
     }
 
+    /// The block is evaluated in a new scope, which is dropped when the block ends.
+    fn eval_block_expression(&mut self, expression: &BlockExpression) -> EvaluateResult<Object> {
+        let parent = self.environment.clone();
+        self.environment = Environment::new(Some(parent));
+
+        let result = self.eval_statements(&expression.statements);
+
+        if let Some(parent) = self.environment.parent.take() {
+            self.environment = *parent;
+        }
+
+        result
+    }
+
+    /// Evaluates the expressions in order and returns the last value.
+    fn eval_sequence_expression(
+        &mut self,
+        sequence: &SequenceExpression,
+    ) -> EvaluateResult<Object> {
+        let mut value = Object::Void;
+
+        for expression in &sequence.expressions {
+            value = self.eval_expression(expression, sequence.position)?;
+
+            if let Some(value) = &self.return_value {
+                return Ok(value.clone());
+            }
+
+            if self.loop_signal.is_some() {
+                return Ok(Object::Void);
+            }
+        }
+
+        Ok(value)
+    }
+
+    /// Evaluates the handler with the error message bound if the body fails. Errors in the handler propagate.
+    fn eval_try_expression(&mut self, expression: &TryExpression) -> EvaluateResult<Object> {
+        let error = match self.eval_block_expression(&expression.body) {
+            Ok(value) => return Ok(value),
+            Err(error)
+                if matches!(
+                    error.kind,
+                    EvaluateErrorKind::Exit(_) | EvaluateErrorKind::Timeout(_)
+                ) =>
+            {
+                return Err(error)
+            }
+            Err(error) => error,
+        };
+
+        let parent = self.environment.clone();
+        self.environment = Environment::new(Some(parent));
+        self.environment.insert(
+            &expression.identifier.value,
+            Object::String(crate::Error::from(error).error.message),
+        );
+
+        let result = self.eval_statements(&expression.handler.statements);
+
+        if let Some(parent) = self.environment.parent.take() {
+            self.environment = *parent;
+        }
+
+        result
+    }
+
+    /// Evaluates the body of the first arm whose pattern matches the value and whose guard is `true`.
+    fn eval_match_expression(&mut self, expression: &MatchExpression) -> EvaluateResult<Object> {
+        let value = self.eval_expression(&expression.value, expression.position)?;
+
+        for arm in &expression.arms {
+            if let MatchPattern::Literal(literal) = &arm.pattern {
+                let pattern =
+                    self.eval_expression(&Expression::Literal(literal.clone()), arm.position)?;
+
+                if !self.equals(&value, &pattern) {
+                    continue;
+                }
+            }
+
+            let parent = self.environment.clone();
+            self.environment = Environment::new(Some(parent));
+
+            if let MatchPattern::Binding(identifier) = &arm.pattern {
+                self.environment.insert(&identifier.value, value.clone());
+            }
+
+            let result = self.eval_match_arm(arm);
+
+            if let Some(parent) = self.environment.parent.take() {
+                self.environment = *parent;
+            }
+
+            if let Some(result) = result.transpose() {
+                return result;
+            }
+        }
+
+        Err(EvaluateError::new(
+            EvaluateErrorKind::NoMatchingArm(value.to_string()),
+            expression.position,
+        ))
+    }
+
+    /// Evaluates the body of the arm if its guard is `true`, or returns `None` if it is `false`.
+    fn eval_match_arm(&mut self, arm: &MatchArm) -> EvaluateResult<Option<Object>> {
+        if let Some(guard) = &arm.guard {
+            match self.eval_expression(guard, arm.position)? {
+                Object::Boolean(true) => {}
+                Object::Boolean(false) => return Ok(None),
+                guard => {
+                    return Err(EvaluateError::new(
+                        EvaluateErrorKind::TypeMismatch(
+                            guard.type_name().to_string(),
+                            "boolean".to_string(),
+                        ),
+                        arm.position,
+                    ))
+                }
+            }
+        }
+
+        self.eval_block_expression(&arm.body).map(Some)
+    }
+
+    /// The function is evaluated first, then the arguments from left to right.
+    fn eval_call_expression(&mut self, expression: &CallExpression) -> EvaluateResult<Object> {
+        let function = self.eval_expression(&expression.function, expression.position)?;
+        let arguments = expression
+            .arguments
+            .iter()
+            .map(|argument| self.eval_expression(argument, expression.position))
+            .collect::<EvaluateResult<Vec<_>>>()?;
+
+        self.call_function(function, arguments, expression.position)
+    }
+
+    /// Calls a function (or builtin) object with already evaluated arguments.
+    pub fn call_function(
+        &mut self,
+        function: Object,
+        arguments: Vec<Object>,
+        position: Position,
+    ) -> EvaluateResult<Object> {
+        let (parameters, body, closure) = match function {
+            Object::Function(parameters, _, body, closure) => (parameters, body, closure),
+            Object::Builtin(name) => {
+                return builtin::lookup(name)
+                    .ok_or_else(|| {
+                        EvaluateError::new(
+                            EvaluateErrorKind::IdentifierNotDefined(name.to_string()),
+                            position,
+                        )
+                    })
+                    .and_then(|builtin| builtin(self, arguments, position))
+            }
+            Object::Memoized(function, cache) => {
+                return builtin::call_memoized(self, *function, cache, arguments, position)
+            }
+            function => {
+                return Err(EvaluateError::new(
+                    EvaluateErrorKind::NotCallable(function.to_string()),
+                    position,
+                ))
+            }
+        };
+
+        let is_spread = parameters
+            .last()
+            .is_some_and(|parameter| parameter.kind == ParameterKind::Spread);
+
+        if (!is_spread && arguments.len() != parameters.len())
+            || (is_spread && arguments.len() < parameters.len() - 1)
+        {
+            return Err(EvaluateError::new(
+                EvaluateErrorKind::ArgumentCountMismatch(
+                    parameters.len().to_string(),
+                    arguments.len().to_string(),
+                ),
+                position,
+            ));
+        }
+
+        let mut environment = Environment::new(Some(closure));
+        let mut arguments = arguments.into_iter();
+
+        for parameter in &parameters {
+            let value = match parameter.kind {
+                ParameterKind::Normal => arguments.next().unwrap_or(Object::Void),
+                ParameterKind::Spread => Object::Array(arguments.by_ref().collect()),
+            };
+
+            let bindings = match &parameter.pattern {
+                Some(pattern) => Self::pattern_bindings(pattern, value, position)?,
+                None => vec![(&parameter.identifier, value)],
+            };
+
+            for (identifier, value) in bindings {
+                environment
+                    .insert(&identifier.value, value)
+                    .ok_or_else(|| {
+                        EvaluateError::new(
+                            EvaluateErrorKind::IdentifierAlreadyDefined(identifier.value.clone()),
+                            parameter.position,
+                        )
+                    })?;
+            }
+        }
+
+        let environment = std::mem::replace(&mut self.environment, environment);
+        let result = self.eval_statements(&body.statements);
+        self.environment = environment;
+
+        let value = self.return_value.take();
+        result.map(|result| value.unwrap_or(result))
+    }
+
+    /// Destructures an argument with the pattern of its parameter, failing if the value doesn't have its shape.
+    fn pattern_bindings(
+        pattern: &Pattern,
+        value: Object,
+        position: Position,
+    ) -> EvaluateResult<Vec<(&Identifier, Object)>> {
+        match (pattern, value) {
+            (Pattern::Struct(identifiers), Object::Struct(name, fields)) => identifiers
+                .iter()
+                .map(|identifier| {
+                    fields
+                        .iter()
+                        .find(|(field, _)| *field == identifier.value)
+                        .map(|(_, value)| (identifier, value.clone()))
+                        .ok_or_else(|| {
+                            EvaluateError::new(
+                                EvaluateErrorKind::FieldNotDefined(
+                                    identifier.value.clone(),
+                                    name.clone(),
+                                ),
+                                position,
+                            )
+                        })
+                })
+                .collect(),
+            (Pattern::Array(identifiers), Object::Array(elements))
+                if identifiers.len() == elements.len() =>
+            {
+                Ok(identifiers.iter().zip(elements).collect())
+            }
+            (pattern, value) => {
+                let value = match value {
+                    Object::Array(elements) => format!("an array of {} elements", elements.len()),
+                    value => value.type_name().to_string(),
+                };
+
+                Err(EvaluateError::new(
+                    EvaluateErrorKind::PatternMismatch(pattern.to_string(), value),
+                    position,
+                ))
+            }
+        }
+    }
+
+    /// Without an alternative, the value is `null` if the consequence ends with an expression.
+    fn eval_if_expression(&mut self, expression: &IfExpression) -> EvaluateResult<Object> {
+        match self.eval_expression(&expression.condition, expression.position)? {
+            Object::Boolean(true) => self.eval_block_expression(&expression.consequence),
+            Object::Boolean(false) => match &expression.alternative {
+                Some(alternative) => self.eval_block_expression(alternative),
+                None => match expression.consequence.statements.last() {
+                    Some(Statement::ReturnExpressionStatement(_)) => Ok(Object::Null),
+                    _ => Ok(Object::Void),
+                },
+            },
+            condition => Err(EvaluateError::new(
+                EvaluateErrorKind::TypeMismatch(
+                    condition.type_name().to_string(),
+                    "boolean".to_string(),
+                ),
+                expression.position,
+            )),
+        }
+    }
+
+    /// Converts between numbers, strings and booleans.
+    fn eval_cast_expression(&mut self, expression: &CastExpression) -> EvaluateResult<Object> {
+        let value = self.eval_expression(&expression.expression, expression.position)?;
+
+        let result = match (&value, &expression.data_type.kind) {
+            (Object::Number(_), DataTypeKind::Number)
+            | (Object::String(_), DataTypeKind::String)
+            | (Object::Boolean(_), DataTypeKind::Boolean) => Some(value.clone()),
+            (Object::Number(_) | Object::Boolean(_), DataTypeKind::String) => {
+                Some(Object::String(value.to_string()))
+            }
+            (Object::Boolean(boolean), DataTypeKind::Number) => {
+                Some(Object::Number(if *boolean { 1.0 } else { 0.0 }))
+            }
+            (Object::Number(number), DataTypeKind::Boolean) => {
+                Some(Object::Boolean(*number != 0.0 && !number.is_nan()))
+            }
+            (Object::String(string), DataTypeKind::Number) => {
+                string.trim().parse().ok().map(Object::Number)
+            }
+            (Object::String(string), DataTypeKind::Boolean) => {
+                string.parse().ok().map(Object::Boolean)
+            }
+            _ => None,
+        };
+
+        result.ok_or_else(|| {
+            EvaluateError::new(
+                EvaluateErrorKind::InvalidCast(
+                    value.to_string(),
+                    expression.data_type.kind.to_string(),
+                ),
+                expression.position,
+            )
+        })
+    }
+
+    /// An index must be a non-negative integer small enough for a `usize`, rather than being truncated or saturated.
+    fn checked_index(index: f64, position: Position) -> EvaluateResult<usize> {
+        if index < 0.0 || index.fract() != 0.0 || index >= usize::MAX as f64 {
+            return Err(EvaluateError::new(
+                EvaluateErrorKind::InvalidIndex(index.to_string()),
+                position,
+            ));
+        }
+
+        Ok(index as usize)
+    }
+
+    fn eval_index_expression(&mut self, expression: &IndexExpression) -> EvaluateResult<Object> {
+        let left = self.eval_expression(&expression.left, expression.position)?;
+
+        if let Expression::RangeExpression(range) = expression.index.as_ref() {
+            return self.eval_slice(left, range);
+        }
+
+        let index = self.eval_expression(&expression.index, expression.position)?;
+
+        let (index, length) = match (&left, &index) {
+            (Object::Array(elements), Object::Number(index)) => {
+                let index = Self::checked_index(*index, expression.position)?;
+
+                if let Some(element) = elements.get(index) {
+                    return Ok(element.clone());
+                }
+
+                (index, elements.len())
+            }
+            (Object::String(string), Object::Number(index)) => {
+                let index = Self::checked_index(*index, expression.position)?;
+
+                if let Some(char) = string.chars().nth(index) {
+                    return Ok(Object::String(char.to_string()));
+                }
+
+                (index, string.chars().count())
+            }
+            (Object::Struct(name, fields), Object::String(field)) => {
+                return fields
+                    .iter()
+                    .find(|(key, _)| key == field)
+                    .map(|(_, value)| value.clone())
+                    .ok_or_else(|| {
+                        EvaluateError::new(
+                            EvaluateErrorKind::FieldNotDefined(field.clone(), name.clone()),
+                            expression.position,
+                        )
+                    })
+            }
+            _ => {
+                return Err(EvaluateError::new(
+                    EvaluateErrorKind::InvalidOperator2(
+                        "[]".to_string(),
+                        left.to_string(),
+                        index.to_string(),
+                    ),
+                    expression.position,
+                ))
+            }
+        };
+
+        Err(EvaluateError::new(
+            EvaluateErrorKind::IndexOutOfBounds(index.to_string(), length.to_string()),
+            expression.position,
+        ))
+    }
+
+    /// Evaluates `left[start..end]`. Bounds beyond the length are clamped.
+    fn eval_slice(&mut self, left: Object, range: &RangeExpression) -> EvaluateResult<Object> {
+        let length = match &left {
+            Object::Array(elements) => elements.len(),
+            Object::String(string) => string.chars().count(),
+            _ => {
+                return Err(EvaluateError::new(
+                    EvaluateErrorKind::InvalidOperator1("[..]".to_string(), left.to_string()),
+                    range.position,
+                ))
+            }
+        };
+
+        let mut bound = |expression: &Option<Box<Expression>>, default: usize| match expression {
+            Some(expression) => match self.eval_expression(expression, range.position)? {
+                Object::Number(number) => Ok((number.max(0.0) as usize).min(length)),
+                object => Err(EvaluateError::new(
+                    EvaluateErrorKind::TypeMismatch(
+                        object.type_name().to_string(),
+                        "number".to_string(),
+                    ),
+                    range.position,
+                )),
+            },
+            None => Ok(default),
+        };
+
+        let start = bound(&range.start, 0)?;
+        let end = bound(&range.end, length)?.max(start);
+
+        Ok(match left {
+            Object::Array(elements) => Object::Array(elements[start..end].to_vec()),
+            Object::String(string) => {
+                Object::String(string.chars().skip(start).take(end - start).collect())
+            }
+            _ => {
+                return Err(EvaluateError::new(
+                    EvaluateErrorKind::InvalidOperator1("[..]".to_string(), left.to_string()),
+                    range.position,
+                ))
+            }
+        })
+    }
+
     fn eval_prefix_expression(&mut self, expression: &PrefixExpression) -> EvaluateResult<Object> {
         let right = self.eval_expression(&expression.right, expression.position)?;
 
@@ -153,7 +934,59 @@ impl Evaluator {
         }
     }
 
+    /// `a?.b` evaluates to `null` when `a` is `null`, but only for that access.
+    fn eval_member_expression(&mut self, expression: &InfixExpression) -> EvaluateResult<Object> {
+        let left = self.eval_expression(&expression.left, expression.position)?;
+
+        if expression.operator == InfixOperator::OptionalDot && left == Object::Null {
+            return Ok(Object::Null);
+        }
+
+        let field = match expression.right.as_ref() {
+            Expression::Literal(Literal::Identifier(identifier)) => &identifier.value,
+            // The parser only accepts identifiers after `.`
+            _ => {
+                return Err(EvaluateError::new(
+                    EvaluateErrorKind::InternalError("expected a field name after `.`".to_string()),
+                    expression.position,
+                ))
+            }
+        };
+
+        match &left {
+            Object::Struct(_, fields) => fields
+                .iter()
+                .find(|(key, _)| key == field)
+                .map(|(_, value)| value.clone()),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            let name = match &left {
+                Object::Struct(name, _) => name.clone(),
+                object => object.type_name().to_string(),
+            };
+
+            EvaluateError::new(
+                EvaluateErrorKind::FieldNotDefined(field.clone(), name),
+                expression.position,
+            )
+        })
+    }
+
     fn eval_infix_expression(&mut self, expression: &InfixExpression) -> EvaluateResult<Object> {
+        match expression.operator {
+            InfixOperator::Dot | InfixOperator::OptionalDot => {
+                return self.eval_member_expression(expression)
+            }
+            InfixOperator::NullishCoalescing => {
+                return match self.eval_expression(&expression.left, expression.position)? {
+                    Object::Null => self.eval_expression(&expression.right, expression.position),
+                    left => Ok(left),
+                }
+            }
+            _ => {}
+        }
+
         let left = self.eval_expression(&expression.left, expression.position)?;
         let right = self.eval_expression(&expression.right, expression.position)?;
 
@@ -189,37 +1022,1180 @@ impl Evaluator {
         }
 
         match expression.operator {
-            InfixOperator::Dot => todo!(),
-            InfixOperator::Plus => operate!(Number, Number, +),
+            InfixOperator::Dot | InfixOperator::OptionalDot | InfixOperator::NullishCoalescing => {
+                Err(EvaluateError::new(
+                    EvaluateErrorKind::InternalError(format!(
+                        "`{}` is evaluated before its right operand",
+                        expression.operator
+                    )),
+                    expression.position,
+                ))
+            }
+            InfixOperator::Plus => match (&left, &right) {
+                (Object::String(left), Object::String(right)) => {
+                    Ok(Object::String(format!("{left}{right}")))
+                }
+                (Object::String(_), Object::Number(_)) | (Object::Number(_), Object::String(_))
+                    if !self.strict =>
+                {
+                    Ok(Object::String(format!("{left}{right}")))
+                }
+                _ => operate!(Number, Number, +),
+            },
             InfixOperator::Minus => operate!(Number, Number, -),
             InfixOperator::Asterisk => operate!(Number, Number, *),
+            InfixOperator::Pow => match (&left, &right) {
+                (Object::Number(left), Object::Number(right)) => {
+                    Ok(Object::Number(left.powf(*right)))
+                }
+                _ => Err(EvaluateError::new(
+                    EvaluateErrorKind::InvalidOperator2(
+                        expression.operator.to_string(),
+                        left.to_string(),
+                        right.to_string(),
+                    ),
+                    expression.position,
+                )),
+            },
             InfixOperator::Slash => operate!(Number, Number, /),
             InfixOperator::Percent => operate!(Number, Number, %),
-            InfixOperator::EQ => Ok(Object::Boolean(left == right)),
-            InfixOperator::NEQ => Ok(Object::Boolean(left != right)),
+            InfixOperator::EQ => Ok(Object::Boolean(self.equals(&left, &right))),
+            InfixOperator::NEQ => Ok(Object::Boolean(!self.equals(&left, &right))),
             InfixOperator::GT => operate!(Number, Boolean, >),
             InfixOperator::GTE => operate!(Number, Boolean, >=),
             InfixOperator::LT => operate!(Number, Boolean, <),
             InfixOperator::LTE => operate!(Number, Boolean, <=),
+            InfixOperator::In => match (&left, &right) {
+                (_, Object::Array(elements)) => Ok(Object::Boolean(
+                    elements.iter().any(|element| self.equals(&left, element)),
+                )),
+                (Object::String(key), Object::Struct(_, fields)) => Ok(Object::Boolean(
+                    fields.iter().any(|(field, _)| field == key),
+                )),
+                _ => Err(EvaluateError::new(
+                    EvaluateErrorKind::InvalidOperator2(
+                        expression.operator.to_string(),
+                        left.to_string(),
+                        right.to_string(),
+                    ),
+                    expression.position,
+                )),
+            },
         }
     }
 
     fn eval_literal(&mut self, literal: &Literal, _position: Position) -> EvaluateResult<Object> {
         match literal {
-            Literal::Identifier(identifier) => {
-                self.environment.get(&identifier.value).ok_or_else(|| {
+            Literal::Identifier(identifier) => self
+                .environment
+                .get(&identifier.value)
+                .or_else(|| builtin::get(&identifier.value))
+                .ok_or_else(|| {
                     EvaluateError::new(
                         EvaluateErrorKind::IdentifierNotDefined(identifier.value.clone()),
                         identifier.position,
                     )
-                })
-            }
+                }),
             Literal::NumberLiteral(literal) => Ok(Object::Number(literal.value)),
             Literal::StringLiteral(literal) => Ok(Object::String(literal.value.clone())),
             Literal::BooleanLiteral(literal) => Ok(Object::Boolean(literal.value)),
-            Literal::FunctionLiteral(_) => todo!(),
-            Literal::ArrayLiteral(_) => todo!(),
-            Literal::StructLiteral(_) => todo!(),
+            Literal::NullLiteral(_) => Ok(Object::Null),
+            Literal::FunctionLiteral(literal) => Ok(Object::Function(
+                literal.parameters.clone(),
+                literal
+                    .return_type
+                    .clone()
+                    .unwrap_or_else(|| DataType::new(DataTypeKind::Void, literal.position)),
+                literal.body.clone(),
+                self.environment.clone(),
+            )),
+            Literal::ArrayLiteral(literal) => Ok(Object::Array(
+                literal
+                    .elements
+                    .iter()
+                    .map(|element| self.eval_expression(element, literal.position))
+                    .collect::<EvaluateResult<Vec<_>>>()?,
+            )),
+            Literal::StructLiteral(literal) => {
+                let mut fields: Vec<(String, Object)> = Vec::new();
+
+                for (key, value) in &literal.fields {
+                    let (name, position) = match key {
+                        FieldKey::Named(identifier) => {
+                            (identifier.value.clone(), identifier.position)
+                        }
+                        FieldKey::Computed(key, position) => {
+                            match self.eval_expression(key, *position)? {
+                                Object::String(name) => (name, *position),
+                                object => {
+                                    return Err(EvaluateError::new(
+                                        EvaluateErrorKind::TypeMismatch(
+                                            object.type_name().to_string(),
+                                            "string".to_string(),
+                                        ),
+                                        *position,
+                                    ))
+                                }
+                            }
+                        }
+                    };
+
+                    if fields.iter().any(|(field, _)| *field == name) {
+                        return Err(EvaluateError::new(
+                            EvaluateErrorKind::DuplicateField(name),
+                            position,
+                        ));
+                    }
+
+                    fields.push((name, self.eval_expression(value, position)?));
+                }
+
+                Ok(Object::Struct(literal.identifier.value.clone(), fields))
+            }
         }
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use parser::{
+        ast::{ExpressionStatement, NullLiteral},
+        Parser,
+    };
+    use std::{cell::RefCell, rc::Rc};
+
+    /// Parses and evaluates the source, returning the evaluator for inspecting its environment.
+    pub fn evaluate(source: &str) -> EvaluateResult<Evaluator> {
+        let program = Parser::from(source).parse_program().unwrap();
+
+        let mut evaluator = Evaluator::new(program);
+        evaluator.evaluate().map(|_| evaluator)
+    }
+
+    #[test]
+    fn test_in_array() {
+        let evaluator = evaluate(
+            r#"
+            let x = 2 in [1, 2, 3];
+            let y = [1] in [[1], [2]];
+            let z = 4 in [1, 2, 3];
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(evaluator.environment.get("x"), Some(Object::Boolean(true)));
+        assert_eq!(evaluator.environment.get("y"), Some(Object::Boolean(true)));
+        assert_eq!(evaluator.environment.get("z"), Some(Object::Boolean(false)));
+    }
+
+    #[test]
+    fn test_in_struct() {
+        let evaluator = evaluate(
+            r#"
+            let point = struct Point { x: 1, y: 2 };
+            let x = "x" in point;
+            let z = "z" in point;
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(evaluator.environment.get("x"), Some(Object::Boolean(true)));
+        assert_eq!(evaluator.environment.get("z"), Some(Object::Boolean(false)));
+    }
+
+    #[test]
+    fn test_in_not_collection() {
+        let error = evaluate("let x = 1 in 5;").unwrap_err();
+
+        assert_eq!(
+            error.kind,
+            EvaluateErrorKind::InvalidOperator2("in".to_string(), "1".to_string(), "5".to_string())
+        );
+    }
+
+    #[test]
+    fn test_slice_array() {
+        let evaluator = evaluate(
+            r#"
+            let array = [1, 2, 3, 4];
+            let a = array[1..3];
+            let b = array[..2];
+            let c = array[2..];
+            let d = array[..];
+            let e = array[2..10];
+            "#,
+        )
+        .unwrap();
+
+        let array = |elements: &[f64]| {
+            Some(Object::Array(
+                elements.iter().map(|x| Object::Number(*x)).collect(),
+            ))
+        };
+
+        assert_eq!(evaluator.environment.get("a"), array(&[2.0, 3.0]));
+        assert_eq!(evaluator.environment.get("b"), array(&[1.0, 2.0]));
+        assert_eq!(evaluator.environment.get("c"), array(&[3.0, 4.0]));
+        assert_eq!(evaluator.environment.get("d"), array(&[1.0, 2.0, 3.0, 4.0]));
+        assert_eq!(evaluator.environment.get("e"), array(&[3.0, 4.0]));
+    }
+
+    #[test]
+    fn test_slice_string() {
+        let evaluator = evaluate(
+            r#"
+            let text = "hello";
+            let a = text[1..3];
+            let b = text[..2];
+            let c = text[3..];
+            let d = text[..];
+            "#,
+        )
+        .unwrap();
+
+        let string = |string: &str| Some(Object::String(string.to_string()));
+
+        assert_eq!(evaluator.environment.get("a"), string("el"));
+        assert_eq!(evaluator.environment.get("b"), string("he"));
+        assert_eq!(evaluator.environment.get("c"), string("lo"));
+        assert_eq!(evaluator.environment.get("d"), string("hello"));
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let evaluator = evaluate(
+            r#"
+            let x = 1;
+            let y = "x = ${x}, ${"nested ${x + 1}"} \{x\}";
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("y"),
+            Some(Object::String("x = 1, nested 2 {x}".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_pow() {
+        let evaluator = evaluate(
+            r#"
+            let x = 2 ** 3 ** 2;
+            let y = -2 ** 2;
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(evaluator.environment.get("x"), Some(Object::Number(512.0)));
+        assert_eq!(evaluator.environment.get("y"), Some(Object::Number(-4.0)));
+    }
+
+    #[test]
+    fn test_percent() {
+        let evaluator = evaluate(
+            r#"
+            let x = 1 + 4 % 3;
+            let y = 2 * 5 % 3;
+            let z = 7 % 4 * 2;
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(evaluator.environment.get("x"), Some(Object::Number(2.0)));
+        assert_eq!(evaluator.environment.get("y"), Some(Object::Number(1.0)));
+        assert_eq!(evaluator.environment.get("z"), Some(Object::Number(6.0)));
+    }
+
+    #[test]
+    fn test_call_function() {
+        let evaluator = evaluate(
+            r#"
+            let add = fn(a: number, b: number) -> number { return a + b; };
+            let x = add(1, 2);
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(evaluator.environment.get("x"), Some(Object::Number(3.0)));
+    }
+
+    #[test]
+    fn test_member_access_chain() {
+        let evaluator = evaluate(
+            r#"
+            let a = struct A {
+                b: struct B {
+                    c: [struct C { d: 1 }, struct C { d: 2 }],
+                },
+            };
+            let x = a.b.c[1].d;
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(evaluator.environment.get("x"), Some(Object::Number(2.0)));
+    }
+
+    #[test]
+    fn test_member_access_not_defined() {
+        let error = evaluate(
+            r#"
+            let a = struct A { b: 1 };
+            let x = a.b.c;
+            "#,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.kind,
+            EvaluateErrorKind::FieldNotDefined("c".to_string(), "number".to_string())
+        );
+    }
+
+    #[test]
+    fn test_sequence_expression() {
+        let evaluator = evaluate(
+            r#"
+            mut calls = 0;
+            let count = fn() -> number { calls = calls + 1; calls };
+            let x = (count(), count(), count() * 10);
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(evaluator.environment.get("x"), Some(Object::Number(30.0)));
+        assert_eq!(
+            evaluator.environment.get("calls"),
+            Some(Object::Number(3.0))
+        );
+    }
+
+    #[test]
+    fn test_malformed_expressions() {
+        let malformed = |expression| {
+            Evaluator::new(vec![Statement::ExpressionStatement(ExpressionStatement {
+                expression,
+                position: Position::default(),
+            })])
+            .evaluate()
+            .unwrap_err()
+            .kind
+        };
+        let internal_error = |message: &str| EvaluateErrorKind::InternalError(message.to_string());
+
+        assert_eq!(
+            malformed(Expression::Error(Position::default())),
+            internal_error("a malformed expression can't be evaluated")
+        );
+        assert_eq!(
+            malformed(Expression::RangeExpression(RangeExpression {
+                start: None,
+                end: None,
+                position: Position::default(),
+            })),
+            internal_error("a range can only be used as an index")
+        );
+
+        let member = InfixExpression {
+            left: Box::new(Expression::Literal(Literal::NullLiteral(NullLiteral {
+                position: Position::default(),
+            }))),
+            operator: InfixOperator::Dot,
+            right: Box::new(Expression::Error(Position::default())),
+            position: Position::default(),
+        };
+
+        assert_eq!(
+            malformed(Expression::InfixExpression(member)),
+            internal_error("expected a field name after `.`")
+        );
+    }
+
+    #[test]
+    fn test_optional_chaining() {
+        let evaluator = evaluate(
+            r#"
+            let a = struct A { b: null };
+            let x = a.b?.c;
+            let y = a?.b;
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(evaluator.environment.get("x"), Some(Object::Null));
+        assert_eq!(evaluator.environment.get("y"), Some(Object::Null));
+
+        assert_eq!(
+            evaluate("let a = struct A { b: null }; let x = a.b.c;")
+                .unwrap_err()
+                .kind,
+            EvaluateErrorKind::FieldNotDefined("c".to_string(), "null".to_string())
+        );
+    }
+
+    #[test]
+    fn test_nullish_coalescing() {
+        let evaluator = evaluate(
+            r#"
+            let x = null ?? 5;
+            let y = 0 ?? 5;
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(evaluator.environment.get("x"), Some(Object::Number(5.0)));
+        assert_eq!(evaluator.environment.get("y"), Some(Object::Number(0.0)));
+    }
+
+    #[test]
+    fn test_closure_captures_by_reference() {
+        let evaluator = evaluate(
+            r#"
+            let counter = fn() -> fn() -> number {
+                mut count = 0;
+
+                fn() -> number {
+                    count = count + 1;
+                    count
+                }
+            };
+
+            let next = counter();
+            next();
+            next();
+            let x = next();
+            let y = counter()();
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(evaluator.environment.get("x"), Some(Object::Number(3.0)));
+        assert_eq!(evaluator.environment.get("y"), Some(Object::Number(1.0)));
+    }
+
+    #[test]
+    fn test_closure_observes_outer_mutation() {
+        let evaluator = evaluate(
+            r#"
+            mut x = 1;
+            let get = fn() -> number { x };
+            x = 2;
+            let y = get();
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(evaluator.environment.get("y"), Some(Object::Number(2.0)));
+    }
+
+    #[test]
+    fn test_bare_return() {
+        let evaluator = evaluate(
+            r#"
+            let f = fn() -> void {
+                return;
+            };
+            let x = f();
+            let y = println("");
+            "#,
+        )
+        .unwrap();
+
+        // Like a body without a `return` and the builtins without a result, a bare `return` has no value.
+        assert_eq!(evaluator.environment.get("x"), Some(Object::Void));
+        assert_eq!(evaluator.environment.get("y"), Some(Object::Void));
+    }
+
+    #[test]
+    fn test_empty_function() {
+        let evaluator = evaluate(
+            r#"
+            let f = fn() {};
+            let x = f();
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(evaluator.environment.get("x"), Some(Object::Void));
+
+        assert_eq!(
+            evaluate("let f = fn() {}; f(1);").unwrap_err().kind,
+            EvaluateErrorKind::ArgumentCountMismatch("0".to_string(), "1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_cast() {
+        let evaluator = evaluate(
+            r#"
+            let a = 1.5 as string;
+            let b = " 42 " as number;
+            let c = true as number;
+            let d = 0 as boolean;
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("a"),
+            Some(Object::String("1.5".to_string()))
+        );
+        assert_eq!(evaluator.environment.get("b"), Some(Object::Number(42.0)));
+        assert_eq!(evaluator.environment.get("c"), Some(Object::Number(1.0)));
+        assert_eq!(evaluator.environment.get("d"), Some(Object::Boolean(false)));
+    }
+
+    #[test]
+    fn test_invalid_cast() {
+        assert_eq!(
+            evaluate(r#"let x = "abc" as number;"#).unwrap_err().kind,
+            EvaluateErrorKind::InvalidCast("abc".to_string(), "Number".to_string())
+        );
+
+        assert_eq!(
+            evaluate("let x = struct A { b: 1 } as number;")
+                .unwrap_err()
+                .kind,
+            EvaluateErrorKind::InvalidCast("A".to_string(), "Number".to_string())
+        );
+    }
+
+    #[test]
+    fn test_program_value() {
+        let value =
+            |source| Evaluator::new(Parser::from(source).parse_program().unwrap()).evaluate();
+
+        assert_eq!(value("let x = 2; x * 3"), Ok(Object::Number(6.0)));
+        assert_eq!(value("return 1; 2"), Ok(Object::Number(1.0)));
+        assert_eq!(value("let x = 2; x * 3;"), Ok(Object::Number(6.0)));
+    }
+
+    #[test]
+    fn test_parameter_patterns() {
+        let source = |call| {
+            format!(
+                r#"
+                struct P {{ x: number, y: number }}
+                struct Q {{ x: number }}
+                let dist = fn({{x, y}}: P) -> number {{ x * x + y * y }};
+                let swap = fn([a, b]: number[]) -> number[] {{ [b, a] }};
+                let result = {call};
+                "#
+            )
+        };
+        let result = |call| {
+            evaluate(&source(call)).map(|evaluator| evaluator.environment.get("result").unwrap())
+        };
+        let error = |call| evaluate(&source(call)).unwrap_err().kind;
+
+        assert_eq!(
+            result("dist(struct P { x: 3, y: 4 })"),
+            Ok(Object::Number(25.0))
+        );
+        assert_eq!(
+            result("swap([1, 2])"),
+            Ok(Object::Array(vec![
+                Object::Number(2.0),
+                Object::Number(1.0)
+            ]))
+        );
+        assert_eq!(
+            error("swap([1, 2, 3])"),
+            EvaluateErrorKind::PatternMismatch(
+                "[a, b]".to_string(),
+                "an array of 3 elements".to_string()
+            )
+        );
+        assert_eq!(
+            error("dist(struct Q { x: 1 })"),
+            EvaluateErrorKind::FieldNotDefined("y".to_string(), "Q".to_string())
+        );
+        assert_eq!(
+            error("dist(1)"),
+            EvaluateErrorKind::PatternMismatch("{x, y}".to_string(), "number".to_string())
+        );
+    }
+
+    #[test]
+    fn test_const_function() {
+        let value =
+            |source| Evaluator::new(Parser::from(source).parse_program().unwrap()).evaluate();
+
+        assert_eq!(
+            value("let square = const fn(x: number) -> number { x * x }; let n = 3; square(n)"),
+            Ok(Object::Number(9.0))
+        );
+    }
+
+    #[test]
+    fn test_struct_index() {
+        let evaluator = evaluate(r#"let p = struct Point { x: 1 }; let x = p["x"];"#).unwrap();
+
+        assert_eq!(evaluator.environment.get("x"), Some(Object::Number(1.0)));
+        assert_eq!(
+            evaluate(r#"let p = struct Point { x: 1 }; let y = p["y"];"#)
+                .unwrap_err()
+                .kind,
+            EvaluateErrorKind::FieldNotDefined("y".to_string(), "Point".to_string())
+        );
+    }
+
+    #[test]
+    fn test_try_expression() {
+        let evaluator = evaluate(
+            r#"
+            let a = try { [1, 2][5] } catch (e) { e };
+            let b = try { [1, 2][1] } catch (e) { 0 };
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("a"),
+            Some(Object::String(
+                crate::INDEX_OUT_OF_BOUNDS
+                    .message
+                    .replace("{0}", "5")
+                    .replace("{1}", "2")
+            ))
+        );
+        assert_eq!(evaluator.environment.get("b"), Some(Object::Number(2.0)));
+        assert_eq!(
+            evaluate("let c = try { [1][3] } catch (e) { [1][4] };")
+                .unwrap_err()
+                .kind,
+            EvaluateErrorKind::IndexOutOfBounds("4".to_string(), "1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_match_expression() {
+        let evaluator = evaluate(
+            r#"
+            let sign = fn(n: number) -> string {
+                match n { 0 => "zero", m if m > 0 => "positive", _ => "negative" }
+            };
+            let a = sign(5);
+            let b = sign(-5);
+            let c = sign(0);
+            let d = match "x" { "y" => 1, "x" => 2, _ => 3 };
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("a"),
+            Some(Object::String("positive".to_string()))
+        );
+        assert_eq!(
+            evaluator.environment.get("b"),
+            Some(Object::String("negative".to_string()))
+        );
+        assert_eq!(
+            evaluator.environment.get("c"),
+            Some(Object::String("zero".to_string()))
+        );
+        assert_eq!(evaluator.environment.get("d"), Some(Object::Number(2.0)));
+        assert_eq!(evaluator.environment.get("m"), None);
+
+        assert_eq!(
+            evaluate("let x = match 1 { n if n > [1][3] => 1, _ => 2 };")
+                .unwrap_err()
+                .kind,
+            EvaluateErrorKind::IndexOutOfBounds("3".to_string(), "1".to_string())
+        );
+        assert_eq!(
+            evaluate("let x = match 2 { 1 => 1 };").unwrap_err().kind,
+            EvaluateErrorKind::NoMatchingArm("2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_timeout() {
+        let timeout = |source| {
+            let program = Parser::from(source).parse_program().unwrap();
+            Evaluator::new(program).evaluate_with_timeout(Duration::from_millis(20))
+        };
+
+        assert_eq!(
+            timeout("while true {}").unwrap_err().kind,
+            EvaluateErrorKind::Timeout("20".to_string())
+        );
+        assert_eq!(
+            timeout("mut i = 0; let x = try { while true { i = i + 1; }; 1 } catch (e) { 2 };")
+                .unwrap_err()
+                .kind,
+            EvaluateErrorKind::Timeout("20".to_string())
+        );
+        assert_eq!(timeout("1 + 1"), Ok(Object::Number(2.0)));
+
+        #[derive(Debug)]
+        struct StoppedClock;
+
+        impl builtin::Clock for StoppedClock {
+            fn now(&self) -> f64 {
+                0.0
+            }
+        }
+
+        let program = Parser::from("while true {}").parse_program().unwrap();
+        let mut evaluator = Evaluator::new(program);
+        evaluator.clock = Box::new(StoppedClock);
+
+        assert_eq!(
+            evaluator
+                .evaluate_with_timeout(Duration::from_millis(20))
+                .unwrap_err()
+                .kind,
+            EvaluateErrorKind::Timeout("20".to_string())
+        );
+    }
+
+    #[test]
+    fn test_invalid_index() {
+        let error = |source| evaluate(source).unwrap_err().kind;
+
+        assert_eq!(
+            error("let x = [1, 2][1.5];"),
+            EvaluateErrorKind::InvalidIndex("1.5".to_string())
+        );
+        assert_eq!(
+            error(r#"let x = "ab"[-1];"#),
+            EvaluateErrorKind::InvalidIndex("-1".to_string())
+        );
+        assert_eq!(
+            error("let x = [1, 2][10 ** 300];"),
+            EvaluateErrorKind::InvalidIndex(1e300.to_string())
+        );
+        assert_eq!(
+            error("let x = [1, 2][2 ** 53];"),
+            EvaluateErrorKind::IndexOutOfBounds(2f64.powi(53).to_string(), "2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_throw_statement() {
+        let evaluator = evaluate(
+            r#"
+            let f = fn(x: number) -> number {
+                throw "x is ${x}";
+            };
+            let message = try { f(1) } catch (e) { e };
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("message"),
+            Some(Object::String("x is 1".to_string()))
+        );
+
+        let error = evaluate(r#"throw "oops";"#).unwrap_err();
+
+        assert_eq!(error.kind, EvaluateErrorKind::UserError("oops".to_string()));
+        assert_eq!(
+            crate::Error::from(error).to_string(),
+            "Error[3009] at 1:13: oops"
+        );
+    }
+
+    #[test]
+    fn test_function_equality() {
+        let evaluator = evaluate(
+            r#"
+            let f = fn(x: number) -> number { x + 1 };
+            let g = fn(x: number) -> number { x + 1 };
+            let h = fn(x: number) -> number { x + 2 };
+            let y = 1;
+            let i = fn(x: number) -> number { x + 1 };
+            "#,
+        )
+        .unwrap();
+        let get = |name| evaluator.environment.get(name).unwrap();
+
+        assert_eq!(get("f"), get("g"));
+        assert_eq!(get("f").to_string(), get("g").to_string());
+        assert_eq!(get("f"), get("i"));
+        assert_ne!(get("f"), get("h"));
+        assert_ne!(get("f").to_string(), get("h").to_string());
+        let hash = get("f").to_string().replace("fn(x) #", "");
+        assert!(hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn test_argument_evaluation_order() {
+        let evaluator = evaluate(
+            r#"
+            let log = buffer();
+            let f = fn(x: number) -> number { push(log, x); x };
+            let g = fn(a: number, b: number, c: number) -> number { a };
+
+            g(f(1), f(2), f(3));
+            let x = try { g(f(4), panic("oops"), f(5)) } catch (e) { 0 };
+            let logged = to_array(log);
+            "#,
+        );
+
+        assert_eq!(
+            evaluator.unwrap().environment.get("logged"),
+            Some(Object::Array(
+                [1.0, 2.0, 3.0, 4.0]
+                    .into_iter()
+                    .map(Object::Number)
+                    .collect()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_scope_teardown() {
+        let mut evaluator = evaluate("let x = 1; let y = { let z = 2; z };").unwrap();
+
+        assert_eq!(evaluator.environment.get("z"), None);
+        assert_eq!(evaluator.environment.get("y"), Some(Object::Number(2.0)));
+
+        // Declaring `x` again, like a REPL would, replaces the old binding once it is removed.
+        let program = Parser::from(r#"let x = "a";"#).parse_program().unwrap();
+
+        assert!(evaluator.eval_statements(&program).is_err());
+        assert_eq!(evaluator.environment.remove("x"), Some(Object::Number(1.0)));
+        assert!(evaluator.eval_statements(&program).is_ok());
+        assert_eq!(
+            evaluator.environment.get("x"),
+            Some(Object::String("a".to_string()))
+        );
+        assert_eq!(
+            evaluator.environment.remove("x"),
+            Some(Object::String("a".to_string()))
+        );
+        assert_eq!(evaluator.environment.remove("x"), None);
+    }
+
+    #[test]
+    fn test_typeof_expression() {
+        let evaluator = evaluate(
+            r#"
+            let a = typeof 1;
+            let b = typeof [[true]];
+            let c = typeof fn(x: number) -> string { "" };
+            let d = typeof null;
+            "#,
+        )
+        .unwrap();
+        let get = |name| evaluator.environment.get(name).unwrap().to_string();
+
+        assert_eq!(get("a"), "Number");
+        assert_eq!(get("b"), "Boolean[][]");
+        assert_eq!(get("c"), "fn(Number) -> String");
+        assert_eq!(get("d"), "null");
+    }
+
+    #[test]
+    fn test_if_expression() {
+        let evaluator = evaluate(
+            r#"
+            let fib = fn(n: number) -> number {
+                if (n <= 1) {
+                    return n;
+                } else {
+                    return fib(n - 1) + fib(n - 2);
+                };
+            };
+            let x = fib(10);
+            let y = if 1 > 2 { "a" } else if 2 > 1 { "b" } else { "c" };
+            let z = if false { 1 };
+            let w = if true { 1 };
+            let v = if false { let b = 1; };
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(evaluator.environment.get("x"), Some(Object::Number(55.0)));
+        assert_eq!(
+            evaluator.environment.get("y"),
+            Some(Object::String("b".to_string()))
+        );
+        assert_eq!(evaluator.environment.get("z"), Some(Object::Null));
+        assert_eq!(evaluator.environment.get("w"), Some(Object::Number(1.0)));
+        assert_eq!(evaluator.environment.get("v"), Some(Object::Void));
+        assert_eq!(
+            evaluate("let x = if 1 { 2 } else { 3 };").unwrap_err().kind,
+            EvaluateErrorKind::TypeMismatch("number".to_string(), "boolean".to_string())
+        );
+    }
+
+    #[test]
+    fn test_string_concatenation() {
+        let evaluator = evaluate(r#"let x = "a" + "b" + "c";"#).unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("x"),
+            Some(Object::String("abc".to_string()))
+        );
+        assert_eq!(
+            evaluate(r#"let x = "a" + true;"#).unwrap_err().kind,
+            EvaluateErrorKind::InvalidOperator1("+".to_string(), "true".to_string())
+        );
+    }
+
+    #[test]
+    fn test_strict_concatenation() {
+        let evaluate = |source: &str, strict: bool| {
+            let mut evaluator = Evaluator::new(Parser::from(source).parse_program().unwrap());
+            evaluator.strict = strict;
+            evaluator.evaluate()
+        };
+
+        assert_eq!(
+            evaluate(r#""count: " + 5"#, false),
+            Ok(Object::String("count: 5".to_string()))
+        );
+        assert_eq!(
+            evaluate(r#"1.5 + "x""#, false),
+            Ok(Object::String("1.5x".to_string()))
+        );
+        assert_eq!(
+            evaluate(r#""count: " + 5"#, true).unwrap_err().kind,
+            EvaluateErrorKind::InvalidOperator1("+".to_string(), "5".to_string())
+        );
+    }
+
+    #[test]
+    fn test_struct_literal_keys() {
+        let evaluator = evaluate(
+            r#"
+            let k = "y";
+            let p = struct P { "with space": 1, [k]: 2, ["z" + k]: 3 };
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("p"),
+            Some(Object::Struct(
+                "P".to_string(),
+                vec![
+                    ("with space".to_string(), Object::Number(1.0)),
+                    ("y".to_string(), Object::Number(2.0)),
+                    ("zy".to_string(), Object::Number(3.0)),
+                ]
+            ))
+        );
+        assert_eq!(
+            evaluate(r#"let k = "x"; let p = struct P { x: 1, [k]: 2 };"#)
+                .unwrap_err()
+                .kind,
+            EvaluateErrorKind::DuplicateField("x".to_string())
+        );
+    }
+
+    #[test]
+    fn test_error_position() {
+        let error = evaluate(
+            "let xs = [1, 2, 3];
+let f = fn(i: number) -> number {
+    1 + xs[i]
+};
+f(5);",
+        )
+        .unwrap_err();
+
+        let Position(line, column) = error.position;
+        assert_eq!((line, column), (3, 11));
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut evaluator = evaluate("let x = 1; mut y = 2;").unwrap();
+        let snapshot = evaluator.snapshot();
+
+        let line = Parser::from("y = 5; let z = 3; [1][4];")
+            .parse_program()
+            .unwrap();
+        assert!(evaluator.eval_statements(&line).is_err());
+        assert_eq!(evaluator.environment.get("y"), Some(Object::Number(5.0)));
+
+        evaluator.restore(snapshot);
+
+        assert_eq!(evaluator.environment.get("x"), Some(Object::Number(1.0)));
+        assert_eq!(evaluator.environment.get("y"), Some(Object::Number(2.0)));
+        assert_eq!(evaluator.environment.get("z"), None);
+
+        evaluator.reset();
+        assert_eq!(evaluator.environment.get("x"), None);
+    }
+
+    #[test]
+    fn test_while_statement() {
+        let evaluator = evaluate(
+            "
+            let log = buffer();
+            mut i = 0;
+            outer: while i < 10 {
+                i = i + 1;
+                if i == 2 { continue; };
+                mut j = 0;
+                while true {
+                    j = j + 1;
+                    if i == 4 { break outer; };
+                    if j == 2 { continue outer; };
+                    push(log, i * 10 + j);
+                };
+                push(log, 0);
+            }
+            let result = to_array(log);
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(evaluator.environment.get("i"), Some(Object::Number(4.0)));
+        assert_eq!(
+            evaluator.environment.get("result"),
+            Some(Object::Array(vec![
+                Object::Number(11.0),
+                Object::Number(31.0)
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_return_from_while() {
+        let evaluator = evaluate(
+            "
+            let find = fn(xs: number[], x: number) -> number {
+                mut i = 0;
+                while i < 3 {
+                    if xs[i] == x { return i; };
+                    i = i + 1;
+                }
+                -1
+            };
+            let found = find([4, 5, 6], 5);
+            let missing = find([4, 5, 6], 7);
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.environment.get("found"),
+            Some(Object::Number(1.0))
+        );
+        assert_eq!(
+            evaluator.environment.get("missing"),
+            Some(Object::Number(-1.0))
+        );
+    }
+
+    #[test]
+    fn test_epsilon() {
+        let source = "let a = 0.1 + 0.2 == 0.3; let b = 0.1 + 0.2 != 0.3; let c = 1 == 1.1; let d = 0.3 in [0.1 + 0.2];";
+        let program = Parser::from(source).parse_program().unwrap();
+
+        let exact = evaluate(source).unwrap();
+        assert_eq!(exact.environment.get("a"), Some(Object::Boolean(false)));
+        assert_eq!(exact.environment.get("b"), Some(Object::Boolean(true)));
+        assert_eq!(exact.environment.get("d"), Some(Object::Boolean(false)));
+
+        let mut approximate = Evaluator::new(program);
+        approximate.epsilon = Some(1e-9);
+        approximate.evaluate().unwrap();
+
+        assert_eq!(
+            approximate.environment.get("a"),
+            Some(Object::Boolean(true))
+        );
+        assert_eq!(
+            approximate.environment.get("b"),
+            Some(Object::Boolean(false))
+        );
+        assert_eq!(
+            approximate.environment.get("c"),
+            Some(Object::Boolean(false))
+        );
+        assert_eq!(
+            approximate.environment.get("d"),
+            Some(Object::Boolean(true))
+        );
+    }
+
+    #[test]
+    fn test_breakpoint() {
+        let program = Parser::from(
+            "let f = fn(x: number) -> number {\n    let y = x * 2;\n    y + 1\n};\nlet a = f(1);\nlet b = f(5);\nf(a)",
+        )
+        .parse_program()
+        .unwrap();
+
+        let pauses = Rc::new(RefCell::new(Vec::new()));
+        let mut evaluator = Evaluator::new(program);
+        evaluator.set_breakpoint(3, {
+            let pauses = pauses.clone();
+            move |position, environment| {
+                pauses.borrow_mut().push((
+                    position.0,
+                    environment.get("x"),
+                    environment.get("y"),
+                    environment.get("a"),
+                ))
+            }
+        });
+        evaluator.set_breakpoint(7, {
+            let pauses = pauses.clone();
+            move |position, environment| {
+                pauses
+                    .borrow_mut()
+                    .push((position.0, None, None, environment.get("a")))
+            }
+        });
+        evaluator.evaluate().unwrap();
+
+        let number = |number| Some(Object::Number(number));
+        assert_eq!(
+            *pauses.borrow(),
+            vec![
+                (3, number(1.0), number(2.0), None),
+                (3, number(5.0), number(10.0), number(3.0)),
+                (7, None, None, number(3.0)),
+                (3, number(3.0), number(6.0), number(3.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_invariants() {
+        let program = Parser::from(
+            "
+            mut i = 0;
+            let f = fn(x: number) -> number { if x > 2 { return x; }; x * 2 };
+            while i < 20 { i = i + f(i + 1); if i == 5 { break; }; }
+            ",
+        )
+        .parse_program()
+        .unwrap();
+
+        let mut evaluator = Evaluator::new(program);
+        evaluator.check_invariants = true;
+        evaluator.evaluate().unwrap();
+
+        assert_eq!(evaluator.environment.get("i"), Some(Object::Number(5.0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "invariant violated: the scope at depth 0 shares its bindings")]
+    fn test_check_invariants_shared_scope() {
+        let program = Parser::from("let x = 1;").parse_program().unwrap();
+
+        let mut evaluator = Evaluator::new(program);
+        evaluator.check_invariants = true;
+
+        let global = evaluator.environment.clone();
+        evaluator.environment = Environment {
+            store: global.store.clone(),
+            parent: Some(Box::new(global)),
+        };
+
+        let _ = evaluator.evaluate();
+    }
+}
@@ -1,14 +1,225 @@
-use parser::ast::{BlockExpression, Parameter};
-use std::fmt;
+use super::enviroment::Environment;
+use parser::ast::{BlockExpression, DataType, DataTypeKind, FunctionType, Parameter, Position};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
 
-#[derive(Debug, PartialEq, Clone)]
+/// The results of a memoized function, keyed by the hash of their arguments.
+/// Arguments with the same hash are told apart by comparing them.
+pub type Cache = HashMap<u64, Vec<(Vec<Object>, Object)>>;
+
+#[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub enum Object {
     Number(f64),
     String(String),
     Boolean(bool),
-    Function(Vec<Parameter>, BlockExpression),
+    /// The parameters, return type and body of a function, and the environment it captured.
+    Function(Vec<Parameter>, DataType, BlockExpression, Environment),
     Array(Vec<Object>),
+    /// A mutable array. Copies of a buffer share its elements, so `push` and `pop` through any of them are visible to all.
+    Buffer(Rc<RefCell<Vec<Object>>>),
+    Struct(String, Vec<(String, Object)>),
+    Builtin(&'static str),
+    /// A function returned by `memoize`, and the cache of its results. Copies of it share the cache.
+    Memoized(Box<Object>, Rc<RefCell<Cache>>),
+    Null,
+    Void,
+}
+
+impl Object {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Object::Number(_) => "number",
+            Object::String(_) => "string",
+            Object::Boolean(_) => "boolean",
+            Object::Function(..) | Object::Builtin(_) | Object::Memoized(..) => "function",
+            Object::Array(_) => "array",
+            Object::Buffer(_) => "buffer",
+            Object::Struct(_, _) => "struct",
+            Object::Null => "null",
+            Object::Void => "void",
+        }
+    }
+
+    /// Returns the type of the value, with the types of functions as they are annotated.
+    /// An empty array is a `never[]`, and an array's element type is the type of its first element.
+    /// `null`, buffers and builtins have no type.
+    pub fn type_of(&self) -> Option<DataTypeKind> {
+        let data_type = |kind| DataType::new(kind, Position::default());
+
+        Some(match self {
+            Object::Number(_) => DataTypeKind::Number,
+            Object::String(_) => DataTypeKind::String,
+            Object::Boolean(_) => DataTypeKind::Boolean,
+            Object::Function(parameters, return_type, _, _) => DataTypeKind::Fn(FunctionType {
+                generics: None,
+                parameters: parameters
+                    .iter()
+                    .map(|parameter| (parameter.data_type.clone(), parameter.kind))
+                    .collect(),
+                return_type: Box::new(return_type.clone()),
+                position: Position::default(),
+            }),
+            Object::Array(elements) => {
+                DataTypeKind::Array(Box::new(data_type(match elements.first() {
+                    Some(element) => element.type_of()?,
+                    None => DataTypeKind::Never,
+                })))
+            }
+            Object::Struct(name, _) => DataTypeKind::Custom(name.clone()),
+            Object::Memoized(function, _) => function.type_of()?,
+            Object::Void => DataTypeKind::Void,
+            Object::Buffer(_) | Object::Builtin(_) | Object::Null => return None,
+        })
+    }
+}
+
+/// Functions are compared by their parameters and body, ignoring the environment they captured.
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        equals(self, other, &mut Vec::new())
+    }
+}
+
+type BufferPointer = *const RefCell<Vec<Object>>;
+
+/// Compares two values, assuming a pair of buffers that is already being compared further up is equal.
+fn equals(
+    left: &Object,
+    right: &Object,
+    buffers: &mut Vec<(BufferPointer, BufferPointer)>,
+) -> bool {
+    let all = |left: &[Object], right: &[Object], buffers: &mut Vec<_>| {
+        left.len() == right.len()
+            && left
+                .iter()
+                .zip(right)
+                .all(|(left, right)| equals(left, right, buffers))
+    };
+
+    match (left, right) {
+        (Object::Number(left), Object::Number(right)) => left == right,
+        (Object::String(left), Object::String(right)) => left == right,
+        (Object::Boolean(left), Object::Boolean(right)) => left == right,
+        (
+            Object::Function(left, left_return_type, left_body, _),
+            Object::Function(right, right_return_type, right_body, _),
+        ) => left == right && left_return_type == right_return_type && left_body == right_body,
+        (Object::Array(left), Object::Array(right)) => all(left, right, buffers),
+        (Object::Buffer(left), Object::Buffer(right)) => {
+            let pair = (Rc::as_ptr(left), Rc::as_ptr(right));
+
+            if Rc::ptr_eq(left, right) || buffers.contains(&pair) {
+                return true;
+            }
+
+            buffers.push(pair);
+            let equal = all(&left.borrow(), &right.borrow(), buffers);
+            buffers.pop();
+
+            equal
+        }
+        (Object::Struct(left, left_fields), Object::Struct(right, right_fields)) => {
+            left == right
+                && left_fields.len() == right_fields.len()
+                && left_fields.iter().zip(right_fields).all(
+                    |((left_name, left), (right_name, right))| {
+                        left_name == right_name && equals(left, right, buffers)
+                    },
+                )
+        }
+        (Object::Builtin(left), Object::Builtin(right)) => left == right,
+        (Object::Memoized(left, _), Object::Memoized(right, _)) => equals(left, right, buffers),
+        (Object::Null, Object::Null) | (Object::Void, Object::Void) => true,
+        _ => false,
+    }
+}
+
+/// Hashes a value by its structure, consistently with `PartialEq`.
+impl Hash for Object {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash(self, state, &mut Vec::new());
+    }
+}
+
+/// Hashes a value like `equals` compares it, skipping the buffers already being hashed.
+fn hash<H: Hasher>(object: &Object, state: &mut H, buffers: &mut Vec<BufferPointer>) {
+    let all = |elements: &[Object], state: &mut H, buffers: &mut Vec<_>| {
+        elements.len().hash(state);
+
+        for element in elements {
+            hash(element, state, buffers);
+        }
+    };
+
+    std::mem::discriminant(object).hash(state);
+
+    match object {
+        Object::Number(number) => (number + 0.0).to_bits().hash(state),
+        Object::String(string) => string.hash(state),
+        Object::Boolean(boolean) => boolean.hash(state),
+        Object::Function(parameters, _, body, _) => {
+            parameters.len().hash(state);
+            body_hash(body).hash(state);
+        }
+        Object::Array(elements) => all(elements, state, buffers),
+        Object::Buffer(elements) => {
+            if buffers.contains(&Rc::as_ptr(elements)) {
+                return;
+            }
+
+            buffers.push(Rc::as_ptr(elements));
+            all(&elements.borrow(), state, buffers);
+            buffers.pop();
+        }
+        Object::Struct(name, fields) => {
+            name.hash(state);
+            fields.len().hash(state);
+
+            for (field, value) in fields {
+                field.hash(state);
+                hash(value, state, buffers);
+            }
+        }
+        Object::Builtin(name) => name.hash(state),
+        Object::Memoized(function, _) => hash(function, state, buffers),
+        Object::Null | Object::Void => {}
+    }
+}
+
+/// Hashes the body of a function without its positions, the same way on every platform.
+fn body_hash(body: &BlockExpression) -> u64 {
+    let mut hasher = Fnv(0xcbf2_9ce4_8422_2325);
+    body.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+struct Fnv(u64);
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64);
+    }
 }
 
 impl fmt::Display for Object {
@@ -17,8 +228,68 @@ impl fmt::Display for Object {
             Object::Number(number) => write!(f, "{number}"),
             Object::String(string) => write!(f, "{string}"),
             Object::Boolean(boolean) => write!(f, "{boolean}"),
-            Object::Function(_, _) => write!(f, "Function"),
+            Object::Function(parameters, _, body, _) => {
+                let parameters = parameters
+                    .iter()
+                    .map(|parameter| parameter.identifier.value.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                write!(f, "fn({parameters}) #{:016x}", body_hash(body))
+            }
             Object::Array(_) => write!(f, "Array"),
+            Object::Buffer(_) => write!(f, "Buffer"),
+            Object::Struct(name, _) => write!(f, "{name}"),
+            Object::Builtin(name) => write!(f, "Builtin({name})"),
+            Object::Memoized(function, _) => write!(f, "{function}"),
+            Object::Null => write!(f, "null"),
+            Object::Void => write!(f, "Void"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::tests::evaluate;
+
+    #[test]
+    fn test_type_of() {
+        let evaluator = evaluate(
+            r#"
+            struct P { x: number }
+            let f = fn(x: number, spread rest: string[]) -> boolean { true };
+            let p = struct P { x: 1 };
+            "#,
+        )
+        .unwrap();
+        let type_of = |object: Object| object.type_of().map(|kind| kind.to_string());
+
+        assert_eq!(type_of(Object::Number(1.0)), Some("Number".to_string()));
+        assert_eq!(
+            type_of(Object::String("a".to_string())),
+            Some("String".to_string())
+        );
+        assert_eq!(type_of(Object::Boolean(true)), Some("Boolean".to_string()));
+        assert_eq!(
+            type_of(evaluator.environment.get("f").unwrap()),
+            Some("fn(Number, String[]) -> Boolean".to_string())
+        );
+        assert_eq!(
+            type_of(Object::Array(vec![Object::Array(vec![Object::Number(
+                1.0
+            )])])),
+            Some("Number[][]".to_string())
+        );
+        assert_eq!(type_of(Object::Array(vec![])), Some("Never[]".to_string()));
+        assert_eq!(
+            type_of(evaluator.environment.get("p").unwrap()),
+            Some("P".to_string())
+        );
+        assert_eq!(type_of(Object::Void), Some("Void".to_string()));
+        assert_eq!(type_of(Object::Array(vec![Object::Null])), None);
+        assert_eq!(type_of(Object::Buffer(Rc::new(RefCell::new(vec![])))), None);
+        assert_eq!(type_of(Object::Builtin("sort")), None);
+        assert_eq!(type_of(Object::Null), None);
+    }
+}
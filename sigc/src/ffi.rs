@@ -0,0 +1,522 @@
+//! A C-compatible representation of values, and functions to evaluate a program and call its functions from C.
+//!
+//! Strings are UTF-8 bytes followed by a NUL, and arrays (and buffers) are a pointer to their elements and a length.
+//! Functions, structs and the other values without a C equivalent are opaque handles, which can only be passed back.
+//! Every value returned by this module owns its memory until it is passed to `sntk_value_free`,
+//! while values built by C code (e.g. arguments) stay owned by the caller.
+
+use crate::{
+    evaluate_source,
+    evaluator::{json::MAX_DEPTH, object::Object, Evaluator},
+    Error,
+};
+use parser::ast::Position;
+use std::{
+    any::Any,
+    ffi::{c_char, c_void, CStr},
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr, slice,
+};
+
+/// The kinds of values, stored as a `u32` in `Value::tag`, so that C code can't build an invalid one.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueTag {
+    Null = 0,
+    Void = 1,
+    Number = 2,
+    String = 3,
+    Boolean = 4,
+    Array = 5,
+    Handle = 6,
+}
+
+impl TryFrom<u32> for ValueTag {
+    type Error = String;
+
+    fn try_from(tag: u32) -> Result<Self, Self::Error> {
+        Ok(match tag {
+            0 => ValueTag::Null,
+            1 => ValueTag::Void,
+            2 => ValueTag::Number,
+            3 => ValueTag::String,
+            4 => ValueTag::Boolean,
+            5 => ValueTag::Array,
+            6 => ValueTag::Handle,
+            tag => return Err(format!("invalid value tag `{tag}`")),
+        })
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Value {
+    /// One of the `ValueTag`s.
+    pub tag: u32,
+    pub number: f64,
+    /// Any value except `0` is `true`.
+    pub boolean: u8,
+    /// The bytes of a string, the elements of an array, or the object behind a handle.
+    pub pointer: *mut c_void,
+    /// The number of bytes of a string (without the NUL), or of elements of an array.
+    pub length: usize,
+}
+
+impl Value {
+    fn new(tag: ValueTag) -> Self {
+        Self {
+            tag: tag as u32,
+            number: 0.0,
+            boolean: 0,
+            pointer: ptr::null_mut(),
+            length: 0,
+        }
+    }
+
+    fn string(string: &str) -> Self {
+        let mut bytes = string.as_bytes().to_vec();
+        bytes.push(0);
+
+        Self {
+            pointer: Box::into_raw(bytes.into_boxed_slice()) as *mut c_void,
+            length: string.len(),
+            ..Self::new(ValueTag::String)
+        }
+    }
+
+    fn array(elements: &[Object], depth: usize) -> Result<Self, String> {
+        let mut values = Vec::with_capacity(elements.len());
+
+        for element in elements {
+            match Self::nested(element, depth + 1) {
+                Ok(value) => values.push(value),
+                Err(message) => {
+                    for value in values {
+                        unsafe { value.free() };
+                    }
+
+                    return Err(message);
+                }
+            }
+        }
+
+        let elements = values.into_boxed_slice();
+
+        Ok(Self {
+            length: elements.len(),
+            pointer: Box::into_raw(elements) as *mut c_void,
+            ..Self::new(ValueTag::Array)
+        })
+    }
+
+    /// Converts an object nested in `depth` arrays, failing deeper than `MAX_DEPTH` (e.g. on a buffer that contains itself).
+    fn nested(object: &Object, depth: usize) -> Result<Self, String> {
+        if depth > MAX_DEPTH {
+            return Err(format!("value nested deeper than {MAX_DEPTH} levels"));
+        }
+
+        Ok(match object {
+            Object::Number(number) => Self {
+                number: *number,
+                ..Self::new(ValueTag::Number)
+            },
+            Object::Boolean(boolean) => Self {
+                boolean: *boolean as u8,
+                ..Self::new(ValueTag::Boolean)
+            },
+            Object::String(string) => Self::string(string),
+            Object::Array(elements) => Self::array(elements, depth)?,
+            Object::Buffer(elements) => Self::array(&elements.borrow(), depth)?,
+            Object::Null => Self::new(ValueTag::Null),
+            Object::Void => Self::new(ValueTag::Void),
+            object => Self {
+                pointer: Box::into_raw(Box::new(object.clone())) as *mut c_void,
+                ..Self::new(ValueTag::Handle)
+            },
+        })
+    }
+
+    /// Converts the value back to an object, failing on an invalid tag or a missing pointer.
+    /// The value may also have been built by C code (handles can only come from this module).
+    ///
+    /// # Safety
+    ///
+    /// The pointer of a string or an array must point to `length` bytes or elements,
+    /// and the pointer of a handle must come from a value returned by this module that was not freed yet.
+    pub unsafe fn to_object(&self) -> Result<Object, String> {
+        let tag = ValueTag::try_from(self.tag)?;
+
+        if self.pointer.is_null()
+            && (self.length > 0 || tag == ValueTag::Handle)
+            && matches!(tag, ValueTag::String | ValueTag::Array | ValueTag::Handle)
+        {
+            return Err(format!("`{tag:?}` value without a pointer"));
+        }
+
+        Ok(match tag {
+            ValueTag::Null => Object::Null,
+            ValueTag::Void => Object::Void,
+            ValueTag::Number => Object::Number(self.number),
+            ValueTag::Boolean => Object::Boolean(self.boolean != 0),
+            ValueTag::String if self.length == 0 => Object::String(String::new()),
+            ValueTag::String => Object::String(
+                String::from_utf8_lossy(slice::from_raw_parts(
+                    self.pointer as *const u8,
+                    self.length,
+                ))
+                .into_owned(),
+            ),
+            ValueTag::Array if self.length == 0 => Object::Array(Vec::new()),
+            ValueTag::Array => Object::Array(
+                slice::from_raw_parts(self.pointer as *const Value, self.length)
+                    .iter()
+                    .map(|value| value.to_object())
+                    .collect::<Result<_, _>>()?,
+            ),
+            ValueTag::Handle => (*(self.pointer as *const Object)).clone(),
+        })
+    }
+
+    /// Frees the memory of a value built by `TryFrom<&Object>`, including its elements.
+    unsafe fn free(self) {
+        if self.pointer.is_null() {
+            return;
+        }
+
+        match ValueTag::try_from(self.tag) {
+            Ok(ValueTag::String) => drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+                self.pointer as *mut u8,
+                self.length + 1,
+            ))),
+            Ok(ValueTag::Array) => {
+                let elements = Box::from_raw(ptr::slice_from_raw_parts_mut(
+                    self.pointer as *mut Value,
+                    self.length,
+                ));
+
+                for element in elements.iter() {
+                    element.free();
+                }
+            }
+            Ok(ValueTag::Handle) => drop(Box::from_raw(self.pointer as *mut Object)),
+            _ => {}
+        }
+    }
+}
+
+impl TryFrom<&Object> for Value {
+    type Error = String;
+
+    fn try_from(object: &Object) -> Result<Self, Self::Error> {
+        Self::nested(object, 0)
+    }
+}
+
+/// Describes a panic, which must not unwind into the C caller.
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    let message = match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => payload
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_default(),
+    };
+
+    format!("internal error: {message}")
+}
+
+/// Where the errors of a call from C are reported, since there is no call site: the definition of the function.
+fn definition(function: &Object) -> Option<Position> {
+    match function {
+        Object::Function(_, _, body, _) => Some(body.position),
+        Object::Memoized(function, _) => definition(function),
+        _ => None,
+    }
+}
+
+/// Parses, analyzes and evaluates a NUL-terminated program, and writes its value to `result`.
+/// Returns the evaluator to call the program's functions with, or null (with the error message in `result`) if it fails.
+/// Nothing is written if `result` is null.
+///
+/// # Safety
+///
+/// `source` must be null or a NUL-terminated string, and `result` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sntk_evaluate(
+    source: *const c_char,
+    result: *mut Value,
+) -> *mut Evaluator {
+    if result.is_null() {
+        return ptr::null_mut();
+    }
+
+    let outcome = catch_unwind(|| {
+        if source.is_null() {
+            return Err("`source` is null".to_string());
+        }
+
+        let source = CStr::from_ptr(source).to_string_lossy();
+
+        let (evaluator, value) = evaluate_source(&source).map_err(|error| error.to_string())?;
+
+        Ok((evaluator, Value::try_from(&value)?))
+    });
+
+    match outcome.unwrap_or_else(|payload| Err(panic_message(payload))) {
+        Ok((evaluator, value)) => {
+            ptr::write(result, value);
+            Box::into_raw(Box::new(evaluator))
+        }
+        Err(message) => {
+            ptr::write(result, Value::string(&message));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Calls a function handle with `length` arguments, and writes its value to `result`.
+/// Returns `false` (with the error message in `result`) if the call fails. Nothing is written if `result` is null.
+///
+/// # Safety
+///
+/// `evaluator` must be null or come from `sntk_evaluate`, `function` must be null or point to a value,
+/// `arguments` must point to `length` values (see `Value::to_object`), and `result` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sntk_call(
+    evaluator: *mut Evaluator,
+    function: *const Value,
+    arguments: *const Value,
+    length: usize,
+    result: *mut Value,
+) -> bool {
+    if result.is_null() {
+        return false;
+    }
+
+    // A panic can leave the evaluator half updated, but it is only read again by further calls.
+    let outcome = catch_unwind(AssertUnwindSafe(|| {
+        if evaluator.is_null() || function.is_null() || (arguments.is_null() && length > 0) {
+            return Err("`evaluator`, `function` or `arguments` is null".to_string());
+        }
+
+        let function = (*function).to_object()?;
+        let arguments = match length {
+            0 => Vec::new(),
+            length => slice::from_raw_parts(arguments, length)
+                .iter()
+                .map(|argument| argument.to_object())
+                .collect::<Result<_, _>>()?,
+        };
+        let position = definition(&function);
+
+        match (*evaluator).call_function(function, arguments, position.unwrap_or_default()) {
+            Ok(value) => Value::try_from(&value),
+            Err(error) => Err(match (Error::from(error), position) {
+                (error, Some(_)) => error.to_string(),
+                (Error { error, .. }, None) => format!("Error[{}]: {}", error.code, error.message),
+            }),
+        }
+    }));
+
+    match outcome.unwrap_or_else(|payload| Err(panic_message(payload))) {
+        Ok(value) => {
+            ptr::write(result, value);
+            true
+        }
+        Err(message) => {
+            ptr::write(result, Value::string(&message));
+            false
+        }
+    }
+}
+
+/// Frees a value returned by `sntk_evaluate` or `sntk_call`, including its elements.
+///
+/// # Safety
+///
+/// The value must have been returned by this module, and not freed already.
+#[no_mangle]
+pub unsafe extern "C" fn sntk_value_free(value: Value) {
+    let _ = catch_unwind(|| value.free());
+}
+
+/// Frees an evaluator returned by `sntk_evaluate`. Handles from it stay valid.
+///
+/// # Safety
+///
+/// `evaluator` must be null or come from `sntk_evaluate`, and not be freed already.
+#[no_mangle]
+pub unsafe extern "C" fn sntk_evaluator_free(evaluator: *mut Evaluator) {
+    if !evaluator.is_null() {
+        let _ = catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(evaluator))));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{ffi::CString, mem::MaybeUninit};
+
+    #[test]
+    fn test_round_trip() {
+        let objects = [
+            Object::Number(1.5),
+            Object::Boolean(true),
+            Object::String("héllo".to_string()),
+            Object::Null,
+            Object::Array(vec![
+                Object::Number(1.0),
+                Object::Array(vec![Object::String("a".to_string())]),
+            ]),
+        ];
+
+        for object in objects {
+            let value = Value::try_from(&object).unwrap();
+            assert_eq!(unsafe { value.to_object() }, Ok(object));
+            unsafe { sntk_value_free(value) };
+        }
+
+        let value = Value::try_from(&Object::String("ab".to_string())).unwrap();
+        assert_eq!((value.tag, value.length), (ValueTag::String as u32, 2));
+        assert_eq!(unsafe { *(value.pointer as *const u8).add(2) }, 0);
+        unsafe { sntk_value_free(value) };
+    }
+
+    #[test]
+    fn test_values_from_c() {
+        let mut bytes = *b"hi";
+        let string = Value {
+            pointer: bytes.as_mut_ptr() as *mut c_void,
+            length: 2,
+            ..Value::new(ValueTag::String)
+        };
+        let mut elements = [string, Value::new(ValueTag::Null)];
+        let array = Value {
+            pointer: elements.as_mut_ptr() as *mut c_void,
+            length: 2,
+            ..Value::new(ValueTag::Array)
+        };
+
+        assert_eq!(
+            unsafe { array.to_object() },
+            Ok(Object::Array(vec![
+                Object::String("hi".to_string()),
+                Object::Null
+            ]))
+        );
+
+        let invalid = Value {
+            tag: 42,
+            ..Value::new(ValueTag::Null)
+        };
+        assert_eq!(
+            unsafe { invalid.to_object() },
+            Err("invalid value tag `42`".to_string())
+        );
+        assert!(unsafe { Value::new(ValueTag::Handle).to_object() }.is_err());
+    }
+
+    #[test]
+    fn test_call() {
+        let source =
+            CString::new("let add = fn(a: number, b: number) -> number { a + b }; add").unwrap();
+
+        unsafe {
+            let mut function = MaybeUninit::uninit();
+            let evaluator = sntk_evaluate(source.as_ptr(), function.as_mut_ptr());
+            let function = function.assume_init();
+            assert!(!evaluator.is_null());
+            assert_eq!(function.tag, ValueTag::Handle as u32);
+
+            let arguments = [
+                Value::try_from(&Object::Number(1.0)).unwrap(),
+                Value::try_from(&Object::Number(2.0)).unwrap(),
+            ];
+            let mut result = MaybeUninit::uninit();
+            assert!(sntk_call(
+                evaluator,
+                &function,
+                arguments.as_ptr(),
+                arguments.len(),
+                result.as_mut_ptr()
+            ));
+            let result = result.assume_init();
+            assert_eq!(result.to_object(), Ok(Object::Number(3.0)));
+            sntk_value_free(result);
+
+            // Errors of the call itself are reported at the definition of the function.
+            let mut result = MaybeUninit::uninit();
+            assert!(!sntk_call(
+                evaluator,
+                &function,
+                arguments.as_ptr(),
+                1,
+                result.as_mut_ptr()
+            ));
+            let result = result.assume_init();
+            let Ok(Object::String(message)) = result.to_object() else {
+                panic!("expected an error message");
+            };
+            assert!(message.starts_with("Error[") && !message.contains(" at 0:0"));
+            sntk_value_free(result);
+
+            let mut result = MaybeUninit::uninit();
+            assert!(!sntk_call(
+                ptr::null_mut(),
+                &function,
+                ptr::null(),
+                0,
+                result.as_mut_ptr()
+            ));
+            sntk_value_free(result.assume_init());
+            assert!(!sntk_call(
+                evaluator,
+                &function,
+                ptr::null(),
+                0,
+                ptr::null_mut()
+            ));
+
+            sntk_value_free(function);
+            sntk_evaluator_free(evaluator);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_errors() {
+        unsafe {
+            let mut result = MaybeUninit::uninit();
+            let source = CString::new("let x = ;").unwrap();
+            assert!(sntk_evaluate(source.as_ptr(), result.as_mut_ptr()).is_null());
+            let result = result.assume_init();
+            assert_eq!(
+                result.to_object(),
+                Ok(Object::String(
+                    "Error[1003] at 1:9: Unexpected token `;`".to_string()
+                ))
+            );
+            sntk_value_free(result);
+
+            let mut result = MaybeUninit::uninit();
+            let source = CString::new(
+                "declare buffer = fn() -> number[]; declare push = fn(number[], number[]) -> void;
+                let b = buffer(); push(b, b); b",
+            )
+            .unwrap();
+            assert!(sntk_evaluate(source.as_ptr(), result.as_mut_ptr()).is_null());
+            let result = result.assume_init();
+            assert_eq!(
+                result.to_object(),
+                Ok(Object::String(
+                    "value nested deeper than 128 levels".to_string()
+                ))
+            );
+            sntk_value_free(result);
+
+            let mut result = MaybeUninit::uninit();
+            assert!(sntk_evaluate(ptr::null(), result.as_mut_ptr()).is_null());
+            sntk_value_free(result.assume_init());
+            assert!(sntk_evaluate(source.as_ptr(), ptr::null_mut()).is_null());
+        }
+    }
+}
@@ -0,0 +1,462 @@
+//! The Sanetaka compiler driver: diagnostics for every stage, and the evaluator.
+
+#[macro_use]
+extern crate lazy_static;
+
+pub mod evaluator;
+pub mod ffi;
+#[cfg(test)]
+mod test_support;
+
+use analyzer::{analyzer::Analyzer, SemanticError, SemanticWarning};
+use evaluator::{object::Object, EvaluateError, Evaluator};
+use parser::{
+    ast::Position,
+    tokenizer::{tokenize, LexError},
+    Parser, ParsingError,
+};
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub struct ErrorInfo {
+    pub message: String,
+    pub code: i32,
+    pub help: Option<String>,
+}
+
+/// Reads an entry of `error_messages/en.json`, which the build script passes to the compiler.
+macro_rules! error_info {
+    ($name:literal) => {
+        ErrorInfo {
+            message: env!(concat!($name, ".message")).to_string(),
+            code: env!(concat!($name, ".code")).parse().unwrap(),
+            help: option_env!(concat!($name, ".help")).map(str::to_string),
+        }
+    };
+}
+
+lazy_static! {
+    pub static ref EXPECTED_NEXT_TOKEN: ErrorInfo = error_info!("ExpectedNextToken");
+    pub static ref EXPECTED_ONE_OF: ErrorInfo = error_info!("ExpectedOneOf");
+    pub static ref EXPECTED_DATA_TYPE: ErrorInfo = error_info!("ExpectedDataType");
+    pub static ref EXPECTED_EXPRESSION: ErrorInfo = error_info!("ExpectedExpression");
+    pub static ref UNEXPECTED_TOKEN: ErrorInfo = error_info!("UnexpectedToken");
+    pub static ref NESTING_TOO_DEEP: ErrorInfo = error_info!("NestingTooDeep");
+    pub static ref TOO_MANY_ERRORS: ErrorInfo = error_info!("TooManyErrors");
+    pub static ref ILLEGAL_CHARACTER: ErrorInfo = error_info!("IllegalCharacter");
+    pub static ref UNTERMINATED_STRING: ErrorInfo = error_info!("UnterminatedString");
+    pub static ref UNTERMINATED_COMMENT: ErrorInfo = error_info!("UnterminatedComment");
+    pub static ref INVALID_NUMBER: ErrorInfo = error_info!("InvalidNumber");
+    pub static ref TYPE_MISMATCH: ErrorInfo = error_info!("TypeMismatch");
+    pub static ref NESTED_TYPE_MISMATCH: ErrorInfo = error_info!("NestedTypeMismatch");
+    pub static ref IDENTIFIER_NOT_DEFINED: ErrorInfo = error_info!("IdentifierNotDefined");
+    pub static ref TYPE_ALIAS_NOT_DEFINED: ErrorInfo = error_info!("TypeAliasNotDefined");
+    pub static ref IDENTIFIER_ALREADY_DEFINED: ErrorInfo = error_info!("IdentifierAlreadyDefined");
+    pub static ref TYPE_ALIAS_ALREADY_DEFINED: ErrorInfo = error_info!("TypeAliasAlreadyDefined");
+    pub static ref OPERATOR_NOT_SUPPORTED: ErrorInfo = error_info!("OperatorNotSupported");
+    pub static ref TYPE_ANNOTATION_NEEDED: ErrorInfo = error_info!("TypeAnnotationNeeded");
+    pub static ref CANNOT_ASSIGN_TO_IMMUTABLE_VARIABLE: ErrorInfo =
+        error_info!("CannotAssignToImmutableVariable");
+    pub static ref CANNOT_BIND_VOID: ErrorInfo = error_info!("CannotBindVoid");
+    pub static ref INVALID_OPERATOR1: ErrorInfo = error_info!("InvalidOperator1");
+    pub static ref INVALID_OPERATOR2: ErrorInfo = error_info!("InvalidOperator2");
+    pub static ref NOT_CALLABLE: ErrorInfo = error_info!("NotCallable");
+    pub static ref ARGUMENT_COUNT_MISMATCH: ErrorInfo = error_info!("ArgumentCountMismatch");
+    pub static ref INDEX_OUT_OF_BOUNDS: ErrorInfo = error_info!("IndexOutOfBounds");
+    pub static ref FIELD_NOT_DEFINED: ErrorInfo = error_info!("FieldNotDefined");
+    pub static ref INVALID_CAST: ErrorInfo = error_info!("InvalidCast");
+    pub static ref NEGATIVE_COUNT: ErrorInfo = error_info!("NegativeCount");
+    pub static ref NOT_IMPLEMENTED: ErrorInfo = error_info!("NotImplemented");
+    pub static ref USER_ERROR: ErrorInfo = error_info!("UserError");
+    pub static ref DUPLICATE_FIELD: ErrorInfo = error_info!("DuplicateField");
+    pub static ref MISSING_FIELD: ErrorInfo = error_info!("MissingField");
+    pub static ref MISSING_RETURN: ErrorInfo = error_info!("MissingReturn");
+    pub static ref OUTPUT_LIMIT_EXCEEDED: ErrorInfo = error_info!("OutputLimitExceeded");
+    pub static ref EXIT: ErrorInfo = error_info!("Exit");
+    pub static ref INVALID_SIZE: ErrorInfo = error_info!("InvalidSize");
+    pub static ref INVALID_CHARACTER: ErrorInfo = error_info!("InvalidCharacter");
+    pub static ref INVALID_JSON: ErrorInfo = error_info!("InvalidJson");
+    pub static ref NOT_SERIALIZABLE: ErrorInfo = error_info!("NotSerializable");
+    pub static ref INVALID_INDEX: ErrorInfo = error_info!("InvalidIndex");
+    pub static ref EMPTY_ARRAY: ErrorInfo = error_info!("EmptyArray");
+    pub static ref TIMEOUT: ErrorInfo = error_info!("Timeout");
+    pub static ref VALUE_TOO_DEEP: ErrorInfo = error_info!("ValueTooDeep");
+    pub static ref STRING_TOO_LONG: ErrorInfo = error_info!("StringTooLong");
+    pub static ref NO_MATCHING_ARM: ErrorInfo = error_info!("NoMatchingArm");
+    pub static ref INVALID_EXIT_CODE: ErrorInfo = error_info!("InvalidExitCode");
+    pub static ref UNBALANCED_BRACE: ErrorInfo = error_info!("UnbalancedBrace");
+    pub static ref INTERNAL_ERROR: ErrorInfo = error_info!("InternalError");
+    pub static ref NON_CONSTANT_ARRAY_SIZE: ErrorInfo = error_info!("NonConstantArraySize");
+    pub static ref INVALID_ARRAY_SIZE: ErrorInfo = error_info!("InvalidArraySize");
+    pub static ref PATTERN_MISMATCH: ErrorInfo = error_info!("PatternMismatch");
+    pub static ref ARRAY_LENGTH_MISMATCH: ErrorInfo = error_info!("ArrayLengthMismatch");
+    pub static ref LABEL_NOT_DEFINED: ErrorInfo = error_info!("LabelNotDefined");
+    pub static ref NOT_IN_LOOP: ErrorInfo = error_info!("NotInLoop");
+    pub static ref SELF_ASSIGNMENT: ErrorInfo = error_info!("SelfAssignment");
+    pub static ref UNUSED_EXPRESSION: ErrorInfo = error_info!("UnusedExpression");
+    pub static ref DISCARDED_BINDING: ErrorInfo = error_info!("DiscardedBinding");
+    pub static ref MISSING_ELSE: ErrorInfo = error_info!("MissingElse");
+    pub static ref DANGLING_STATEMENT: ErrorInfo = error_info!("DanglingStatement");
+}
+
+fn replace(error_info: ErrorInfo, replacements: &[&str]) -> ErrorInfo {
+    let mut text = error_info.message;
+    for (index, replacement) in replacements.iter().enumerate() {
+        text = text.replace(&format!("{{{}}}", index), replacement);
+    }
+
+    ErrorInfo {
+        message: text,
+        code: error_info.code,
+        help: error_info.help,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Error {
+    pub error: ErrorInfo,
+    pub position: Position,
+    pub suggestion: Option<String>,
+}
+
+impl From<ParsingError> for Error {
+    fn from(error: ParsingError) -> Self {
+        use parser::ParsingErrorKind::*;
+
+        let message = match error.kind {
+            ExpectedNextToken(a, b) => replace(EXPECTED_NEXT_TOKEN.clone(), &[&a, &b]),
+            ExpectedOneOf(a, b) => {
+                let a = a
+                    .iter()
+                    .map(|token| format!("`{token}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                replace(EXPECTED_ONE_OF.clone(), &[&a, &b])
+            }
+            ExpectedDataType(a) => replace(EXPECTED_DATA_TYPE.clone(), &[&a]),
+            ExpectedExpression(a) => replace(EXPECTED_EXPRESSION.clone(), &[&a]),
+            UnexpectedToken(a) => replace(UNEXPECTED_TOKEN.clone(), &[&a]),
+            NestingTooDeep(a) => replace(NESTING_TOO_DEEP.clone(), &[&a.to_string()]),
+            TooManyErrors(a) => replace(TOO_MANY_ERRORS.clone(), &[&a.to_string()]),
+            InvalidNumber(a) => replace(INVALID_NUMBER.clone(), &[&a]),
+            UnterminatedComment => UNTERMINATED_COMMENT.clone(),
+        };
+
+        Self {
+            error: message,
+            position: error.position,
+            suggestion: None,
+        }
+    }
+}
+
+impl From<LexError> for Error {
+    fn from(error: LexError) -> Self {
+        use parser::tokenizer::LexErrorKind::*;
+
+        let message = match error.kind {
+            IllegalCharacter(a) => replace(ILLEGAL_CHARACTER.clone(), &[&a.to_string()]),
+            UnterminatedString => UNTERMINATED_STRING.clone(),
+            UnterminatedComment => UNTERMINATED_COMMENT.clone(),
+            InvalidNumber(a) => replace(INVALID_NUMBER.clone(), &[&a]),
+        };
+
+        Self {
+            error: message,
+            position: error.position,
+            suggestion: None,
+        }
+    }
+}
+
+impl From<SemanticError> for Error {
+    fn from(error: SemanticError) -> Self {
+        use analyzer::SemanticErrorKind::*;
+
+        let message = match error.kind {
+            TypeMismatch(a, b) => replace(TYPE_MISMATCH.clone(), &[&a, &b]),
+            NestedTypeMismatch(a, b, difference) => replace(
+                NESTED_TYPE_MISMATCH.clone(),
+                &[
+                    &a,
+                    &b,
+                    &difference.to_string(),
+                    &difference.left.to_string(),
+                    &difference.right.to_string(),
+                ],
+            ),
+            IdentifierNotDefined(a) => replace(IDENTIFIER_NOT_DEFINED.clone(), &[&a]),
+            TypeAliasNotDefined(a) => replace(TYPE_ALIAS_NOT_DEFINED.clone(), &[&a]),
+            IdentifierAlreadyDefined(a) => replace(IDENTIFIER_ALREADY_DEFINED.clone(), &[&a]),
+            TypeAliasAlreadyDefined(a) => replace(TYPE_ALIAS_ALREADY_DEFINED.clone(), &[&a]),
+            OperatorNotSupported(a, b) => replace(OPERATOR_NOT_SUPPORTED.clone(), &[&a, &b]),
+            TypeAnnotationNeeded => TYPE_ANNOTATION_NEEDED.clone(),
+            CannotAssignToImmutableVariable(a) => {
+                replace(CANNOT_ASSIGN_TO_IMMUTABLE_VARIABLE.clone(), &[&a])
+            }
+            CannotBindVoid(a) => replace(CANNOT_BIND_VOID.clone(), &[&a]),
+            IndexOutOfBounds(a, b) => replace(INDEX_OUT_OF_BOUNDS.clone(), &[&a, &b]),
+            FieldNotDefined(a, b) => replace(FIELD_NOT_DEFINED.clone(), &[&a, &b]),
+            DuplicateField(a) => replace(DUPLICATE_FIELD.clone(), &[&a]),
+            MissingField(a, b) => replace(MISSING_FIELD.clone(), &[&a, &b]),
+            MissingReturn(a) => replace(MISSING_RETURN.clone(), &[&a]),
+            NonConstantArraySize => NON_CONSTANT_ARRAY_SIZE.clone(),
+            InvalidArraySize(a) => replace(INVALID_ARRAY_SIZE.clone(), &[&a]),
+            PatternMismatch(a, b) => replace(PATTERN_MISMATCH.clone(), &[&a, &b]),
+            ArrayLengthMismatch(a, b) => replace(ARRAY_LENGTH_MISMATCH.clone(), &[&a, &b]),
+            LabelNotDefined(a) => replace(LABEL_NOT_DEFINED.clone(), &[&a]),
+            NotInLoop(a) => replace(NOT_IN_LOOP.clone(), &[&a]),
+            InternalError(a) => replace(INTERNAL_ERROR.clone(), &[&a]),
+        };
+
+        Self {
+            error: message,
+            position: error.position,
+            suggestion: error.suggestion,
+        }
+    }
+}
+
+impl From<EvaluateError> for Error {
+    fn from(error: EvaluateError) -> Self {
+        use evaluator::EvaluateErrorKind::*;
+
+        let message = match error.kind {
+            IdentifierAlreadyDefined(a) => replace(IDENTIFIER_ALREADY_DEFINED.clone(), &[&a]),
+            IdentifierNotDefined(a) => replace(IDENTIFIER_NOT_DEFINED.clone(), &[&a]),
+            InvalidOperator1(a, b) => replace(INVALID_OPERATOR1.clone(), &[&a, &b]),
+            InvalidOperator2(a, b, c) => replace(INVALID_OPERATOR2.clone(), &[&a, &b, &c]),
+            TypeMismatch(a, b) => replace(TYPE_MISMATCH.clone(), &[&a, &b]),
+            NotCallable(a) => replace(NOT_CALLABLE.clone(), &[&a]),
+            ArgumentCountMismatch(a, b) => replace(ARGUMENT_COUNT_MISMATCH.clone(), &[&a, &b]),
+            IndexOutOfBounds(a, b) => replace(INDEX_OUT_OF_BOUNDS.clone(), &[&a, &b]),
+            FieldNotDefined(a, b) => replace(FIELD_NOT_DEFINED.clone(), &[&a, &b]),
+            InvalidCast(a, b) => replace(INVALID_CAST.clone(), &[&a, &b]),
+            NegativeCount(a) => replace(NEGATIVE_COUNT.clone(), &[&a]),
+            NotImplemented => NOT_IMPLEMENTED.clone(),
+            UserError(a) => replace(USER_ERROR.clone(), &[&a]),
+            DuplicateField(a) => replace(DUPLICATE_FIELD.clone(), &[&a]),
+            OutputLimitExceeded(a) => replace(OUTPUT_LIMIT_EXCEEDED.clone(), &[&a]),
+            Exit(a) => replace(EXIT.clone(), &[&a.to_string()]),
+            InvalidSize(a) => replace(INVALID_SIZE.clone(), &[&a]),
+            InvalidCharacter(a) => replace(INVALID_CHARACTER.clone(), &[&a]),
+            InvalidJson(a) => replace(INVALID_JSON.clone(), &[&a]),
+            NotSerializable(a) => replace(NOT_SERIALIZABLE.clone(), &[&a]),
+            PatternMismatch(a, b) => replace(PATTERN_MISMATCH.clone(), &[&a, &b]),
+            InvalidIndex(a) => replace(INVALID_INDEX.clone(), &[&a]),
+            EmptyArray(a) => replace(EMPTY_ARRAY.clone(), &[&a]),
+            Timeout(a) => replace(TIMEOUT.clone(), &[&a]),
+            ValueTooDeep(a) => replace(VALUE_TOO_DEEP.clone(), &[&a]),
+            StringTooLong(a) => replace(STRING_TOO_LONG.clone(), &[&a]),
+            NoMatchingArm(a) => replace(NO_MATCHING_ARM.clone(), &[&a]),
+            InvalidExitCode(a) => replace(INVALID_EXIT_CODE.clone(), &[&a]),
+            UnbalancedBrace(a) => replace(UNBALANCED_BRACE.clone(), &[&a]),
+            InternalError(a) => replace(INTERNAL_ERROR.clone(), &[&a]),
+        };
+
+        Self {
+            error: message,
+            position: error.position,
+            suggestion: None,
+        }
+    }
+}
+
+/// Renders a diagnostic, using ANSI escape codes for the severity, position, help and suggestion if `color` is set.
+fn render(
+    severity: &str,
+    info: &ErrorInfo,
+    position: &Position,
+    suggestion: Option<&str>,
+    color: bool,
+) -> String {
+    let paint = |text: String, style: &str| match color {
+        true => format!("\x1b[{style}m{text}\x1b[0m"),
+        false => text,
+    };
+
+    let ErrorInfo {
+        message,
+        code,
+        help,
+    } = info;
+    let style = match severity {
+        "Warning" => "1;33",
+        _ => "1;31",
+    };
+    let help = match help {
+        Some(help) => format!("\n{}: {help}", paint("Help".to_string(), "1;36")),
+        None => String::new(),
+    };
+    let suggestion = match suggestion {
+        Some(suggestion) => format!(
+            "\n{}: {suggestion}",
+            paint("Suggestion".to_string(), "1;32")
+        ),
+        None => String::new(),
+    };
+
+    format!(
+        "{} at {}: {message}{help}{suggestion}",
+        paint(format!("{severity}[{code}]"), style),
+        paint(position.to_string(), "1"),
+    )
+}
+
+impl Error {
+    pub fn render(&self, color: bool) -> String {
+        render(
+            "Error",
+            &self.error,
+            &self.position,
+            self.suggestion.as_deref(),
+            color,
+        )
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(false))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Warning {
+    pub warning: ErrorInfo,
+    pub position: Position,
+}
+
+impl From<SemanticWarning> for Warning {
+    fn from(warning: SemanticWarning) -> Self {
+        use analyzer::SemanticWarningKind::*;
+
+        let message = match warning.kind {
+            SelfAssignment(a) => replace(SELF_ASSIGNMENT.clone(), &[&a]),
+            UnusedExpression => UNUSED_EXPRESSION.clone(),
+            DiscardedBinding => DISCARDED_BINDING.clone(),
+            MissingElse(a) => replace(MISSING_ELSE.clone(), &[&a]),
+            DanglingStatement => DANGLING_STATEMENT.clone(),
+        };
+
+        Self {
+            warning: message,
+            position: warning.position,
+        }
+    }
+}
+
+impl Warning {
+    pub fn render(&self, color: bool) -> String {
+        render("Warning", &self.warning, &self.position, None, color)
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(false))
+    }
+}
+
+/// Lists the tokens of the source, one `position kind` pair per line.
+pub fn dump_tokens(source: &str) -> Result<String, LexError> {
+    Ok(tokenize(source)?
+        .iter()
+        .map(|token| format!("{} {:?}\n", token.position, token.kind))
+        .collect())
+}
+
+/// The warnings only the parser can find, which are reported with the analyzer's: statements indented under an `if`
+/// or `while` body without braces, which aren't part of it.
+pub fn parser_warnings(parser: &Parser) -> Vec<SemanticWarning> {
+    parser
+        .dangling
+        .iter()
+        .map(|position| SemanticWarning::dangling_statement(*position))
+        .collect()
+}
+
+/// Parses and analyzes the source without evaluating it, and returns the warnings of both stages or the first error.
+pub fn check_source(source: &str) -> Result<Vec<Warning>, Error> {
+    let mut parser = Parser::from(source);
+    let program = parser
+        .parse_program()
+        .map_err(|errors| Error::from(errors[0].clone()))?;
+
+    let mut analyzer = Analyzer::new(program);
+    analyzer.analyze()?;
+
+    Ok(analyzer
+        .warnings
+        .into_iter()
+        .chain(parser_warnings(&parser))
+        .map(Warning::from)
+        .collect())
+}
+
+/// Parses, analyzes and evaluates the source, and returns its value or the first error of the failing stage.
+/// Warnings don't stop it, `check_source` returns them.
+pub fn run_source(source: &str) -> Result<Object, Error> {
+    evaluate_source(source).map(|(_, value)| value)
+}
+
+/// Like `run_source`, but also returns the evaluator, whose environment still holds the program's bindings.
+pub fn evaluate_source(source: &str) -> Result<(Evaluator, Object), Error> {
+    let program = Parser::from(source)
+        .parse_program()
+        .map_err(|errors| Error::from(errors[0].clone()))?;
+
+    Analyzer::new(program.clone()).analyze()?;
+
+    let mut evaluator = Evaluator::new(program);
+    let value = evaluator.evaluate()?;
+
+    Ok((evaluator, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_source() {
+        assert_eq!(run_source("let x = 1; x + 1").unwrap(), Object::Number(2.0));
+        assert_eq!(run_source("2 + 3;").unwrap(), Object::Number(5.0));
+        assert_eq!(
+            run_source("let x = 1; return 2; x;").unwrap(),
+            Object::Number(2.0)
+        );
+        assert_eq!(run_source("let x = 1;").unwrap(), Object::Void);
+        assert_eq!(run_source("let x = ;").unwrap_err().error.code, 1003);
+        assert_eq!(
+            run_source("let x: string = 1;").unwrap_err().error.code,
+            2000
+        );
+        assert_eq!(
+            run_source(r#"throw "oops";"#).unwrap_err().to_string(),
+            "Error[3009] at 1:13: oops"
+        );
+        assert_eq!(run_source("0xff + 0b1").unwrap(), Object::Number(256.0));
+        assert_eq!(
+            run_source("0b102;").unwrap_err().to_string(),
+            "Error[1010] at 1:5: Invalid number literal `0b102`"
+        );
+    }
+
+    #[test]
+    fn test_check_source() {
+        let codes = |source| {
+            check_source(source)
+                .unwrap()
+                .iter()
+                .map(|warning| warning.warning.code)
+                .collect::<Vec<_>>()
+        };
+
+        let source = "declare f = fn() -> void;\nlet _ = 1;\nif (true) return 1;\n    f();";
+        assert_eq!(codes(source), vec![2102, 2104]);
+        assert!(codes("declare f = fn() -> void;\nif (true) return 1;\nf();").is_empty());
+        assert_eq!(check_source("let x = ;").unwrap_err().error.code, 1003);
+    }
+}
@@ -1,185 +1,222 @@
-#[macro_use]
-extern crate lazy_static;
-
 mod arguments;
-mod evaluator;
 
-use analyzer::{analyzer::Analyzer, SemanticError};
+use analyzer::{analyzer::Analyzer, SemanticError, SemanticWarning};
 use clap::Parser as _;
-use evaluator::{EvaluateError, Evaluator};
-use parser::{ast::Position, tokenizer::Lexer, Parser, ParsingError};
-use std::{fmt, fs};
-
-#[derive(Debug, Clone)]
-pub struct ErrorInfo {
-    pub message: String,
-    pub code: i32,
-    pub help: Option<String>,
+use parser::{tokenizer::Lexer, Parser};
+use sigc::{
+    dump_tokens,
+    evaluator::{object::Object, EvaluateError, EvaluateErrorKind, Evaluator},
+    parser_warnings, Error, Warning,
+};
+use std::{fs, process};
+
+/// Renders the warnings and errors of the analysis, and returns the code to exit with if `--deny-warnings` fails it.
+fn analysis_report(
+    warnings: Vec<SemanticWarning>,
+    errors: Vec<SemanticError>,
+    deny_warnings: bool,
+    color: bool,
+) -> (Vec<String>, Option<i32>) {
+    let exit_code = (deny_warnings && !warnings.is_empty()).then_some(1);
+    let lines = warnings
+        .into_iter()
+        .map(|warning| Warning::from(warning).render(color))
+        .chain(
+            errors
+                .into_iter()
+                .map(|error| Error::from(error).render(color)),
+        )
+        .collect();
+
+    (lines, exit_code)
 }
 
-impl From<&str> for ErrorInfo {
-    fn from(name: &str) -> Self {
-        Self {
-            message: var(name, "message").unwrap(),
-            code: var(name, "code").unwrap().parse::<i32>().unwrap(),
-            help: var(name, "help"),
+fn main() {
+    let args = arguments::Cli::parse();
+    let color = args.color().enabled();
+    let content = fs::read_to_string(args.source).unwrap();
+
+    if args.emit_tokens {
+        match dump_tokens(&content) {
+            Ok(tokens) => print!("{tokens}"),
+            Err(error) => println!("{}", Error::from(error).render(color)),
         }
     }
-}
 
-#[inline]
-fn var(name: &str, postfix: &str) -> Option<String> {
-    match std::env::var(format!("{}.{}", name, postfix)) {
-        Ok(value) => Some(value),
-        Err(_) => None,
-    }
-}
+    let lexer = Lexer::new(content.as_str());
+    let mut parser = Parser::new(lexer);
+    parser.defines = args.defines.clone();
 
-lazy_static! {
-    pub static ref EXPECTED_NEXT_TOKEN: ErrorInfo = ErrorInfo::from("ExpectedNextToken");
-    pub static ref EXPECTED_DATA_TYPE: ErrorInfo = ErrorInfo::from("ExpectedDataType");
-    pub static ref EXPECTED_EXPRESSION: ErrorInfo = ErrorInfo::from("ExpectedExpression");
-    pub static ref UNEXPECTED_TOKEN: ErrorInfo = ErrorInfo::from("UnexpectedToken");
-    pub static ref TYPE_MISMATCH: ErrorInfo = ErrorInfo::from("TypeMismatch");
-    pub static ref IDENTIFIER_NOT_DEFINED: ErrorInfo = ErrorInfo::from("IdentifierNotDefined");
-    pub static ref TYPE_ALIAS_NOT_DEFINED: ErrorInfo = ErrorInfo::from("TypeAliasNotDefined");
-    pub static ref IDENTIFIER_ALREADY_DEFINED: ErrorInfo =
-        ErrorInfo::from("IdentifierAlreadyDefined");
-    pub static ref TYPE_ALIAS_ALREADY_DEFINED: ErrorInfo =
-        ErrorInfo::from("TypeAliasAlreadyDefined");
-    pub static ref OPERATOR_NOT_SUPPORTED: ErrorInfo = ErrorInfo::from("OperatorNotSupported");
-    pub static ref TYPE_ANNOTATION_NEEDED: ErrorInfo = ErrorInfo::from("TypeAnnotationNeeded");
-    pub static ref CANNOT_ASSIGN_TO_IMMUTABLE_VARIABLE: ErrorInfo =
-        ErrorInfo::from("CannotAssignToImmutableVariable");
-    pub static ref INVALID_OPERATOR1: ErrorInfo = ErrorInfo::from("InvalidOperator1");
-    pub static ref INVALID_OPERATOR2: ErrorInfo = ErrorInfo::from("InvalidOperator2");
-}
+    match parser.parse_program() {
+        Ok(ast) => {
+            // println!("AST: {:#?}", ast);
+            let mut analyzer = Analyzer::new(ast.clone());
+            analyzer.strict = args.strict;
+            analyzer.unions = args.unions;
+
+            let result = analyzer.analyze_program();
+            // brace-less bodies are only visible to the parser, but are reported with the other warnings.
+            analyzer.warnings.extend(parser_warnings(&parser));
+            let (ret, errors) = match result {
+                Ok(ret) => (Some(ret), Vec::new()),
+                Err(errors) => (None, errors),
+            };
+            let (lines, exit_code) = analysis_report(
+                analyzer.warnings.drain(..).collect(),
+                errors,
+                args.deny_warnings,
+                color,
+            );
+
+            for line in lines {
+                println!("{line}");
+            }
 
-fn replace(error_info: ErrorInfo, replacements: &[&str]) -> ErrorInfo {
-    let mut text = error_info.message;
-    for (index, replacement) in replacements.iter().enumerate() {
-        text = text.replace(&format!("{{{}}}", index), replacement);
-    }
+            if let Some(code) = exit_code {
+                process::exit(code);
+            }
 
-    ErrorInfo {
-        message: text,
-        code: error_info.code,
-        help: error_info.help,
+            if let Some(ret) = ret {
+                println!("Analyzed return type: {ret:?}");
+                if args.dump_symbols {
+                    print!("{}", analyzer.symbol_table);
+                }
+                if args.eval {
+                    let mut evaluator = Evaluator::new(ast);
+                    evaluator.strict = args.strict;
+                    evaluator.max_output = args.max_output;
+                    evaluator.epsilon = args.epsilon;
+
+                    match evaluator.evaluate() {
+                        Ok(Object::Void) => {}
+                        Ok(value) => println!("Evaluated value: {value}"),
+                        Err(EvaluateError {
+                            kind: EvaluateErrorKind::Exit(code),
+                            ..
+                        }) => process::exit(code),
+                        Err(error) => println!("{}", Error::from(error).render(color)),
+                    }
+                }
+            }
+        }
+        Err(errors) => {
+            for error in errors {
+                println!("{}", Error::from(error).render(color));
+            }
+        }
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct Error {
-    pub error: ErrorInfo,
-    pub position: Position,
-}
-
-impl From<ParsingError> for Error {
-    fn from(error: ParsingError) -> Self {
-        use parser::ParsingErrorKind::*;
-
-        let message = match error.kind {
-            ExpectedNextToken(a, b) => replace(EXPECTED_NEXT_TOKEN.clone(), &[&a, &b]),
-            ExpectedDataType(a) => replace(EXPECTED_DATA_TYPE.clone(), &[&a]),
-            ExpectedExpression(a) => replace(EXPECTED_EXPRESSION.clone(), &[&a]),
-            UnexpectedToken(a) => replace(UNEXPECTED_TOKEN.clone(), &[&a]),
-        };
-
-        Self {
-            error: message,
-            position: error.position,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arguments::{Cli, ColorChoice};
+    use parser::ast::Position;
+    use sigc::ErrorInfo;
+
+    fn error() -> Error {
+        Error {
+            error: ErrorInfo {
+                message: String::from("Unexpected token `@`"),
+                code: 1003,
+                help: None,
+            },
+            position: Position(1, 2),
+            suggestion: None,
         }
     }
-}
 
-impl From<SemanticError> for Error {
-    fn from(error: SemanticError) -> Self {
-        use analyzer::SemanticErrorKind::*;
-
-        let message = match error.kind {
-            TypeMismatch(a, b) => replace(TYPE_MISMATCH.clone(), &[&a, &b]),
-            IdentifierNotDefined(a) => replace(IDENTIFIER_NOT_DEFINED.clone(), &[&a]),
-            TypeAliasNotDefined(a) => replace(TYPE_ALIAS_NOT_DEFINED.clone(), &[&a]),
-            IdentifierAlreadyDefined(a) => replace(IDENTIFIER_ALREADY_DEFINED.clone(), &[&a]),
-            TypeAliasAlreadyDefined(a) => replace(TYPE_ALIAS_ALREADY_DEFINED.clone(), &[&a]),
-            OperatorNotSupported(a, b) => replace(OPERATOR_NOT_SUPPORTED.clone(), &[&a, &b]),
-            TypeAnnotationNeeded => TYPE_ANNOTATION_NEEDED.clone(),
-            CannotAssignToImmutableVariable(a) => {
-                replace(CANNOT_ASSIGN_TO_IMMUTABLE_VARIABLE.clone(), &[&a])
-            }
-        };
+    #[test]
+    fn test_color_never() {
+        let cli = Cli::parse_from(["sntkc", "main.sn", "--color", "never"]);
 
-        Self {
-            error: message,
-            position: error.position,
-        }
+        assert_eq!(cli.color(), ColorChoice::Never);
+        assert_eq!(error().render(cli.color().enabled()), error().to_string());
+        assert!(!error().render(false).contains('\x1b'));
     }
-}
 
-impl From<EvaluateError> for Error {
-    fn from(error: EvaluateError) -> Self {
-        use evaluator::EvaluateErrorKind::*;
+    #[test]
+    fn test_color_always() {
+        let cli = Cli::parse_from(["sntkc", "main.sn", "--color", "always"]);
+
+        assert!(cli.color().enabled());
+        assert!(error()
+            .render(true)
+            .contains("\x1b[1;31mError[1003]\x1b[0m"));
+        assert_eq!(
+            Cli::parse_from(["sntkc", "main.sn", "--color", "always", "--no-color"]).color(),
+            ColorChoice::Never
+        );
+    }
 
-        let message = match error.kind {
-            IdentifierAlreadyDefined(a) => replace(IDENTIFIER_ALREADY_DEFINED.clone(), &[&a]),
-            IdentifierNotDefined(a) => replace(IDENTIFIER_NOT_DEFINED.clone(), &[&a]),
-            InvalidOperator1(a, b) => replace(INVALID_OPERATOR1.clone(), &[&a, &b]),
-            InvalidOperator2(a, b, c) => replace(INVALID_OPERATOR2.clone(), &[&a, &b, &c]),
-        };
+    #[test]
+    fn test_suggestion() {
+        let program = parser::Parser::from("let x: string = 1;")
+            .parse_program()
+            .unwrap();
+        let error = Error::from(Analyzer::new(program).analyze().unwrap_err());
 
-        Self {
-            error: message,
-            position: error.position,
-        }
+        assert!(error
+            .to_string()
+            .ends_with("\nSuggestion: convert the value with `as string`"));
     }
-}
 
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let Error {
-            error:
-                ErrorInfo {
-                    message,
-                    code,
-                    help,
-                },
-            position,
-        } = self;
-        let help = match help {
-            Some(help) => format!("\nHelp: {}", help),
-            None => String::new(),
+    #[test]
+    fn test_emit_tokens() {
+        assert!(Cli::parse_from(["sntkc", "main.sn", "--emit-tokens"]).emit_tokens);
+        assert_eq!(
+            dump_tokens("let x = \"a\";").unwrap(),
+            "1:1 Let\n1:5 IDENT(\"x\")\n1:7 Assign\n1:9 String(\"a\")\n1:12 Semicolon\n"
+        );
+        assert_eq!(
+            Error::from(dump_tokens("let x = 1 @").unwrap_err()).to_string(),
+            "Error[1006] at 1:11: Illegal character `@`"
+        );
+    }
+
+    #[test]
+    fn test_deny_warnings() {
+        let report = |source, deny_warnings| {
+            let program = parser::Parser::from(source).parse_program().unwrap();
+            let mut analyzer = Analyzer::new(program);
+            let errors = analyzer.analyze_program().err().unwrap_or_default();
+
+            analysis_report(analyzer.warnings, errors, deny_warnings, false)
         };
 
-        write!(f, "Error[{code}] at {position}: {message}{help}",)
+        assert!(Cli::parse_from(["sntkc", "main.sn", "--deny-warnings"]).deny_warnings);
+        assert_eq!(report("let _ = 1;", false).1, None);
+        assert_eq!(report("let _ = 1;", true).1, Some(1));
+        assert_eq!(report("let x = 1;", true).1, None);
+
+        // The errors are reported before the warnings stop the program.
+        let (lines, exit_code) = report("let _ = 1;\nlet x: string = 1;", true);
+        assert_eq!(exit_code, Some(1));
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("Warning[") && lines[1].starts_with("Error["));
     }
-}
 
-fn main() {
-    let args = arguments::Cli::parse();
-    let content = fs::read_to_string(args.source).unwrap();
+    #[test]
+    fn test_dangling_statement() {
+        let source = "if (true) return 1;\n    2;";
+        let mut parser = parser::Parser::from(source);
+        parser.parse_program().unwrap();
 
-    let lexer = Lexer::new(content.as_str());
-    let mut parser = Parser::new(lexer);
+        let warnings = parser_warnings(&parser)
+            .into_iter()
+            .map(Warning::from)
+            .collect::<Vec<_>>();
 
-    match parser.parse_program() {
-        Ok(ast) => {
-            // println!("AST: {:#?}", ast);
-            match Analyzer::new(ast.clone()).analyze() {
-                Ok(ret) => {
-                    println!("Analyzed return type: {ret:?}");
-                    if args.eval {
-                        if let Err(error) = Evaluator::new(ast).evaluate() {
-                            println!("{}", Error::from(error));
-                        }
-                    }
-                }
-                Err(error) => println!("{}", Error::from(error)),
-            }
-        }
-        Err(errors) => {
-            for error in errors {
-                println!("{}", Error::from(error));
-            }
-        }
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].warning.code, 2104);
+    }
+
+    #[test]
+    fn test_defines() {
+        assert_eq!(
+            Cli::parse_from(["sntkc", "main.sn", "--define", "DEBUG", "-D", "WASM"]).defines,
+            vec!["DEBUG", "WASM"]
+        );
+        assert!(Cli::parse_from(["sntkc", "main.sn"]).defines.is_empty());
     }
 }
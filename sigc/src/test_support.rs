@@ -0,0 +1,125 @@
+//! End-to-end test helpers: a program is parsed, analyzed and evaluated in one go.
+
+use crate::evaluator::{object::Object, Evaluator};
+use analyzer::analyzer::{Analyzer, AnalyzerReturnKind};
+use parser::Parser;
+
+/// Runs the whole pipeline on the source and returns the analyzed return type and the evaluated value.
+/// Panics with the failing stage if any of them reports an error.
+pub fn run(source: &str) -> (AnalyzerReturnKind, Object) {
+    let program = Parser::from(source)
+        .parse_program()
+        .unwrap_or_else(|errors| panic!("parsing failed: {errors:?}\nsource: {source}"));
+
+    let return_type = Analyzer::new(program.clone())
+        .analyze()
+        .unwrap_or_else(|error| panic!("analysis failed: {error:?}\nsource: {source}"));
+
+    let value = Evaluator::new(program)
+        .evaluate()
+        .unwrap_or_else(|error| panic!("evaluation failed: {error:?}\nsource: {source}"));
+
+    (return_type, value)
+}
+
+/// Asserts that the program type checks as `return_type` and evaluates to `value`.
+pub fn assert_program(source: &str, return_type: AnalyzerReturnKind, value: Object) {
+    assert_eq!(run(source), (return_type, value), "source: {source}");
+}
+
+mod tests {
+    use super::*;
+    use parser::ast::DataTypeKind;
+
+    #[test]
+    fn test_arithmetic() {
+        assert_program(
+            "1 + 2 * 3 ** 2",
+            AnalyzerReturnKind::Expression(DataTypeKind::Number),
+            Object::Number(19.0),
+        );
+    }
+
+    #[test]
+    fn test_function_call() {
+        assert_program(
+            r#"
+            let add = fn(a: number, b: number) -> number { a + b };
+            add(1, 2)
+            "#,
+            AnalyzerReturnKind::Expression(DataTypeKind::Number),
+            Object::Number(3.0),
+        );
+    }
+
+    #[test]
+    fn test_early_return() {
+        assert_program(
+            r#"
+            let x = "a";
+            return x;
+            "#,
+            AnalyzerReturnKind::EarlyReturn(DataTypeKind::String),
+            Object::String(String::from("a")),
+        );
+    }
+
+    #[test]
+    fn test_nested_function_capture() {
+        assert_program(
+            r#"
+            let outer = fn(x: number) -> fn() -> number {
+                return fn() -> number { return x; };
+            };
+            let inner = outer(5);
+            outer(1)() + inner()
+            "#,
+            AnalyzerReturnKind::Expression(DataTypeKind::Number),
+            Object::Number(6.0),
+        );
+    }
+
+    #[test]
+    fn test_if_expression() {
+        assert_program(
+            r#"
+            let sign = fn(n: number) -> string {
+                if n > 0 { "+" } else if n < 0 { "-" } else { "0" }
+            };
+            sign(2) + sign(-2) + sign(0)
+            "#,
+            AnalyzerReturnKind::Expression(DataTypeKind::String),
+            Object::String(String::from("+-0")),
+        );
+    }
+
+    #[test]
+    fn test_recursive_function() {
+        assert_program(
+            r#"
+            let fib = fn(n: number) -> number {
+                if (n <= 1) {
+                    return n;
+                } else {
+                    return fib(n - 1) + fib(n - 2);
+                };
+            };
+            fib(10)
+            "#,
+            AnalyzerReturnKind::Expression(DataTypeKind::Number),
+            Object::Number(55.0),
+        );
+    }
+
+    #[test]
+    fn test_declared_builtin() {
+        assert_program(
+            r#"
+            declare sort = fn(number[]) -> number[];
+            sort([3, 1, 2])[0]
+            "#,
+            AnalyzerReturnKind::Expression(DataTypeKind::Number),
+            Object::Number(1.0),
+        );
+    }
+}